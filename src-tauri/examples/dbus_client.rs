@@ -0,0 +1,199 @@
+// Test client for the D-Bus service (see `dbus`). It adds a throwaway prompt
+// with `prompt-buddy add`, so run it against an app with a scratch data dir on
+// a private bus. After `cargo build`:
+//
+//     export XDG_DATA_HOME=$(mktemp -d)
+//     dbus-run-session -- sh -c 'target/debug/prompt-buddy & sleep 5;
+//         cargo run --example dbus_client [-- [--inject] [<path to prompt-buddy>]]; kill $!'
+//
+// It checks the bus name, every method and the `PromptsUpdated` signal, and
+// exits with 1 if anything is off. `--inject` also types the throwaway prompt
+// into the focused window.
+
+#[cfg(target_os = "linux")]
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let inject = args.iter().any(|arg| arg == "--inject");
+    let binary = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(client::default_binary);
+    match client::run(&binary, inject) {
+        Ok(0) => println!("✅ All checks passed"),
+        Ok(failed) => {
+            println!("❌ {} checks failed", failed);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            println!("❌ {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn main() {
+    println!("ℹ️  The D-Bus service is only available on Linux");
+}
+
+#[cfg(target_os = "linux")]
+mod client {
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Stdio};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use zbus::blocking::{Connection, MessageIterator};
+    use zbus::MatchRule;
+
+    const BUS_NAME: &str = "org.promptbuddy.PromptBuddy";
+    const OBJECT_PATH: &str = "/org/promptbuddy/PromptBuddy";
+    const INTERFACE: &str = "org.promptbuddy.PromptBuddy";
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    // target/<profile>/prompt-buddy, next to target/<profile>/examples.
+    pub fn default_binary() -> PathBuf {
+        let exe = std::env::current_exe().unwrap_or_default();
+        let profile_dir = exe.parent().and_then(|dir| dir.parent());
+        profile_dir.unwrap_or(&exe).join("prompt-buddy")
+    }
+
+    struct Checks {
+        failed: usize,
+    }
+
+    impl Checks {
+        fn check(&mut self, name: &str, passed: bool, detail: impl std::fmt::Debug) {
+            if passed {
+                println!("✅ {}", name);
+            } else {
+                println!("❌ {}: {:?}", name, detail);
+                self.failed += 1;
+            }
+        }
+    }
+
+    fn call<B, R>(connection: &Connection, method: &str, body: &B) -> zbus::Result<R>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+        R: for<'d> zbus::zvariant::DynamicDeserialize<'d>,
+    {
+        connection
+            .call_method(Some(BUS_NAME), OBJECT_PATH, Some(INTERFACE), method, body)?
+            .body()
+            .deserialize()
+    }
+
+    fn add_prompt(binary: &Path, title: &str) -> Result<(), String> {
+        let mut child = Command::new(binary)
+            .args(["add", "--title", title])
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", binary.display(), e))?;
+        child
+            .stdin
+            .take()
+            .ok_or("No stdin")?
+            .write_all(b"Written by dbus_client")
+            .map_err(|e| format!("Failed to write the prompt: {}", e))?;
+        let status = child.wait().map_err(|e| e.to_string())?;
+        if !status.success() {
+            return Err(format!("prompt-buddy add failed with {}", status));
+        }
+        Ok(())
+    }
+
+    // Runs all checks and returns how many failed.
+    pub fn run(binary: &Path, inject: bool) -> Result<usize, String> {
+        let connection = Connection::session().map_err(|e| format!("No session bus: {}", e))?;
+        let mut checks = Checks { failed: 0 };
+
+        let owned: zbus::Result<bool> = connection
+            .call_method(
+                Some("org.freedesktop.DBus"),
+                "/org/freedesktop/DBus",
+                Some("org.freedesktop.DBus"),
+                "NameHasOwner",
+                &(BUS_NAME,),
+            )
+            .and_then(|reply| reply.body().deserialize());
+        checks.check(
+            "bus name is owned",
+            owned.as_ref().is_ok_and(|o| *o),
+            &owned,
+        );
+
+        let before: zbus::Result<Vec<(String, String)>> = call(&connection, "ListPrompts", &());
+        checks.check("ListPrompts", before.is_ok(), &before);
+
+        // Listen before changing anything, so the signal can't be missed.
+        let rule = MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .interface(INTERFACE)
+            .and_then(|rule| rule.member("PromptsUpdated"))
+            .map_err(|e| e.to_string())?
+            .build();
+        let signals = MessageIterator::for_match_rule(rule, &connection, None)
+            .map_err(|e| format!("Failed to subscribe: {}", e))?;
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for message in signals.flatten() {
+                if sender.send(message.body().deserialize::<u32>()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let title = format!("dbusclient {}", token);
+        add_prompt(binary, &title)?;
+        let count = receiver.recv_timeout(TIMEOUT);
+        checks.check(
+            "PromptsUpdated signal",
+            matches!(&count, Ok(Ok(count)) if before.as_ref().is_ok_and(|b| *count as usize == b.len() + 1)),
+            &count,
+        );
+
+        let prompts: zbus::Result<Vec<(String, String)>> = call(&connection, "ListPrompts", &());
+        let id = prompts
+            .as_ref()
+            .ok()
+            .and_then(|prompts| prompts.iter().find(|(_, t)| *t == title))
+            .map(|(id, _)| id.clone());
+        checks.check("ListPrompts has the new prompt", id.is_some(), &prompts);
+
+        let hits: zbus::Result<Vec<(String, String)>> = call(&connection, "Search", &(&token,));
+        checks.check(
+            "Search",
+            hits.as_ref()
+                .is_ok_and(|hits| hits.iter().any(|(_, t)| *t == title)),
+            &hits,
+        );
+
+        let missing: zbus::Result<String> = call(&connection, "Inject", &("no such prompt",));
+        checks.check(
+            "Inject of an unknown prompt fails",
+            matches!(&missing, Err(zbus::Error::MethodError(name, _, _))
+                if name.as_str() == "org.freedesktop.DBus.Error.Failed"),
+            &missing,
+        );
+
+        if inject {
+            let injected: zbus::Result<String> =
+                call(&connection, "Inject", &(id.unwrap_or_default(),));
+            checks.check("Inject", injected.is_ok(), &injected);
+        }
+
+        for attempt in ["Toggle shows the bar", "Toggle hides it again"] {
+            let toggled: zbus::Result<()> = call(&connection, "Toggle", &());
+            checks.check(attempt, toggled.is_ok(), &toggled);
+        }
+
+        let unknown: zbus::Result<()> = call(&connection, "Nope", &());
+        checks.check("unknown method fails", unknown.is_err(), &unknown);
+
+        Ok(checks.failed)
+    }
+}
@@ -0,0 +1,351 @@
+// Integration test client for the JSON-RPC socket (see `ipc`). It creates and
+// deletes a throwaway prompt, so run it against an app with a scratch data dir
+// (on macOS, point `HOME` at one instead). After `cargo build`:
+//
+//     export XDG_DATA_HOME=$(mktemp -d)
+//     sh -c 'target/debug/prompt-buddy & sleep 5;
+//         cargo run --example rpc_client [-- --inject]; kill $!'
+//
+// It goes through every method, checks the protocol errors and the
+// `promptsUpdated` notification, and exits with 1 if anything is off.
+// `--inject` also types the throwaway prompt into the focused window and waits
+// for `injectionResult`.
+
+#[cfg(unix)]
+fn main() {
+    // Without a scratch data dir this would talk to the real app and leave
+    // undo and trash entries in the real library.
+    if cfg!(target_os = "linux") && std::env::var_os("XDG_DATA_HOME").is_none() {
+        println!("❌ Set XDG_DATA_HOME to a scratch directory first");
+        std::process::exit(1);
+    }
+    let inject = std::env::args().any(|arg| arg == "--inject");
+    match client::run(inject) {
+        Ok(0) => println!("✅ All checks passed"),
+        Ok(failed) => {
+            println!("❌ {} checks failed", failed);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            println!("❌ {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    println!("ℹ️  The JSON-RPC socket is not available on this platform");
+}
+
+#[cfg(unix)]
+mod client {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    use prompt_picker_lib::{cli, ipc};
+    use serde_json::{json, Value};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    struct Client {
+        reader: BufReader<UnixStream>,
+        writer: UnixStream,
+        next_id: u64,
+        // Notifications read while waiting for an answer.
+        notifications: Vec<Value>,
+    }
+
+    impl Client {
+        fn connect() -> Result<Self, String> {
+            let path = ipc::socket_path(&cli::data_dir()?);
+            let stream = UnixStream::connect(&path)
+                .map_err(|e| format!("Failed to connect to {}: {}", path.display(), e))?;
+            stream
+                .set_read_timeout(Some(TIMEOUT))
+                .map_err(|e| format!("Failed to set a timeout: {}", e))?;
+            let writer = stream
+                .try_clone()
+                .map_err(|e| format!("Failed to clone the socket: {}", e))?;
+            Ok(Client {
+                reader: BufReader::new(stream),
+                writer,
+                next_id: 1,
+                notifications: Vec::new(),
+            })
+        }
+
+        fn send(&mut self, line: &str) -> Result<(), String> {
+            self.writer
+                .write_all(format!("{}\n", line).as_bytes())
+                .map_err(|e| format!("Failed to send: {}", e))
+        }
+
+        fn read(&mut self) -> Result<Value, String> {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => Err("The app closed the connection".to_string()),
+                Ok(_) => serde_json::from_str(&line).map_err(|e| format!("Invalid JSON: {}", e)),
+                Err(e) => Err(format!("No answer: {}", e)),
+            }
+        }
+
+        // The next message that isn't a notification.
+        fn answer(&mut self) -> Result<Value, String> {
+            loop {
+                let message = self.read()?;
+                if message.get("method").is_some() && message.get("id").is_none() {
+                    self.notifications.push(message);
+                } else {
+                    return Ok(message);
+                }
+            }
+        }
+
+        fn raw(&mut self, line: &str) -> Result<Value, String> {
+            self.send(line)?;
+            self.answer()
+        }
+
+        // The result of `method`, or the error code and message.
+        fn call(
+            &mut self,
+            method: &str,
+            params: Value,
+        ) -> Result<Result<Value, (i64, String)>, String> {
+            let id = self.next_id;
+            self.next_id += 1;
+            let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+            let response = self.raw(&request.to_string())?;
+            if response["id"] != json!(id) {
+                return Err(format!("Answer to {} has id {}", method, response["id"]));
+            }
+            Ok(match response.get("error") {
+                Some(error) => Err((
+                    error["code"].as_i64().unwrap_or_default(),
+                    error["message"].as_str().unwrap_or_default().to_string(),
+                )),
+                None => Ok(response.get("result").cloned().unwrap_or(Value::Null)),
+            })
+        }
+
+        fn notification(&mut self, method: &str) -> Result<Value, String> {
+            loop {
+                if let Some(index) = self
+                    .notifications
+                    .iter()
+                    .position(|n| n["method"] == method)
+                {
+                    return Ok(self.notifications.remove(index));
+                }
+                let message = self.read()?;
+                self.notifications.push(message);
+            }
+        }
+    }
+
+    struct Checks {
+        failed: usize,
+    }
+
+    impl Checks {
+        fn check(&mut self, name: &str, passed: bool, detail: impl std::fmt::Debug) {
+            if passed {
+                println!("✅ {}", name);
+            } else {
+                println!("❌ {}: {:?}", name, detail);
+                self.failed += 1;
+            }
+        }
+    }
+
+    fn error_code(result: &Result<Value, (i64, String)>) -> Option<i64> {
+        result.as_ref().err().map(|(code, _)| *code)
+    }
+
+    // Runs all checks and returns how many failed.
+    pub fn run(inject: bool) -> Result<usize, String> {
+        let mut client = Client::connect()?;
+        let mut checks = Checks { failed: 0 };
+
+        let result = client.call("events.subscribe", json!({}))?;
+        checks.check("events.subscribe", result.is_ok(), &result);
+
+        let result = client.call("prompts.list", json!({}))?;
+        checks.check(
+            "prompts.list",
+            result.as_ref().is_ok_and(Value::is_array),
+            &result,
+        );
+
+        let title = format!("rpc_client {}", uuid::Uuid::new_v4());
+        let result = client.call(
+            "prompts.create",
+            json!({
+                "title": title,
+                "content": "Hello {{who}} from {{lang|Rust}}",
+                "tags": ["rpc-test"],
+            }),
+        )?;
+        let Ok(created) = result else {
+            checks.check("prompts.create", false, &result);
+            return Ok(checks.failed);
+        };
+        checks.check("prompts.create", created["title"] == json!(title), &created);
+        let id = created["id"].as_str().unwrap_or_default().to_string();
+
+        let updated = client.notification("promptsUpdated");
+        checks.check(
+            "promptsUpdated notification",
+            updated
+                .as_ref()
+                .is_ok_and(|n| n["params"]["count"].is_u64()),
+            &updated,
+        );
+
+        let result = client.call("prompts.get", json!({ "prompt": id }))?;
+        checks.check(
+            "prompts.get by id",
+            result.as_ref().is_ok_and(|p| p["id"] == json!(id)),
+            &result,
+        );
+        let result = client.call("prompts.get", json!({ "prompt": title.to_uppercase() }))?;
+        checks.check(
+            "prompts.get by title",
+            result.as_ref().is_ok_and(|p| p["id"] == json!(id)),
+            &result,
+        );
+
+        let result = client.call(
+            "prompts.render",
+            json!({ "prompt": id, "vars": { "who": "tests" } }),
+        )?;
+        checks.check(
+            "prompts.render",
+            result
+                .as_ref()
+                .is_ok_and(|text| text == "Hello tests from Rust"),
+            &result,
+        );
+        let result = client.call("prompts.render", json!({ "prompt": id }))?;
+        checks.check(
+            "prompts.render without a value",
+            error_code(&result) == Some(ipc::METHOD_FAILED),
+            &result,
+        );
+
+        let result = client.call(
+            "prompts.update",
+            json!({ "prompt": id, "content": "Bye {{who}}", "folder": "rpc-test/nested" }),
+        )?;
+        checks.check(
+            "prompts.update",
+            result.as_ref().is_ok_and(|p| {
+                p["content"] == "Bye {{who}}"
+                    && p["folder"] == "rpc-test/nested"
+                    && p["title"] == json!(title)
+            }),
+            &result,
+        );
+
+        let result = client.call("shortcuts.status", json!({}))?;
+        checks.check(
+            "shortcuts.status",
+            result.as_ref().is_ok_and(|s| s["paused"].is_boolean()),
+            &result,
+        );
+
+        let result = client.call("focus.targets", json!(null))?;
+        checks.check(
+            "focus.targets",
+            result.as_ref().is_ok_and(Value::is_object),
+            &result,
+        );
+
+        if inject {
+            let result = client.call(
+                "prompts.inject",
+                json!({ "prompt": id, "vars": { "who": "rpc_client" } }),
+            )?;
+            checks.check("prompts.inject", result.is_ok(), &result);
+            let injected = client.notification("injectionResult");
+            checks.check(
+                "injectionResult notification",
+                injected
+                    .as_ref()
+                    .is_ok_and(|n| n["params"]["ok"] == json!(result.is_ok())),
+                &injected,
+            );
+        }
+
+        let result = client.call("prompts.nope", json!({}))?;
+        checks.check(
+            "unknown method",
+            error_code(&result) == Some(ipc::METHOD_NOT_FOUND),
+            &result,
+        );
+        let result = client.call("prompts.get", json!({}))?;
+        checks.check(
+            "missing params",
+            error_code(&result) == Some(ipc::INVALID_PARAMS),
+            &result,
+        );
+        let result = client.call("prompts.get", json!(["positional"]))?;
+        checks.check(
+            "positional params",
+            error_code(&result) == Some(ipc::INVALID_PARAMS),
+            &result,
+        );
+
+        let answer = client.raw("{not json")?;
+        checks.check(
+            "parse error",
+            answer["error"]["code"] == json!(ipc::PARSE_ERROR),
+            &answer,
+        );
+        let answer = client.raw(r#"{"id": 99, "method": "prompts.list"}"#)?;
+        checks.check(
+            "missing jsonrpc version",
+            answer["error"]["code"] == json!(ipc::INVALID_REQUEST) && answer["id"] == json!(99),
+            &answer,
+        );
+        let answer = client.raw("[]")?;
+        checks.check(
+            "empty batch",
+            answer["error"]["code"] == json!(ipc::INVALID_REQUEST),
+            &answer,
+        );
+        let answer = client.raw(
+            r#"[{"jsonrpc": "2.0", "id": "a", "method": "prompts.list"},
+                {"jsonrpc": "2.0", "method": "prompts.list"},
+                {"jsonrpc": "2.0", "id": "b", "method": "prompts.nope"}]"#
+                .replace('\n', "")
+                .as_str(),
+        )?;
+        checks.check(
+            "batch",
+            answer.as_array().is_some_and(|answers| {
+                answers.len() == 2
+                    && answers[0]["id"] == "a"
+                    && answers[0]["result"].is_array()
+                    && answers[1]["error"]["code"] == json!(ipc::METHOD_NOT_FOUND)
+            }),
+            &answer,
+        );
+
+        let result = client.call("prompts.delete", json!({ "prompt": id }))?;
+        checks.check("prompts.delete", result.is_ok(), &result);
+        let result = client.call("prompts.get", json!({ "prompt": id }))?;
+        checks.check(
+            "deleted prompt is gone",
+            error_code(&result) == Some(ipc::METHOD_FAILED),
+            &result,
+        );
+
+        let result = client.call("events.unsubscribe", json!({}))?;
+        checks.check("events.unsubscribe", result.is_ok(), &result);
+
+        Ok(checks.failed)
+    }
+}
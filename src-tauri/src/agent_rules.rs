@@ -0,0 +1,426 @@
+// Publishes prompts into a project as instructions for coding agents:
+// - `cursor`: one `.cursor/rules/<title>.mdc` rule per prompt, with front
+//   matter for its description, globs and `alwaysApply`;
+// - `agents`: a section of `AGENTS.md`;
+// - `copilot`: a block of `.github/copilot-instructions.md`.
+//
+// Publishing again is idempotent. Rule files carry the id of their prompt, so a
+// renamed prompt replaces its old file; sections live between `SECTION_START`
+// and `SECTION_END`, and only what is between them is rewritten.
+//
+// Agents read the files as they are, so prompts are published rendered, with
+// every variable at its default. Prompts that would need someone to fill in a
+// variable or approve a `{{sh:}}` command are refused instead.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::backups;
+use crate::library;
+use crate::markdown_store;
+use crate::prompts::Prompt;
+use crate::template;
+
+const RULE_MARKER: &str = "<!-- prompt-buddy:";
+const SECTION_START: &str = "<!-- prompt-buddy:start -->";
+const SECTION_END: &str = "<!-- prompt-buddy:end -->";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleTarget {
+    Cursor,
+    Agents,
+    Copilot,
+}
+
+pub fn write_cursor_rules(
+    project: &Path,
+    prompts: &[Prompt],
+    globs: &str,
+    always_apply: bool,
+) -> Result<Vec<PathBuf>, String> {
+    let prompts = rendered(prompts)?;
+    let dir = project.join(".cursor").join("rules");
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    // Rule files written earlier, by prompt id.
+    let existing: Vec<(String, PathBuf)> = fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "mdc"))
+        .filter_map(|path| {
+            let raw = fs::read_to_string(&path).ok()?;
+            let id = raw.lines().find_map(rule_id)?;
+            Some((id.to_string(), path))
+        })
+        .collect();
+
+    let mut written = Vec::new();
+    for prompt in &prompts {
+        for (_, path) in existing.iter().filter(|(id, _)| *id == prompt.id) {
+            fs::remove_file(path)
+                .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))?;
+        }
+        let mut path = dir.join(format!("{}.mdc", markdown_store::slugify(&prompt.title)));
+        let mut n = 2;
+        while path.exists() {
+            path = dir.join(format!("{}-{}.mdc", markdown_store::slugify(&prompt.title), n));
+            n += 1;
+        }
+        backups::write_atomic(&path, render_rule(prompt, globs, always_apply).as_bytes())?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+// `prompts` with their variables filled in with the defaults. Fails, naming
+// every prompt in the way, if any has a command or a variable without one.
+fn rendered(prompts: &[Prompt]) -> Result<Vec<Prompt>, String> {
+    let mut problems = Vec::new();
+    let mut rendered = Vec::new();
+    for prompt in prompts {
+        if !template::commands(&prompt.content).is_empty() {
+            problems.push(format!("“{}” runs shell commands", prompt.title));
+            continue;
+        }
+        let required: Vec<String> = template::variables(&prompt.content)
+            .into_iter()
+            .filter(|v| v.default.is_none())
+            .map(|v| v.name)
+            .collect();
+        if !required.is_empty() {
+            problems.push(format!(
+                "“{}” needs a value for {}",
+                prompt.title,
+                required.join(", ")
+            ));
+            continue;
+        }
+        let content = template::render(&prompt.content, &HashMap::new())
+            .map_err(|e| format!("Failed to render “{}”: {}", prompt.title, e))?;
+        rendered.push(Prompt {
+            content,
+            ..prompt.clone()
+        });
+    }
+    if !problems.is_empty() {
+        return Err(format!(
+            "Can't publish prompts that need input: {}. Give their variables defaults or leave them out.",
+            problems.join("; ")
+        ));
+    }
+    Ok(rendered)
+}
+
+// Id in a `<!-- prompt-buddy: <id> -->` line.
+fn rule_id(line: &str) -> Option<&str> {
+    let id = line.trim().strip_prefix(RULE_MARKER)?.strip_suffix("-->")?.trim();
+    (!id.is_empty() && id != "start" && id != "end").then_some(id)
+}
+
+fn render_rule(prompt: &Prompt, globs: &str, always_apply: bool) -> String {
+    // Cursor reads these fields verbatim rather than as YAML, so no quoting.
+    let description = prompt.title.replace('\n', " ");
+    format!(
+        "---\ndescription: {}\nglobs: {}\nalwaysApply: {}\n---\n{} {} -->\n\n{}\n",
+        description,
+        globs.trim(),
+        always_apply,
+        RULE_MARKER,
+        prompt.id,
+        prompt.content.trim_end()
+    )
+}
+
+// Writes `prompts` into the managed section of `path`, creating the file (and
+// its directory) if needed. Text outside the section is left as it is.
+pub fn write_section(path: &Path, prompts: &[Prompt]) -> Result<(), String> {
+    let existing = match fs::read_to_string(path) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let section = render_section(&rendered(prompts)?);
+
+    let contents = match (existing.find(SECTION_START), existing.find(SECTION_END)) {
+        (Some(start), Some(end)) if start < end => format!(
+            "{}{}{}",
+            &existing[..start],
+            section,
+            &existing[end + SECTION_END.len()..]
+        ),
+        (Some(_), _) | (_, Some(_)) => {
+            return Err(format!(
+                "{} has an unmatched Prompt Buddy marker; fix or remove it first",
+                path.display()
+            ))
+        }
+        (None, None) if existing.trim().is_empty() => format!("{}\n", section),
+        (None, None) => format!("{}\n\n{}\n", existing.trim_end(), section),
+    };
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    backups::write_atomic(path, contents.as_bytes())
+}
+
+fn render_section(prompts: &[Prompt]) -> String {
+    let mut section = format!(
+        "{}\n<!-- Generated by Prompt Buddy. Edits between these markers are overwritten. -->\n",
+        SECTION_START
+    );
+    for prompt in prompts {
+        section.push_str(&format!("\n## {}\n\n{}\n", prompt.title, prompt.content.trim_end()));
+    }
+    section.push_str(SECTION_END);
+    section
+}
+
+// Publishes the prompts in `ids` (in library order) into `project` and returns
+// the files written. `globs` and `always_apply` only apply to Cursor rules.
+#[tauri::command]
+pub async fn export_agent_rules(
+    target: RuleTarget,
+    project: String,
+    ids: Vec<String>,
+    globs: Option<String>,
+    always_apply: Option<bool>,
+) -> Result<Vec<String>, String> {
+    let project = PathBuf::from(&project);
+    if !project.is_dir() {
+        return Err(format!("{} is not a directory", project.display()));
+    }
+    let prompts: Vec<Prompt> = library::prompts()
+        .into_iter()
+        .filter(|p| ids.contains(&p.id))
+        .collect();
+    if prompts.is_empty() {
+        return Err("No prompts selected".to_string());
+    }
+    println!("📝 Publishing {} prompts to {} ({:?})", prompts.len(), project.display(), target);
+
+    let written = match target {
+        RuleTarget::Cursor => write_cursor_rules(
+            &project,
+            &prompts,
+            globs.as_deref().unwrap_or(""),
+            always_apply.unwrap_or(false),
+        )?,
+        RuleTarget::Agents => {
+            let path = project.join("AGENTS.md");
+            write_section(&path, &prompts)?;
+            vec![path]
+        }
+        RuleTarget::Copilot => {
+            let path = project.join(".github").join("copilot-instructions.md");
+            write_section(&path, &prompts)?;
+            vec![path]
+        }
+    };
+    Ok(written.iter().map(|path| path.display().to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(id: &str, title: &str, content: &str) -> Prompt {
+        Prompt {
+            id: id.to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            color: crate::prompts::DEFAULT_COLOR.to_string(),
+            abbreviation: None,
+            tags: Vec::new(),
+            folder: None,
+        }
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("prompt-buddy-rules-{}", uuid::Uuid::new_v4()))
+    }
+
+    // The names of the files in `dir`, sorted.
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn writing_a_section_twice_changes_nothing() {
+        let dir = temp_dir();
+        let path = dir.join(".github").join("copilot-instructions.md");
+        let prompts = [prompt("a", "Style", "Use tabs.\n")];
+        write_section(&path, &prompts).unwrap();
+        let first = fs::read_to_string(&path).unwrap();
+        write_section(&path, &prompts).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), first);
+        assert!(first.starts_with(SECTION_START));
+        assert!(first.contains("\n## Style\n\nUse tabs.\n"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn only_the_section_is_rewritten() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("AGENTS.md");
+        fs::write(&path, "# Agents\n\nOur own notes.\n").unwrap();
+
+        write_section(&path, &[prompt("a", "Old", "Old rule")]).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("# Agents\n\nOur own notes.\n\n<!--"));
+        fs::write(&path, format!("{}\nMore notes below.\n", contents)).unwrap();
+
+        write_section(&path, &[prompt("a", "New", "New rule")]).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("# Agents\n\nOur own notes.\n\n<!--"));
+        assert!(contents.ends_with(&format!("{}\n\nMore notes below.\n", SECTION_END)));
+        assert!(contents.contains("## New") && !contents.contains("## Old"));
+        assert_eq!(contents.matches(SECTION_START).count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_unmatched_markers() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("AGENTS.md");
+        for contents in [
+            format!("Notes\n{}\n", SECTION_START),
+            format!("Notes\n{}\n", SECTION_END),
+            format!("{}\n{}\n", SECTION_END, SECTION_START),
+        ] {
+            fs::write(&path, &contents).unwrap();
+            assert!(write_section(&path, &[]).is_err());
+            assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn writing_cursor_rules_twice_changes_nothing() {
+        let project = temp_dir();
+        let prompts = [
+            prompt("a", "Code Style", "Use tabs."),
+            prompt("b", "Tests", "Write tests."),
+        ];
+        write_cursor_rules(&project, &prompts, "*.rs", true).unwrap();
+        let dir = project.join(".cursor").join("rules");
+        let first = fs::read_to_string(dir.join("code-style.mdc")).unwrap();
+        write_cursor_rules(&project, &prompts, "*.rs", true).unwrap();
+
+        assert_eq!(file_names(&dir), ["code-style.mdc", "tests.mdc"]);
+        assert_eq!(
+            fs::read_to_string(dir.join("code-style.mdc")).unwrap(),
+            first
+        );
+        assert_eq!(
+            first,
+            "---\ndescription: Code Style\nglobs: *.rs\nalwaysApply: true\n---\n<!-- prompt-buddy: a -->\n\nUse tabs.\n"
+        );
+        fs::remove_dir_all(&project).unwrap();
+    }
+
+    #[test]
+    fn a_renamed_prompt_replaces_its_rule() {
+        let project = temp_dir();
+        let dir = project.join(".cursor").join("rules");
+        fs::create_dir_all(&dir).unwrap();
+        // A rule of someone else's with the name the prompt will take.
+        fs::write(dir.join("style.mdc"), "Hand-written").unwrap();
+
+        write_cursor_rules(&project, &[prompt("a", "Code Style", "x")], "", false).unwrap();
+        let written =
+            write_cursor_rules(&project, &[prompt("a", "Style", "x")], "", false).unwrap();
+
+        assert_eq!(written, [dir.join("style-2.mdc")]);
+        assert_eq!(file_names(&dir), ["style-2.mdc", "style.mdc"]);
+        assert_eq!(
+            fs::read_to_string(dir.join("style.mdc")).unwrap(),
+            "Hand-written"
+        );
+        fs::remove_dir_all(&project).unwrap();
+    }
+
+    #[test]
+    fn publishes_variables_at_their_defaults() {
+        let project = temp_dir();
+        let prompts = [prompt(
+            "a",
+            "Style",
+            "Indent with {{indent|tabs}}, {{ indent }} only.",
+        )];
+        let path = project.join("AGENTS.md");
+        write_section(&path, &prompts).unwrap();
+        let section = fs::read_to_string(&path).unwrap();
+        assert!(
+            section.contains("\n## Style\n\nIndent with tabs, tabs only.\n"),
+            "{}",
+            section
+        );
+
+        write_cursor_rules(&project, &prompts, "", false).unwrap();
+        let rule =
+            fs::read_to_string(project.join(".cursor").join("rules").join("style.mdc")).unwrap();
+        assert!(
+            rule.ends_with("-->\n\nIndent with tabs, tabs only.\n"),
+            "{}",
+            rule
+        );
+        fs::remove_dir_all(&project).unwrap();
+    }
+
+    #[test]
+    fn refuses_prompts_that_need_input() {
+        let project = temp_dir();
+        fs::create_dir_all(&project).unwrap();
+        let prompts = [
+            prompt("a", "Fine", "Use {{indent|tabs}}."),
+            prompt(
+                "b",
+                "Branch",
+                "We are on {{sh: git branch --show-current}}.",
+            ),
+            prompt(
+                "c",
+                "Ask",
+                "Review {{file}} for {{goal|bugs}} and {{owner}}.",
+            ),
+        ];
+
+        let error = write_section(&project.join("AGENTS.md"), &prompts).unwrap_err();
+        assert!(error.contains("“Branch” runs shell commands"), "{}", error);
+        assert!(
+            error.contains("“Ask” needs a value for file, owner"),
+            "{}",
+            error
+        );
+        assert!(!error.contains("Fine"), "{}", error);
+        assert!(write_cursor_rules(&project, &prompts[1..2], "", false).is_err());
+        assert!(write_cursor_rules(&project, &prompts[2..], "", false).is_err());
+        assert_eq!(file_names(&project), Vec::<String>::new());
+        fs::remove_dir_all(&project).unwrap();
+    }
+
+    #[test]
+    fn reads_rule_ids() {
+        assert_eq!(rule_id("<!-- prompt-buddy: abc -->"), Some("abc"));
+        assert_eq!(rule_id("  <!-- prompt-buddy:abc-->  "), Some("abc"));
+        assert_eq!(rule_id(SECTION_START), None);
+        assert_eq!(rule_id("<!-- prompt-buddy: -->"), None);
+        assert_eq!(rule_id("<!-- other: abc -->"), None);
+    }
+}
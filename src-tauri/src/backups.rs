@@ -0,0 +1,312 @@
+// Crash safety for the prompt library: atomic file writes, and rolling
+// snapshots of the library in `<app data>/backups` that `restore_backup` can
+// bring back.
+//
+// Snapshots are plain JSON prompt lists independent of the storage backend,
+// taken of the library as it was *before* a write (at most one per
+// `SNAPSHOT_INTERVAL`, so a burst of edits doesn't evict older history) and
+// when the library is opened. The newest `MAX_SNAPSHOTS` are kept.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::prompts::Prompt;
+
+const SNAPSHOT_PREFIX: &str = "prompts-";
+const SNAPSHOT_SUFFIX: &str = ".json";
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const MAX_SNAPSHOTS: usize = 20;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Snapshot {
+    created_at: u64,
+    prompts: Vec<Prompt>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    name: String,
+    // Unix seconds.
+    created_at: u64,
+    prompt_count: usize,
+}
+
+// Writes `contents` to a temporary file next to `path`, flushes it to disk and
+// renames it over `path`, so a crash leaves either the old or the new file.
+// The temporary file is hidden, which keeps the Markdown watcher off it.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Invalid path: {}", path.display()))?;
+    let temp = path.with_file_name(format!(".{}.tmp", name));
+
+    let result = (|| {
+        let mut file = File::create(&temp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("Failed to write {}: {}", path.display(), e));
+    }
+    Ok(())
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Moves an unreadable file into the backup directory so that nothing
+// overwrites it, and returns where it went.
+pub fn quarantine(dir: &Path, path: &Path) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("library");
+    let target = dir.join(format!("{}.corrupt-{}", name, unix_now()));
+    fs::rename(path, &target)
+        .or_else(|_| fs::copy(path, &target).map(|_| ()))
+        .map_err(|e| format!("Failed to move {} aside: {}", path.display(), e))?;
+    Ok(target)
+}
+
+// Snapshots `prompts` unless the newest snapshot is recent enough (or `force`
+// is set), then prunes old ones.
+pub fn snapshot(dir: &Path, prompts: &[Prompt], force: bool) -> Result<(), String> {
+    if prompts.is_empty() {
+        return Ok(());
+    }
+    let now = unix_now();
+    if !force {
+        if let Some(newest) = list(dir).first() {
+            if now.saturating_sub(newest.created_at) < SNAPSHOT_INTERVAL.as_secs() {
+                return Ok(());
+            }
+        }
+    }
+
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let snapshot = Snapshot {
+        created_at: now,
+        prompts: prompts.to_vec(),
+    };
+    let contents = serde_json::to_vec_pretty(&snapshot)
+        .map_err(|e| format!("Failed to serialize backup: {}", e))?;
+
+    // Names carry milliseconds so they sort chronologically even when several
+    // snapshots are taken within one second.
+    let mut millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let mut path = dir.join(format!("{}{}{}", SNAPSHOT_PREFIX, millis, SNAPSHOT_SUFFIX));
+    while path.exists() {
+        millis += 1;
+        path = dir.join(format!("{}{}{}", SNAPSHOT_PREFIX, millis, SNAPSHOT_SUFFIX));
+    }
+    write_atomic(&path, &contents)?;
+    println!("🗄️  Backed up {} prompts to {}", prompts.len(), path.display());
+
+    for old in list(dir).into_iter().skip(MAX_SNAPSHOTS) {
+        if let Err(e) = fs::remove_file(dir.join(&old.name)) {
+            println!("⚠️  Failed to remove old backup {}: {}", old.name, e);
+        }
+    }
+    Ok(())
+}
+
+// Readable snapshots, newest first.
+pub fn list(dir: &Path) -> Vec<BackupInfo> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<(u128, BackupInfo)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            let millis = snapshot_millis(&name)?;
+            let snapshot = read_snapshot(&entry.path()).ok()?;
+            Some((
+                millis,
+                BackupInfo {
+                    name,
+                    created_at: snapshot.created_at,
+                    prompt_count: snapshot.prompts.len(),
+                },
+            ))
+        })
+        .collect();
+    backups.sort_by_key(|(millis, _)| std::cmp::Reverse(*millis));
+    backups.into_iter().map(|(_, info)| info).collect()
+}
+
+pub fn load(dir: &Path, name: &str) -> Result<Vec<Prompt>, String> {
+    // Names come from the frontend; never let them leave the backup directory.
+    if snapshot_millis(name).is_none() {
+        return Err(format!("Invalid backup name: {}", name));
+    }
+    read_snapshot(&dir.join(name)).map(|snapshot| snapshot.prompts)
+}
+
+// Timestamp of a `prompts-<unix millis>.json` file name.
+fn snapshot_millis(name: &str) -> Option<u128> {
+    name.strip_prefix(SNAPSHOT_PREFIX)?
+        .strip_suffix(SNAPSHOT_SUFFIX)?
+        .parse()
+        .ok()
+}
+
+fn read_snapshot(path: &Path) -> Result<Snapshot, String> {
+    let raw = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("Backup {} is corrupted: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("prompt-buddy-backups-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn prompt(title: &str) -> Prompt {
+        Prompt {
+            id: title.to_lowercase(),
+            title: title.to_string(),
+            content: format!("{} content", title),
+            color: crate::prompts::DEFAULT_COLOR.to_string(),
+            abbreviation: None,
+            tags: Vec::new(),
+            folder: None,
+        }
+    }
+
+    // Writes a snapshot file by hand, as if taken at `created_at`.
+    fn write_snapshot(dir: &Path, millis: u128, created_at: u64, prompts: &[Prompt]) {
+        fs::create_dir_all(dir).unwrap();
+        let snapshot = Snapshot {
+            created_at,
+            prompts: prompts.to_vec(),
+        };
+        fs::write(
+            dir.join(format!("{}{}{}", SNAPSHOT_PREFIX, millis, SNAPSHOT_SUFFIX)),
+            serde_json::to_vec(&snapshot).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn throttles_snapshots_unless_forced() {
+        let dir = temp_dir();
+        snapshot(&dir, &[prompt("A")], false).unwrap();
+        snapshot(&dir, &[prompt("B")], false).unwrap();
+        assert_eq!(list(&dir).len(), 1);
+        assert_eq!(load(&dir, &list(&dir)[0].name).unwrap(), [prompt("A")]);
+
+        snapshot(&dir, &[prompt("C")], true).unwrap();
+        let backups = list(&dir);
+        assert_eq!(backups.len(), 2);
+        assert_eq!(load(&dir, &backups[0].name).unwrap(), [prompt("C")]);
+
+        // Nothing to keep of an empty library.
+        snapshot(&dir, &[], true).unwrap();
+        assert_eq!(list(&dir).len(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn snapshots_again_once_the_interval_has_passed() {
+        let dir = temp_dir();
+        let old = unix_now() - SNAPSHOT_INTERVAL.as_secs() - 1;
+        write_snapshot(&dir, u128::from(old) * 1000, old, &[prompt("Old")]);
+
+        snapshot(&dir, &[prompt("New")], false).unwrap();
+        let backups = list(&dir);
+        assert_eq!(backups.len(), 2);
+        assert_eq!(load(&dir, &backups[0].name).unwrap(), [prompt("New")]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn keeps_the_newest_snapshots() {
+        let dir = temp_dir();
+        for i in 0..MAX_SNAPSHOTS + 5 {
+            snapshot(&dir, &[prompt(&format!("P{}", i))], true).unwrap();
+        }
+        let backups = list(&dir);
+        assert_eq!(backups.len(), MAX_SNAPSHOTS);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), MAX_SNAPSHOTS);
+        let newest = load(&dir, &backups[0].name).unwrap();
+        assert_eq!(newest, [prompt(&format!("P{}", MAX_SNAPSHOTS + 4))]);
+        let oldest = load(&dir, &backups[MAX_SNAPSHOTS - 1].name).unwrap();
+        assert_eq!(oldest, [prompt("P5")]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn lists_readable_snapshots_newest_first() {
+        let dir = temp_dir();
+        write_snapshot(&dir, 2_000, 2, &[prompt("A"), prompt("B")]);
+        write_snapshot(&dir, 10_000, 10, &[prompt("C")]);
+        write_snapshot(&dir, 900, 1, &[prompt("D")]);
+        fs::write(dir.join("prompts-5000.json"), "{ not json").unwrap();
+        fs::write(dir.join("notes.json"), "{}").unwrap();
+
+        let backups = list(&dir);
+        let names: Vec<&str> = backups.iter().map(|backup| backup.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "prompts-10000.json",
+                "prompts-2000.json",
+                "prompts-900.json"
+            ]
+        );
+        assert_eq!(backups[1].created_at, 2);
+        assert_eq!(backups[1].prompt_count, 2);
+        assert!(list(&dir.join("missing")).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn loads_only_snapshot_names() {
+        let dir = temp_dir();
+        let backups = dir.join("backups");
+        write_snapshot(&backups, 1_000, 1, &[prompt("A")]);
+        write_snapshot(&dir, 1_000, 1, &[prompt("Outside")]);
+        fs::write(dir.join("prompts.json"), "{}").unwrap();
+
+        assert_eq!(load(&backups, "prompts-1000.json").unwrap(), [prompt("A")]);
+        for name in [
+            "../prompts-1000.json",
+            "prompts-1000.json/../../prompts-1000.json",
+            "prompts-../prompts-1000.json",
+            "../prompts.json",
+            "/etc/passwd",
+            "prompts-.json",
+            "prompts-1000.txt",
+        ] {
+            let error = load(&backups, name).unwrap_err();
+            assert!(
+                error.starts_with("Invalid backup name"),
+                "{}: {}",
+                name,
+                error
+            );
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
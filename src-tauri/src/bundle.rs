@@ -0,0 +1,504 @@
+// Import and export of prompt bundles, for moving a library between machines
+// or sharing part of it:
+// - `json`: a versioned bundle file, `{"format": "prompt-buddy-bundle",
+//   "version": 1, "exportedAt": ..., "prompts": [...]}`;
+// - `markdown`: a directory with one `.md` file per prompt, in the format of
+//   the Markdown storage backend;
+// - `csv`: one row per prompt with `id,title,content,color,abbreviation,tags,folder`
+//   columns (only `content` is required; tags are comma-separated);
+// - `espanso`, `alfred`, `raycast`, `vscode`: import only, from other snippet
+//   tools (see `snippet_import`). What cannot be converted is listed in the
+//   preview's `issues`.
+//
+// Imports are merged into the library. A prompt collides with an existing one
+// that has the same id or, failing that, the same title; `MergeStrategy`
+// decides what happens then. `preview_import` reports what an import would do
+// without changing anything.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::backups;
+use crate::library::{self, PromptStore};
+use crate::markdown_store::MarkdownStore;
+use crate::prompts::{self, Prompt};
+use crate::snippet_import::{self, ImportIssue};
+
+const BUNDLE_FORMAT: &str = "prompt-buddy-bundle";
+const BUNDLE_VERSION: u64 = 1;
+
+const CSV_COLUMNS: &[&str] = &["id", "title", "content", "color", "abbreviation", "tags", "folder"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BundleFormat {
+    Json,
+    Markdown,
+    Csv,
+    Espanso,
+    Alfred,
+    Raycast,
+    Vscode,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeStrategy {
+    // Keep the existing prompt, drop the imported one.
+    Skip,
+    // Replace the existing prompt (in place) with the imported one.
+    Overwrite,
+    // Add the imported prompt as a new one next to the existing one, with a
+    // fresh id, and a numbered title if the title is taken.
+    KeepBoth,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Bundle {
+    format: String,
+    version: u64,
+    #[serde(default)]
+    exported_at: u64,
+    prompts: Vec<Prompt>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportChange {
+    // "add", "overwrite", "skip" or "keepBoth".
+    action: &'static str,
+    title: String,
+    // Title of the existing prompt it collided with, if any.
+    existing_title: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    added: usize,
+    overwritten: usize,
+    skipped: usize,
+    kept_both: usize,
+    changes: Vec<ImportChange>,
+    // Snippets, or parts of them, that could not be converted.
+    issues: Vec<ImportIssue>,
+}
+
+// The prompts in `path`, and what could not be converted from it.
+pub fn read(format: BundleFormat, path: &Path) -> Result<(Vec<Prompt>, Vec<ImportIssue>), String> {
+    let (prompts, issues) = match format {
+        BundleFormat::Json => (read_json(path)?, Vec::new()),
+        BundleFormat::Markdown => {
+            if !path.is_dir() {
+                return Err(format!("{} is not a directory", path.display()));
+            }
+            (MarkdownStore::open(path.to_path_buf())?.load()?, Vec::new())
+        }
+        BundleFormat::Csv => (read_csv(path)?, Vec::new()),
+        BundleFormat::Espanso => snippet_import::espanso(path)?.into_parts(),
+        BundleFormat::Alfred => snippet_import::alfred(path)?.into_parts(),
+        BundleFormat::Raycast => snippet_import::raycast(path)?.into_parts(),
+        BundleFormat::Vscode => snippet_import::vscode(path)?.into_parts(),
+    };
+    Ok((prompts.into_iter().map(clean).collect(), issues))
+}
+
+pub fn write(format: BundleFormat, path: &Path, prompts: &[Prompt]) -> Result<(), String> {
+    match format {
+        BundleFormat::Json => {
+            let bundle = Bundle {
+                format: BUNDLE_FORMAT.to_string(),
+                version: BUNDLE_VERSION,
+                exported_at: backups::unix_now(),
+                prompts: prompts.to_vec(),
+            };
+            let contents = serde_json::to_vec_pretty(&bundle)
+                .map_err(|e| format!("Failed to serialize bundle: {}", e))?;
+            backups::write_atomic(path, &contents)
+        }
+        BundleFormat::Markdown => {
+            // The Markdown store owns every prompt file in its directory, so
+            // never let an export loose on one that has other things in it.
+            let occupied = fs::read_dir(path)
+                .map(|mut entries| entries.next().is_some())
+                .unwrap_or(false);
+            if occupied {
+                return Err(format!(
+                    "{} is not empty; export Markdown into a new or empty directory",
+                    path.display()
+                ));
+            }
+            MarkdownStore::open(path.to_path_buf())?.save(prompts)
+        }
+        BundleFormat::Csv => write_csv(path, prompts),
+        other => Err(format!("{:?} is an import-only format", other)),
+    }
+}
+
+fn read_json(path: &Path) -> Result<Vec<Prompt>, String> {
+    let raw = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let bundle: Bundle = serde_json::from_str(&raw)
+        .map_err(|e| format!("{} is not a prompt bundle: {}", path.display(), e))?;
+    if bundle.format != BUNDLE_FORMAT {
+        return Err(format!("{} is not a prompt bundle", path.display()));
+    }
+    if bundle.version > BUNDLE_VERSION {
+        return Err(format!(
+            "{} is a v{} bundle, but this version of Prompt Buddy only understands up to v{}",
+            path.display(),
+            bundle.version,
+            BUNDLE_VERSION
+        ));
+    }
+    Ok(bundle.prompts)
+}
+
+fn read_csv(path: &Path) -> Result<Vec<Prompt>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+    let column = |name: &str| headers.iter().position(|header| header == name);
+    let content_column = column("content")
+        .ok_or_else(|| format!("{} has no 'content' column", path.display()))?;
+
+    let mut imported = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| format!("{}: row {}: {}", path.display(), i + 2, e))?;
+        let field = |name: &str| {
+            column(name)
+                .and_then(|index| record.get(index))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        imported.push(Prompt {
+            id: field("id").unwrap_or_default(),
+            title: field("title").unwrap_or_else(|| format!("Prompt {}", i + 1)),
+            content: record.get(content_column).unwrap_or_default().to_string(),
+            color: field("color").unwrap_or_else(|| prompts::DEFAULT_COLOR.to_string()),
+            abbreviation: field("abbreviation"),
+            tags: field("tags")
+                .map(|tags| tags.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            folder: field("folder"),
+        });
+    }
+    Ok(imported)
+}
+
+fn write_csv(path: &Path, prompts: &[Prompt]) -> Result<(), String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let rows = std::iter::once(CSV_COLUMNS.iter().map(|c| c.to_string()).collect::<Vec<_>>())
+        .chain(prompts.iter().map(|p| {
+            vec![
+                p.id.clone(),
+                p.title.clone(),
+                p.content.clone(),
+                p.color.clone(),
+                p.abbreviation.clone().unwrap_or_default(),
+                p.tags.join(", "),
+                p.folder.clone().unwrap_or_default(),
+            ]
+        }));
+    for row in rows {
+        writer
+            .write_record(&row)
+            .map_err(|e| format!("Failed to write CSV: {}", e))?;
+    }
+    let contents = writer
+        .into_inner()
+        .map_err(|e| format!("Failed to write CSV: {}", e))?;
+    backups::write_atomic(path, &contents)
+}
+
+// Fills in what an imported prompt may lack and normalizes the rest.
+fn clean(prompt: Prompt) -> Prompt {
+    Prompt {
+        id: if prompt.id.trim().is_empty() {
+            uuid::Uuid::new_v4().to_string()
+        } else {
+            prompt.id
+        },
+        folder: prompt.folder.as_deref().and_then(prompts::normalize_folder),
+        tags: prompts::normalize_tags(&prompt.tags),
+        ..prompt
+    }
+}
+
+// Merges `incoming` into `library` and reports what was done. Prompts are
+// merged one after the other, so duplicates within a bundle collide too.
+fn merge(
+    library: &mut Vec<Prompt>,
+    incoming: Vec<Prompt>,
+    strategy: MergeStrategy,
+) -> ImportPreview {
+    let mut preview = ImportPreview::default();
+    for prompt in incoming {
+        let collision = library.iter().position(|p| p.id == prompt.id).or_else(|| {
+            library
+                .iter()
+                .position(|p| p.title.to_lowercase() == prompt.title.to_lowercase())
+        });
+        let Some(index) = collision else {
+            preview.added += 1;
+            preview.changes.push(ImportChange {
+                action: "add",
+                title: prompt.title.clone(),
+                existing_title: None,
+            });
+            library.push(prompt);
+            continue;
+        };
+
+        let existing_title = Some(library[index].title.clone());
+        let action = match strategy {
+            MergeStrategy::Skip => {
+                preview.skipped += 1;
+                "skip"
+            }
+            MergeStrategy::Overwrite => {
+                preview.overwritten += 1;
+                // Keep the existing id so history and references stay intact.
+                library[index] = Prompt {
+                    id: library[index].id.clone(),
+                    ..prompt.clone()
+                };
+                "overwrite"
+            }
+            MergeStrategy::KeepBoth => {
+                preview.kept_both += 1;
+                let title = unused_title(library, &prompt.title);
+                library.push(Prompt {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    title: title.clone(),
+                    ..prompt.clone()
+                });
+                preview.changes.push(ImportChange {
+                    action: "keepBoth",
+                    title,
+                    existing_title,
+                });
+                continue;
+            }
+        };
+        preview.changes.push(ImportChange {
+            action,
+            title: prompt.title,
+            existing_title,
+        });
+    }
+    preview
+}
+
+// Merges what `read` found into `library`, keeping its conversion issues in
+// the report.
+pub fn merge_read(
+    library: &mut Vec<Prompt>,
+    (incoming, issues): (Vec<Prompt>, Vec<ImportIssue>),
+    strategy: MergeStrategy,
+) -> ImportPreview {
+    ImportPreview {
+        issues,
+        ..merge(library, incoming, strategy)
+    }
+}
+
+// `title`, or `title (2)`, `title (3)`... whichever is free.
+fn unused_title(library: &[Prompt], title: &str) -> String {
+    let taken = |candidate: &str| {
+        library
+            .iter()
+            .any(|p| p.title.to_lowercase() == candidate.to_lowercase())
+    };
+    // Only the id may have collided.
+    if !taken(title) {
+        return title.to_string();
+    }
+    (2..)
+        .map(|n| format!("{} ({})", title, n))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or_else(|| title.to_string())
+}
+
+// Exports the whole library, or only the prompts in `ids`, and returns how
+// many prompts were written.
+pub fn export(format: BundleFormat, path: &Path, ids: Option<&[String]>) -> Result<usize, String> {
+    let prompts: Vec<Prompt> = library::prompts()
+        .into_iter()
+        .filter(|p| ids.is_none_or(|ids| ids.contains(&p.id)))
+        .collect();
+    println!("📤 Exporting {} prompts to {} ({:?})", prompts.len(), path.display(), format);
+    write(format, path, &prompts)?;
+    Ok(prompts.len())
+}
+
+// Merges the prompts in `path` into the library as one undoable step.
+pub fn import(
+    app: &AppHandle,
+    format: BundleFormat,
+    path: &Path,
+    strategy: MergeStrategy,
+) -> Result<ImportPreview, String> {
+    let read = read(format, path)?;
+    println!("📥 Importing {} prompts from {} ({:?})", read.0.len(), path.display(), format);
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut preview = ImportPreview::default();
+    library::update_all(app, &format!("Import “{}”", name), |prompts| {
+        preview = merge_read(prompts, read, strategy);
+        Ok(())
+    })?;
+    Ok(preview)
+}
+
+#[tauri::command]
+pub async fn export_library(
+    format: BundleFormat,
+    path: String,
+    ids: Option<Vec<String>>,
+) -> Result<usize, String> {
+    export(format, &PathBuf::from(&path), ids.as_deref())
+}
+
+#[tauri::command]
+pub async fn preview_import(
+    format: BundleFormat,
+    path: String,
+    strategy: MergeStrategy,
+) -> Result<ImportPreview, String> {
+    let read = read(format, &PathBuf::from(&path))?;
+    Ok(merge_read(&mut library::prompts(), read, strategy))
+}
+
+#[tauri::command]
+pub async fn import_library(
+    app: AppHandle,
+    format: BundleFormat,
+    path: String,
+    strategy: MergeStrategy,
+) -> Result<ImportPreview, String> {
+    import(&app, format, &PathBuf::from(&path), strategy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(id: &str, title: &str) -> Prompt {
+        Prompt {
+            id: id.to_string(),
+            title: title.to_string(),
+            content: format!("content of {}", title),
+            color: prompts::DEFAULT_COLOR.to_string(),
+            abbreviation: None,
+            tags: Vec::new(),
+            folder: None,
+        }
+    }
+
+    fn titles(library: &[Prompt]) -> Vec<&str> {
+        library.iter().map(|p| p.title.as_str()).collect()
+    }
+
+    fn actions(preview: &ImportPreview) -> Vec<&str> {
+        preview.changes.iter().map(|change| change.action).collect()
+    }
+
+    #[test]
+    fn adds_prompts_without_collisions() {
+        let mut library = vec![prompt("a", "Alpha")];
+        let preview = merge(&mut library, vec![prompt("b", "Beta")], MergeStrategy::Skip);
+        assert_eq!(titles(&library), ["Alpha", "Beta"]);
+        assert_eq!(preview.added, 1);
+        assert_eq!(actions(&preview), ["add"]);
+    }
+
+    #[test]
+    fn skip_keeps_the_existing_prompt() {
+        let mut library = vec![prompt("a", "Alpha")];
+        let preview = merge(
+            &mut library,
+            vec![prompt("a", "New Alpha"), prompt("x", "ALPHA")],
+            MergeStrategy::Skip,
+        );
+        assert_eq!(titles(&library), ["Alpha"]);
+        assert_eq!(library[0].content, "content of Alpha");
+        assert_eq!(preview.skipped, 2);
+        assert_eq!(preview.changes[0].existing_title.as_deref(), Some("Alpha"));
+    }
+
+    #[test]
+    fn overwrite_replaces_in_place_and_keeps_the_id() {
+        let mut library = vec![prompt("a", "Alpha"), prompt("b", "Beta")];
+        let preview = merge(
+            &mut library,
+            vec![prompt("x", "alpha")],
+            MergeStrategy::Overwrite,
+        );
+        assert_eq!(titles(&library), ["alpha", "Beta"]);
+        assert_eq!(library[0].id, "a");
+        assert_eq!(library[0].content, "content of alpha");
+        assert_eq!(preview.overwritten, 1);
+    }
+
+    #[test]
+    fn keep_both_numbers_a_taken_title() {
+        let mut library = vec![prompt("a", "Alpha"), prompt("b", "Alpha (2)")];
+        let preview = merge(
+            &mut library,
+            vec![prompt("x", "Alpha")],
+            MergeStrategy::KeepBoth,
+        );
+        assert_eq!(titles(&library), ["Alpha", "Alpha (2)", "Alpha (3)"]);
+        assert_ne!(library[2].id, "x");
+        assert_eq!(preview.kept_both, 1);
+        assert_eq!(preview.changes[0].title, "Alpha (3)");
+    }
+
+    #[test]
+    fn keep_both_keeps_the_title_when_only_the_id_collided() {
+        let mut library = vec![prompt("a", "Alpha")];
+        let preview = merge(
+            &mut library,
+            vec![prompt("a", "Beta")],
+            MergeStrategy::KeepBoth,
+        );
+        assert_eq!(titles(&library), ["Alpha", "Beta"]);
+        assert_eq!(library[0].id, "a");
+        assert_ne!(library[1].id, "a");
+        assert_eq!(actions(&preview), ["keepBoth"]);
+    }
+
+    #[test]
+    fn duplicates_within_a_bundle_collide() {
+        let mut library = Vec::new();
+        let preview = merge(
+            &mut library,
+            vec![prompt("a", "Alpha"), prompt("a", "Alpha")],
+            MergeStrategy::KeepBoth,
+        );
+        assert_eq!(titles(&library), ["Alpha", "Alpha (2)"]);
+        assert_eq!(actions(&preview), ["add", "keepBoth"]);
+    }
+
+    #[test]
+    fn clean_fills_in_a_missing_id() {
+        let cleaned = clean(prompt(" ", "Alpha"));
+        assert!(uuid::Uuid::parse_str(&cleaned.id).is_ok());
+        assert_eq!(clean(prompt("a", "Alpha")).id, "a");
+    }
+}
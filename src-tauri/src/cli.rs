@@ -0,0 +1,541 @@
+// Command-line companion: `prompt-buddy <command>` (the app binary, started
+// with a command) talks to the running app over the JSON-RPC socket in `ipc`,
+// or works on the store files directly when the app isn't running.
+//
+//     prompt-buddy list [--json]
+//     prompt-buddy show <id|title> [--json]
+//     prompt-buddy inject <id|title> [--var name=value]...
+//     prompt-buddy add --title <title> [--tag <tag>]... [--folder <folder>] < content.txt
+//     prompt-buddy export [--format json|markdown|csv] <path>
+//     prompt-buddy import [--format <format>] [--strategy skip|overwrite|keep-both] <path>
+//     prompt-buddy mcp
+//     prompt-buddy native-host install|manifest|serve ...
+//
+// Without the app there is nothing to type with, so `inject` prints the
+// rendered prompt instead.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::backups;
+use crate::bundle::{self, BundleFormat, MergeStrategy};
+use crate::deep_link;
+use crate::ipc::{self, Request, Response};
+use crate::mcp;
+use crate::migrations;
+use crate::native_host::{self, Browser};
+use crate::prompts::Prompt;
+use crate::shell_vars;
+
+// Must match `identifier` in tauri.conf.json: the app data dir is named after it.
+const APP_IDENTIFIER: &str = "com.promptbuddy.app";
+
+const COMMANDS: &[&str] = &[
+    "list",
+    "show",
+    "inject",
+    "add",
+    "export",
+    "import",
+    "mcp",
+    "native-host",
+    "help",
+];
+
+const USAGE: &str = "Usage:
+  prompt-buddy list [--json]
+  prompt-buddy show <id|title> [--json]
+  prompt-buddy inject <id|title> [--var name=value]...
+  prompt-buddy add --title <title> [--tag <tag>]... [--folder <folder>] < content.txt
+  prompt-buddy export [--format json|markdown|csv] <path>
+  prompt-buddy import [--format json|markdown|csv|espanso|alfred|raycast|vscode]
+                      [--strategy skip|overwrite|keep-both] <path>
+  prompt-buddy mcp      (Model Context Protocol server on stdin/stdout)
+  prompt-buddy native-host install --extension-id <id>... [--browser <browser>]...
+  prompt-buddy native-host manifest --browser <browser> --extension-id <id>...
+                        (browsers: chrome, chromium, brave, edge, firefox)";
+
+// Runs the command line if the process was started with a command, and returns
+// its exit code; returns `None` to start the app as usual.
+pub fn run_from_args() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // A `promptbuddy://` link goes to the running app; without one, the app
+    // starts and opens it itself (see `deep_link`).
+    if let Some(url) = deep_link::url_from_args(args.iter().cloned()) {
+        return match forward(&Request::OpenLink { url })? {
+            Ok(_) => Some(0),
+            Err(e) => {
+                attach_console();
+                eprintln!("prompt-buddy: {}", e);
+                Some(1)
+            }
+        };
+    }
+    let command = args.first()?;
+    if !COMMANDS.contains(&command.as_str()) && command != "--help" && command != "-h" {
+        return None;
+    }
+    attach_console();
+    Some(match run(&args) {
+        Ok(()) => 0,
+        Err(CliError::Usage(message)) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            2
+        }
+        Err(CliError::Failed(message)) => {
+            eprintln!("prompt-buddy: {}", message);
+            1
+        }
+    })
+}
+
+enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Failed(message)
+    }
+}
+
+// Release builds on Windows have no console of their own.
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+// Options and positional arguments of one command.
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    fn parse(args: &[String], with_value: &[&str]) -> Result<Self, CliError> {
+        let mut parsed = Args {
+            positional: Vec::new(),
+            options: Vec::new(),
+            flags: Vec::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg.clone());
+                continue;
+            };
+            if let Some((name, value)) = name.split_once('=') {
+                if !with_value.contains(&name) {
+                    return Err(CliError::Usage(format!("Unknown option --{}", name)));
+                }
+                parsed.options.push((name.to_string(), value.to_string()));
+            } else if with_value.contains(&name) {
+                let value = args
+                    .next()
+                    .ok_or_else(|| CliError::Usage(format!("--{} needs a value", name)))?;
+                parsed.options.push((name.to_string(), value.clone()));
+            } else if name == "json" {
+                parsed.flags.push(name.to_string());
+            } else {
+                return Err(CliError::Usage(format!("Unknown option --{}", name)));
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| option == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn all(&self, name: &str) -> Vec<String> {
+        self.options
+            .iter()
+            .filter(|(option, _)| option == name)
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+
+    fn json(&self) -> bool {
+        self.flags.iter().any(|flag| flag == "json")
+    }
+
+    // The single positional argument, e.g. the prompt or path.
+    fn target(&self, what: &str) -> Result<String, CliError> {
+        match self.positional.as_slice() {
+            [target] => Ok(target.clone()),
+            [] => Err(CliError::Usage(format!("Missing {}", what))),
+            _ => Err(CliError::Usage(format!("Expected a single {}", what))),
+        }
+    }
+
+    fn format(&self) -> Result<BundleFormat, CliError> {
+        let format = self.option("format").unwrap_or("json");
+        serde_json::from_value(Value::String(format.to_lowercase()))
+            .map_err(|_| CliError::Usage(format!("Unknown format '{}'", format)))
+    }
+}
+
+fn run(args: &[String]) -> Result<(), CliError> {
+    let (command, rest) = args.split_first().expect("run_from_args checked for a command");
+    match command.as_str() {
+        "list" => {
+            let args = Args::parse(rest, &[])?;
+            let prompts: Vec<Prompt> = from_value(execute(Request::List {})?)?;
+            if args.json() {
+                print_json(&prompts)?;
+            } else {
+                for prompt in prompts {
+                    match &prompt.folder {
+                        Some(folder) => println!("{}\t{}\t{}", prompt.id, prompt.title, folder),
+                        None => println!("{}\t{}", prompt.id, prompt.title),
+                    }
+                }
+            }
+            Ok(())
+        }
+        "show" => {
+            let args = Args::parse(rest, &[])?;
+            let prompt = args.target("prompt id or title")?;
+            let prompt: Prompt = from_value(execute(Request::Get { prompt })?)?;
+            if args.json() {
+                print_json(&prompt)?;
+            } else {
+                println!("{}", prompt.content);
+            }
+            Ok(())
+        }
+        "inject" => {
+            let args = Args::parse(rest, &["var"])?;
+            let prompt = args.target("prompt id or title")?;
+            let mut vars = HashMap::new();
+            for var in args.all("var") {
+                let (name, value) = var.split_once('=').ok_or_else(|| {
+                    CliError::Usage(format!("--var expects name=value, got '{}'", var))
+                })?;
+                vars.insert(name.trim().to_string(), value.to_string());
+            }
+            inject(prompt, vars)
+        }
+        "add" => {
+            let args = Args::parse(rest, &["title", "tag", "folder"])?;
+            let title = args
+                .option("title")
+                .ok_or_else(|| CliError::Usage("add needs --title".to_string()))?
+                .to_string();
+            let mut content = String::new();
+            std::io::stdin()
+                .read_to_string(&mut content)
+                .map_err(|e| format!("Failed to read the prompt from stdin: {}", e))?;
+            let request = Request::Create {
+                title,
+                content: content.trim_end_matches('\n').to_string(),
+                tags: args.all("tag"),
+                folder: args.option("folder").map(str::to_string),
+            };
+            let prompt: Prompt = from_value(execute(request)?)?;
+            println!("{}", prompt.id);
+            Ok(())
+        }
+        "export" => {
+            let args = Args::parse(rest, &["format"])?;
+            let request = Request::Export {
+                format: args.format()?,
+                path: absolute(&args.target("path")?),
+                ids: None,
+            };
+            let count: usize = from_value(execute(request)?)?;
+            println!("Exported {} prompts", count);
+            Ok(())
+        }
+        "import" => {
+            let args = Args::parse(rest, &["format", "strategy"])?;
+            let strategy = match args.option("strategy").unwrap_or("skip") {
+                "skip" => MergeStrategy::Skip,
+                "overwrite" => MergeStrategy::Overwrite,
+                "keep-both" | "keepBoth" => MergeStrategy::KeepBoth,
+                other => return Err(CliError::Usage(format!("Unknown strategy '{}'", other))),
+            };
+            let request = Request::Import {
+                format: args.format()?,
+                path: absolute(&args.target("path")?),
+                strategy,
+            };
+            print_json(&execute(request)?)
+        }
+        "mcp" => {
+            Args::parse(rest, &[])?;
+            Ok(mcp::serve()?)
+        }
+        // Browsers add arguments of their own, such as the extension's origin.
+        "native-host" if rest.first().is_some_and(|arg| arg == "serve") => {
+            Ok(native_host::serve()?)
+        }
+        "native-host" => {
+            let args = Args::parse(rest, &["browser", "extension-id"])?;
+            let ids = args.all("extension-id");
+            if ids.is_empty() {
+                return Err(CliError::Usage("Missing --extension-id".to_string()));
+            }
+            let mut browsers = Vec::new();
+            for name in args.all("browser") {
+                browsers.push(
+                    Browser::from_name(&name)
+                        .ok_or_else(|| CliError::Usage(format!("Unknown browser '{}'", name)))?,
+                );
+            }
+            match args.target("native-host command")?.as_str() {
+                "manifest" => {
+                    let [browser] = browsers[..] else {
+                        return Err(CliError::Usage("Expected a single --browser".to_string()));
+                    };
+                    println!("{}", native_host::manifest(browser, &ids)?);
+                }
+                "install" => {
+                    if browsers.is_empty() {
+                        browsers = native_host::installed_browsers()
+                            .into_iter()
+                            .filter(|browser| ids.iter().any(|id| browser.accepts(id)))
+                            .collect();
+                    }
+                    if browsers.is_empty() {
+                        return Err(CliError::Failed(
+                            "Found no browser for these extension ids; name one with --browser"
+                                .to_string(),
+                        ));
+                    }
+                    let paths = native_host::install(&browsers, &ids)?;
+                    for (browser, path) in browsers.iter().zip(paths) {
+                        println!("{}\t{}", browser.name(), path.display());
+                    }
+                }
+                other => {
+                    return Err(CliError::Usage(format!(
+                        "Unknown native-host command '{}'",
+                        other
+                    )))
+                }
+            }
+            Ok(())
+        }
+        _ => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+    }
+}
+
+fn inject(prompt: String, vars: HashMap<String, String>) -> Result<(), CliError> {
+    let request = Request::Inject {
+        prompt: prompt.clone(),
+        vars: vars.clone(),
+    };
+    if let Some(response) = forward(&request) {
+        let message: String = from_value(response?)?;
+        eprintln!("{}", message);
+        return Ok(());
+    }
+    let text: String = from_value(execute_offline(Request::Render { prompt, vars })?)?;
+    eprintln!("Prompt Buddy isn't running; printing the prompt instead of injecting it");
+    println!("{}", text);
+    Ok(())
+}
+
+// Runs a request in the app if it is running, or against the store files.
+pub(crate) fn execute(request: Request) -> Result<Value, String> {
+    match forward(&request) {
+        Some(result) => result,
+        None => execute_offline(request),
+    }
+}
+
+// The app's answer to `request`, or `None` if it isn't running.
+#[cfg(unix)]
+fn forward(request: &Request) -> Option<Result<Value, String>> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(ipc::socket_path(&data_dir().ok()?)).ok()?;
+    let result = (|| {
+        let mut encoded = ipc::encode(request, Value::from(1))?.to_string();
+        encoded.push('\n');
+        stream
+            .write_all(encoded.as_bytes())
+            .map_err(|e| format!("Failed to reach Prompt Buddy: {}", e))?;
+        let mut line = String::new();
+        BufReader::new(&stream)
+            .read_line(&mut line)
+            .map_err(|e| format!("Failed to read Prompt Buddy's answer: {}", e))?;
+        let response: Response = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid answer from Prompt Buddy: {}", e))?;
+        match response.error {
+            Some(error) => Err(error.message),
+            None => Ok(response.result.unwrap_or(Value::Null)),
+        }
+    })();
+    Some(result)
+}
+
+#[cfg(not(unix))]
+fn forward(_request: &Request) -> Option<Result<Value, String>> {
+    None
+}
+
+fn execute_offline(request: Request) -> Result<Value, String> {
+    // The app may not have started since an update, or ever.
+    migrations::run_offline(&data_dir()?)?;
+    let mut store = open_store()?;
+    let mut prompts = store.load()?;
+    let value = match request {
+        Request::List {} => serde_json::to_value(&prompts),
+        Request::Get { prompt } => serde_json::to_value(ipc::find_prompt(&prompts, &prompt)?),
+        Request::Search { query } => serde_json::to_value(store.search(&query)?),
+        Request::Render { prompt, vars } => {
+            let prompt = ipc::find_prompt(&prompts, &prompt)?;
+            shell_vars::init_offline(&data_dir()?)?;
+            serde_json::to_value(shell_vars::render(&prompt, &vars)?)
+        }
+        Request::Create {
+            title,
+            content,
+            tags,
+            folder,
+        } => {
+            let prompt = ipc::new_prompt(title, content, &tags, folder.as_deref());
+            backups::snapshot(&data_dir()?.join("backups"), &prompts, false)?;
+            prompts.push(prompt.clone());
+            store.save(&prompts)?;
+            serde_json::to_value(prompt)
+        }
+        Request::Export { format, path, ids } => {
+            let selected: Vec<Prompt> = prompts
+                .into_iter()
+                .filter(|p| ids.as_ref().is_none_or(|ids| ids.contains(&p.id)))
+                .collect();
+            bundle::write(format, Path::new(&path), &selected)?;
+            serde_json::to_value(selected.len())
+        }
+        Request::Import {
+            format,
+            path,
+            strategy,
+        } => {
+            let read = bundle::read(format, Path::new(&path))?;
+            backups::snapshot(&data_dir()?.join("backups"), &prompts, false)?;
+            let preview = bundle::merge_read(&mut prompts, read, strategy);
+            store.save(&prompts)?;
+            serde_json::to_value(preview)
+        }
+        _ => return Err("This needs the running app".to_string()),
+    };
+    value.map_err(|e| format!("Failed to serialize result: {}", e))
+}
+
+fn open_store() -> Result<Box<dyn crate::library::PromptStore>, String> {
+    crate::library::open_offline(&data_dir()?)
+}
+
+// The app data dir, as Tauri resolves it for `APP_IDENTIFIER`.
+pub fn data_dir() -> Result<PathBuf, String> {
+    let home = || std::env::var_os("HOME").map(PathBuf::from);
+    let base = if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library").join("Application Support"))
+    } else if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| home().map(|home| home.join(".local").join("share")))
+    };
+    base.map(|base| base.join(APP_IDENTIFIER))
+        .ok_or_else(|| "Failed to find the Prompt Buddy data directory".to_string())
+}
+
+// For modes that speak a protocol on stdout (`mcp`, `native-host`): the library
+// code logs with `println!`, so stdout is pointed at stderr and the protocol
+// goes to the returned copy of the original stdout.
+#[cfg(unix)]
+pub(crate) fn protocol_output() -> Result<std::fs::File, String> {
+    use std::io::Write;
+    use std::os::fd::FromRawFd;
+    use std::os::raw::c_int;
+
+    extern "C" {
+        fn dup(fd: c_int) -> c_int;
+        fn dup2(fd: c_int, to: c_int) -> c_int;
+    }
+
+    let _ = std::io::stdout().flush();
+    unsafe {
+        let original = dup(1);
+        if original < 0 || dup2(2, 1) < 0 {
+            return Err(format!(
+                "Failed to redirect stdout: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(std::fs::File::from_raw_fd(original))
+    }
+}
+
+// Rust looks up the standard handles on every write, so swapping the handle is
+// enough.
+#[cfg(windows)]
+pub(crate) fn protocol_output() -> Result<std::fs::File, String> {
+    use std::io::Write;
+    use std::os::windows::io::FromRawHandle;
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::System::Console::{
+        GetStdHandle, SetStdHandle, STD_ERROR_HANDLE, STD_OUTPUT_HANDLE,
+    };
+
+    let _ = std::io::stdout().flush();
+    unsafe {
+        let original = GetStdHandle(STD_OUTPUT_HANDLE);
+        if original.is_null() || original == INVALID_HANDLE_VALUE {
+            return Err("Prompt Buddy has no stdout".to_string());
+        }
+        if SetStdHandle(STD_OUTPUT_HANDLE, GetStdHandle(STD_ERROR_HANDLE)) == 0 {
+            return Err(format!(
+                "Failed to redirect stdout: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(std::fs::File::from_raw_handle(original))
+    }
+}
+
+// Paths are resolved by the app, whose working directory is not ours.
+fn absolute(path: &str) -> String {
+    std::env::current_dir()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|_| PathBuf::from(path))
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn from_value<T: serde::de::DeserializeOwned>(value: Value) -> Result<T, String> {
+    serde_json::from_value(value).map_err(|e| format!("Unexpected answer: {}", e))
+}
+
+fn print_json(value: &impl serde::Serialize) -> Result<(), CliError> {
+    let encoded = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize result: {}", e))?;
+    println!("{}", encoded);
+    Ok(())
+}
@@ -0,0 +1,118 @@
+// D-Bus service on the session bus (Linux only), so desktop shortcuts and
+// scripts can drive the app where the global-shortcut plugin can't grab keys,
+// e.g. on Wayland:
+//
+//     gdbus call --session --dest org.promptbuddy.PromptBuddy \
+//         --object-path /org/promptbuddy/PromptBuddy \
+//         --method org.promptbuddy.PromptBuddy.Toggle
+//
+// Methods of `org.promptbuddy.PromptBuddy` at `/org/promptbuddy/PromptBuddy`:
+//
+//     Toggle()                    shows or hides the bar
+//     ListPrompts() -> a(ss)      (id, title) of the whole library, in library order
+//     Inject(s prompt) -> s       types a prompt (id or title) into the focused window
+//     Search(s query) -> a(ss)    (id, title), best matches first
+//
+// `ListPrompts` ignores the bar's folder and tag scope and leaves out project
+// prompts, like `prompts.list` of the other interfaces. There is also the
+// signal `PromptsUpdated(u count)` after every library change. The bus is
+// whatever `DBUS_SESSION_BUS_ADDRESS` names, so running the app under
+// `dbus-run-session` puts it on a private bus (see examples/dbus_client.rs).
+
+#[cfg(target_os = "linux")]
+pub use service::start;
+
+#[cfg(not(target_os = "linux"))]
+pub fn start(_app: &tauri::AppHandle) {}
+
+#[cfg(target_os = "linux")]
+mod service {
+    use std::collections::HashMap;
+
+    use tauri::{AppHandle, Listener};
+    use zbus::blocking::Connection;
+    use zbus::fdo;
+    use zbus::object_server::SignalEmitter;
+
+    use crate::ipc::{self, Request};
+    use crate::library;
+
+    pub const BUS_NAME: &str = "org.promptbuddy.PromptBuddy";
+    pub const OBJECT_PATH: &str = "/org/promptbuddy/PromptBuddy";
+
+    struct Service {
+        app: AppHandle,
+    }
+
+    #[zbus::interface(name = "org.promptbuddy.PromptBuddy")]
+    impl Service {
+        fn toggle(&self) {
+            println!("🔔 Toggle requested over D-Bus");
+            crate::toggle_window_visibility_internal(&self.app);
+        }
+
+        fn list_prompts(&self) -> Vec<(String, String)> {
+            library::prompts()
+                .into_iter()
+                .map(|prompt| (prompt.id, prompt.title))
+                .collect()
+        }
+
+        // Typing takes a while, hooks and shell variables longer, so it runs
+        // off the connection's executor, which keeps answering other calls.
+        async fn inject(&self, prompt: String) -> fdo::Result<String> {
+            let app = self.app.clone();
+            let request = Request::Inject {
+                prompt,
+                vars: HashMap::new(),
+            };
+            let message = tauri::async_runtime::spawn_blocking(move || ipc::execute(&app, request))
+                .await
+                .map_err(|e| fdo::Error::Failed(format!("Failed to inject: {}", e)))?
+                .map_err(fdo::Error::Failed)?;
+            Ok(message.as_str().unwrap_or_default().to_string())
+        }
+
+        fn search(&self, query: String) -> fdo::Result<Vec<(String, String)>> {
+            let hits = library::search(&query).map_err(fdo::Error::Failed)?;
+            Ok(hits
+                .into_iter()
+                .map(|hit| (hit.prompt.id, hit.prompt.title))
+                .collect())
+        }
+
+        #[zbus(signal)]
+        async fn prompts_updated(emitter: &SignalEmitter<'_>, count: u32) -> zbus::Result<()>;
+    }
+
+    pub fn start(app: &AppHandle) {
+        let service = Service { app: app.clone() };
+        let connection = match zbus::blocking::connection::Builder::session()
+            .and_then(|builder| builder.name(BUS_NAME))
+            .and_then(|builder| builder.serve_at(OBJECT_PATH, service))
+            .and_then(|builder| builder.build())
+        {
+            Ok(connection) => connection,
+            Err(e) => {
+                println!("⚠️  D-Bus service not available: {}", e);
+                return;
+            }
+        };
+        println!("✅ Serving {} on the session bus", BUS_NAME);
+
+        // The listener keeps the connection, and with it the bus name, alive.
+        app.listen("prompts-updated", move |_| {
+            let count = library::prompts().len() as u32;
+            if let Err(e) = notify(&connection, count) {
+                println!("⚠️  Failed to emit PromptsUpdated: {}", e);
+            }
+        });
+    }
+
+    fn notify(connection: &Connection, count: u32) -> zbus::Result<()> {
+        let service = connection
+            .object_server()
+            .interface::<_, Service>(OBJECT_PATH)?;
+        zbus::block_on(Service::prompts_updated(service.signal_emitter(), count))
+    }
+}
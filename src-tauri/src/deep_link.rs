@@ -0,0 +1,608 @@
+// `promptbuddy://` links, so pages such as the team wiki can fire or add prompts:
+//
+//     promptbuddy://inject?id=<id|title>&var.lang=rust
+//     promptbuddy://add?title=...&content=...[&tag=...]...[&folder=...]
+//     promptbuddy://show
+//
+// macOS hands links to the running app (`RunEvent::Opened`). Elsewhere the
+// scheme starts the binary with the link as its argument; `cli` forwards it to
+// the running app over the socket in `ipc` (`links.open`), and a freshly
+// started app opens it once set up. Windows has no socket yet; there the
+// single-instance plugin hands the link to the running app instead (see
+// `run` in lib.rs).
+//
+// A link can come from any web page, so `inject` and `add` wait for the user to
+// confirm them in a small window, unless the app the link was clicked in (the
+// frontmost app when it arrives) is in `deepLinkTrustedApps`. Browsers can't be
+// trusted that way, since trusting one would trust every page it shows. Only
+// one link can wait for confirmation at a time.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_store::StoreExt;
+
+use crate::ipc::{self, Request};
+use crate::prompts::Prompt;
+use crate::{library, shell_vars, template};
+
+pub const SCHEME: &str = "promptbuddy";
+
+const CONFIRM_WINDOW: &str = "deep-link";
+
+static TRUSTED_APPS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// Names browsers go by as the frontmost app (macOS app names, X11 classes and
+// Windows executables), lowercased.
+const BROWSERS: &[&str] = &[
+    "arc",
+    "brave",
+    "brave browser",
+    "brave-browser",
+    "chrome",
+    "chromium",
+    "chromium-browser",
+    "epiphany",
+    "firefox",
+    "firefox developer edition",
+    "google chrome",
+    "google-chrome",
+    "librewolf",
+    "microsoft edge",
+    "microsoft-edge",
+    "msedge",
+    "navigator",
+    "opera",
+    "safari",
+    "vivaldi",
+    "vivaldi-stable",
+    "zen",
+    "zen browser",
+];
+
+static PENDING: Lazy<Mutex<Option<Pending>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Inject {
+        prompt: String,
+        vars: HashMap<String, String>,
+    },
+    Add {
+        title: String,
+        content: String,
+        tags: Vec<String>,
+        folder: Option<String>,
+    },
+    Show,
+}
+
+// A link waiting for confirmation, with a preview of what it would insert.
+struct Pending {
+    request: LinkRequest,
+    action: Action,
+    // The prompt an `inject` link resolved to when the preview was made.
+    prompt: Option<Prompt>,
+}
+
+// What the confirmation window shows.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkRequest {
+    // "inject" or "add"
+    action: String,
+    title: String,
+    // Shell variables are left as they are: they only run once approved.
+    text: String,
+    commands: Vec<String>,
+    source: Option<String>,
+}
+
+pub fn init(app: &AppHandle) {
+    let saved = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("deepLinkTrustedApps"))
+        .and_then(|value| serde_json::from_value::<Vec<String>>(value).ok());
+    if let Some(apps) = saved {
+        println!(
+            "📋 Loaded {} trusted link sources from settings",
+            apps.len()
+        );
+        *TRUSTED_APPS.lock().unwrap() = apps;
+    }
+
+    if let Err(e) = register(app) {
+        println!("⚠️  Failed to register {}:// links: {}", SCHEME, e);
+    }
+
+    // Started by a link while the app wasn't running.
+    if let Some(url) = url_from_args(std::env::args().skip(1)) {
+        if let Err(e) = open(app, &url) {
+            println!("❌ {}", e);
+        }
+    }
+}
+
+// The link among the command-line arguments, if any.
+pub fn url_from_args(mut args: impl Iterator<Item = String>) -> Option<String> {
+    args.find(|arg| is_link(arg))
+}
+
+fn is_link(arg: &str) -> bool {
+    arg.split_once(':')
+        .is_some_and(|(scheme, _)| scheme.eq_ignore_ascii_case(SCHEME))
+}
+
+pub fn parse(url: &str) -> Result<Action, String> {
+    let invalid = |reason: &str| format!("Invalid link {}: {}", url, reason);
+    let rest = match url.split_once(':') {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case(SCHEME) => rest,
+        _ => return Err(invalid("not a promptbuddy:// link")),
+    };
+    let rest = rest.trim_start_matches('/');
+    let rest = rest.split_once('#').map_or(rest, |(rest, _)| rest);
+    let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    let mut params = Vec::new();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        params.push((decode(name)?, decode(value)?));
+    }
+    let single = |name: &str| -> Result<Option<String>, String> {
+        let mut values = params.iter().filter(|(n, _)| n == name);
+        let value = values.next().map(|(_, v)| v.clone());
+        if values.next().is_some() {
+            return Err(invalid(&format!("`{}` is given more than once", name)));
+        }
+        Ok(value)
+    };
+    let required = |name: &str| -> Result<String, String> {
+        single(name)?
+            .filter(|value| !value.trim().is_empty())
+            .ok_or_else(|| invalid(&format!("`{}` is missing", name)))
+    };
+
+    match action.trim_end_matches('/').to_ascii_lowercase().as_str() {
+        "inject" => Ok(Action::Inject {
+            prompt: required("id")?,
+            vars: params
+                .iter()
+                .filter_map(|(name, value)| {
+                    let name = name.strip_prefix("var.")?;
+                    Some((name.to_string(), value.clone()))
+                })
+                .collect(),
+        }),
+        "add" => Ok(Action::Add {
+            title: required("title")?,
+            content: required("content")?,
+            tags: params
+                .iter()
+                .filter(|(name, value)| name == "tag" && !value.trim().is_empty())
+                .map(|(_, value)| value.trim().to_string())
+                .collect(),
+            folder: single("folder")?,
+        }),
+        "show" => Ok(Action::Show),
+        "" => Err(invalid("no action")),
+        other => Err(invalid(&format!("unknown action `{}`", other))),
+    }
+}
+
+// Query strings from HTML forms encode spaces as `+`.
+fn decode(part: &str) -> Result<String, String> {
+    urlencoding::decode(&part.replace('+', " "))
+        .map(|decoded| decoded.into_owned())
+        .map_err(|e| format!("Invalid link: {}", e))
+}
+
+// Runs a link, or asks for confirmation first.
+pub fn open(app: &AppHandle, url: &str) -> Result<(), String> {
+    let action = parse(url)?;
+    println!("🔗 Opening link: {:?}", action);
+    if action == Action::Show {
+        show_bar(app);
+        return Ok(());
+    }
+
+    let source = crate::get_frontmost_app();
+    let (request, prompt) = describe(&action, source.clone())?;
+    if source.as_deref().is_some_and(is_trusted) {
+        println!("✅ {} is a trusted link source", source.unwrap_or_default());
+        return run(app, action, prompt, &request);
+    }
+
+    {
+        let mut pending = PENDING.lock().unwrap();
+        if pending.is_some() && app.get_webview_window(CONFIRM_WINDOW).is_some() {
+            return Err("Another link is waiting for confirmation".to_string());
+        }
+        *pending = Some(Pending {
+            request,
+            action,
+            prompt,
+        });
+    }
+    // Focus goes back to the source once the user answers.
+    crate::remember_current_app(app);
+    show_confirm_window(app)
+}
+
+fn is_trusted(app_name: &str) -> bool {
+    // Settings from before browsers were refused may still list one.
+    if is_browser(app_name) {
+        return false;
+    }
+    TRUSTED_APPS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|trusted| trusted.eq_ignore_ascii_case(app_name))
+}
+
+fn is_browser(app_name: &str) -> bool {
+    let name = app_name.trim().to_lowercase();
+    let name = name.strip_suffix(".exe").unwrap_or(&name);
+    BROWSERS.contains(&name)
+}
+
+// Checks the link can run, and renders what it would insert, apart from the
+// shell commands it would run. An `inject` link's prompt is looked up here
+// once, so what runs is what was shown even if the library changes meanwhile.
+fn describe(
+    action: &Action,
+    source: Option<String>,
+) -> Result<(LinkRequest, Option<Prompt>), String> {
+    let (kind, title, text, commands, prompt) = match action {
+        Action::Inject { prompt, vars } => {
+            let prompt = ipc::find_prompt(&library::prompts(), prompt)?;
+            let text = template::render_with(&prompt.content, vars, |command| {
+                Ok(format!("{{{{sh: {}}}}}", command))
+            })?;
+            let commands = template::commands(&prompt.content);
+            ("inject", prompt.title.clone(), text, commands, Some(prompt))
+        }
+        Action::Add { title, content, .. } => {
+            ("add", title.clone(), content.clone(), Vec::new(), None)
+        }
+        Action::Show => unreachable!("`show` needs no confirmation"),
+    };
+    let request = LinkRequest {
+        action: kind.to_string(),
+        title,
+        text,
+        commands,
+        source,
+    };
+    Ok((request, prompt))
+}
+
+fn run(
+    app: &AppHandle,
+    action: Action,
+    prompt: Option<Prompt>,
+    request: &LinkRequest,
+) -> Result<(), String> {
+    match action {
+        Action::Inject { vars, .. } => {
+            println!("⌨️  Injecting “{}” from a link", request.title);
+            let prompt = prompt.expect("`describe` resolves the prompt of `inject` links");
+            let text = shell_vars::render(&prompt, &vars)?;
+            crate::inject_and_report(app, &text, Some(&prompt), "link")?;
+        }
+        Action::Add {
+            title,
+            content,
+            tags,
+            folder,
+        } => {
+            ipc::execute(
+                app,
+                Request::Create {
+                    title,
+                    content,
+                    tags,
+                    folder,
+                },
+            )?;
+        }
+        Action::Show => show_bar(app),
+    }
+    Ok(())
+}
+
+fn show_bar(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        println!("❌ Could not find main window");
+        return;
+    };
+    if !window.is_visible().unwrap_or(false) {
+        crate::remember_current_app(app);
+    }
+    if let Err(e) = window.show() {
+        println!("❌ Failed to show window: {}", e);
+    } else {
+        let _ = window.set_focus();
+    }
+}
+
+fn show_confirm_window(app: &AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(CONFIRM_WINDOW) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return Ok(());
+    }
+    WebviewWindowBuilder::new(
+        app,
+        CONFIRM_WINDOW,
+        WebviewUrl::App("index.html?deep-link".into()),
+    )
+    .title("Prompt Buddy Link")
+    .inner_size(420.0, 320.0)
+    .resizable(false)
+    .always_on_top(true)
+    .center()
+    .focused(true)
+    .build()
+    .map(|_| ())
+    .map_err(|e| format!("Failed to open the confirmation window: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_pending_link() -> Result<Option<LinkRequest>, String> {
+    Ok(PENDING
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|pending| pending.request.clone()))
+}
+
+// Answer from the confirmation window, which is closed before anything runs
+// so the text goes to the app the link came from.
+#[tauri::command]
+pub async fn resolve_pending_link(app: AppHandle, approved: bool) -> Result<(), String> {
+    let pending = PENDING.lock().unwrap().take();
+    if let Some(window) = app.get_webview_window(CONFIRM_WINDOW) {
+        let _ = window.close();
+    }
+    let Some(Pending {
+        request,
+        action,
+        prompt,
+    }) = pending
+    else {
+        return Err("No link is waiting for confirmation".to_string());
+    };
+    if !approved {
+        println!("🚫 Link declined: {} “{}”", request.action, request.title);
+        return Ok(());
+    }
+
+    #[cfg(target_os = "macos")]
+    if let Some(source) = &request.source {
+        if !crate::activate_app(source) {
+            println!("⚠️  Failed to reactivate {}", source);
+        }
+    }
+    run(&app, action, prompt, &request)
+}
+
+#[tauri::command]
+pub async fn get_deep_link_trusted_apps() -> Result<Vec<String>, String> {
+    Ok(TRUSTED_APPS.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn update_deep_link_trusted_apps(
+    app: AppHandle,
+    apps: Vec<String>,
+) -> Result<(), String> {
+    let apps: Vec<String> = apps
+        .into_iter()
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect();
+    if let Some(browser) = apps.iter().find(|a| is_browser(a)) {
+        return Err(format!(
+            "{} is a web browser; links from it can come from any page, so it can't be a trusted source",
+            browser
+        ));
+    }
+    println!("🔧 Updating trusted link sources: {:?}", apps);
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("Failed to load settings: {}", e))?;
+    store.set("deepLinkTrustedApps", apps.clone());
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    *TRUSTED_APPS.lock().unwrap() = apps;
+    Ok(())
+}
+
+// macOS registers the scheme from Info.plist when the app is installed.
+#[cfg(target_os = "macos")]
+fn register(_app: &AppHandle) -> Result<(), String> {
+    Ok(())
+}
+
+// A hidden desktop entry for this binary, made the handler of the scheme.
+#[cfg(target_os = "linux")]
+fn register(app: &AppHandle) -> Result<(), String> {
+    use std::process::Command;
+
+    let exe =
+        std::env::current_exe().map_err(|e| format!("Failed to find the app binary: {}", e))?;
+    let dir = app
+        .path()
+        .data_dir()
+        .map_err(|e| format!("Failed to find the data directory: {}", e))?
+        .join("applications");
+    let file_name = format!("{}-handler.desktop", app.config().identifier);
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=Prompt Buddy\nExec=\"{}\" %u\n\
+         Terminal=false\nNoDisplay=true\nMimeType=x-scheme-handler/{};\n",
+        exe.display(),
+        SCHEME
+    );
+    let path = dir.join(&file_name);
+    if std::fs::read_to_string(&path).is_ok_and(|current| current == entry) {
+        return Ok(());
+    }
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    std::fs::write(&path, entry)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let status = Command::new("xdg-mime")
+        .args([
+            "default",
+            &file_name,
+            &format!("x-scheme-handler/{}", SCHEME),
+        ])
+        .status()
+        .map_err(|e| format!("Failed to run xdg-mime: {}", e))?;
+    if !status.success() {
+        return Err(format!("xdg-mime failed with {}", status));
+    }
+    println!("🔗 Registered {}:// links", SCHEME);
+    Ok(())
+}
+
+// The scheme's open command under HKEY_CURRENT_USER, which needs no elevation.
+#[cfg(target_os = "windows")]
+fn register(_app: &AppHandle) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+
+    // CREATE_NO_WINDOW, so no console flashes up.
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let exe =
+        std::env::current_exe().map_err(|e| format!("Failed to find the app binary: {}", e))?;
+    let key = format!(r"HKCU\Software\Classes\{}", SCHEME);
+    let command = format!("\"{}\" \"%1\"", exe.display());
+    let entries = [
+        (key.clone(), None, "URL:Prompt Buddy".to_string()),
+        (key.clone(), Some("URL Protocol"), String::new()),
+        (format!(r"{}\shell\open\command", key), None, command),
+    ];
+    for (key, name, data) in entries {
+        let mut reg = Command::new("reg");
+        reg.args(["add", &key, "/f", "/d", &data]);
+        match name {
+            Some(name) => reg.args(["/v", name]),
+            None => reg.arg("/ve"),
+        };
+        let status = reg
+            .creation_flags(CREATE_NO_WINDOW)
+            .status()
+            .map_err(|e| format!("Failed to run reg: {}", e))?;
+        if !status.success() {
+            return Err(format!("reg add {} failed with {}", key, status));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn register(_app: &AppHandle) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_inject_links() {
+        assert_eq!(
+            parse("promptbuddy://inject?id=Code%20Review&var.lang=rust&var.focus=edge+cases&x=1")
+                .unwrap(),
+            Action::Inject {
+                prompt: "Code Review".to_string(),
+                vars: vars(&[("lang", "rust"), ("focus", "edge cases")]),
+            }
+        );
+        // Scheme and action in any case, with or without slashes.
+        assert_eq!(
+            parse("PromptBuddy:Inject/?id=abc#ignored").unwrap(),
+            Action::Inject {
+                prompt: "abc".to_string(),
+                vars: HashMap::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_add_links() {
+        assert_eq!(
+            parse("promptbuddy://add?title=Hi&content=Line%201%0ALine%202&tag=a&tag=+b+&tag=&folder=Work")
+                .unwrap(),
+            Action::Add {
+                title: "Hi".to_string(),
+                content: "Line 1\nLine 2".to_string(),
+                tags: vec!["a".to_string(), "b".to_string()],
+                folder: Some("Work".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_show_links() {
+        assert_eq!(parse("promptbuddy://show").unwrap(), Action::Show);
+    }
+
+    #[test]
+    fn rejects_invalid_links() {
+        for url in [
+            "https://example.com/inject?id=x",
+            "promptbuddy://",
+            "promptbuddy://delete?id=x",
+            "promptbuddy://inject",
+            "promptbuddy://inject?id=%20",
+            "promptbuddy://inject?id=a&id=b",
+            "promptbuddy://add?title=Hi",
+            "promptbuddy://add?title=Hi&content=x&folder=a&folder=b",
+            "promptbuddy://inject?id=%FF",
+        ] {
+            assert!(parse(url).is_err(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn finds_the_link_among_arguments() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            url_from_args(args(&["--flag", "PROMPTBUDDY://show"]).into_iter()),
+            Some("PROMPTBUDDY://show".to_string())
+        );
+        assert_eq!(url_from_args(args(&["add", "https://x"]).into_iter()), None);
+    }
+
+    #[test]
+    fn never_trusts_browsers() {
+        assert!(is_browser("Google Chrome"));
+        assert!(is_browser("firefox"));
+        assert!(is_browser("msedge.exe"));
+        assert!(!is_browser("Notion"));
+
+        *TRUSTED_APPS.lock().unwrap() = vec!["Notion".to_string(), "Safari".to_string()];
+        assert!(is_trusted("notion"));
+        assert!(!is_trusted("Safari"));
+        assert!(!is_trusted("Slack"));
+    }
+}
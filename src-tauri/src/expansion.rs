@@ -0,0 +1,636 @@
+// Espanso-style text expansion: a global keystroke monitor keeps a rolling
+// buffer of what the user typed in any application. When the buffer ends with
+// a prompt's abbreviation (e.g. `;dbg`), the trigger is erased with backspaces
+// and the prompt is typed through the regular injection path, hooks included.
+// Variables take their defaults, `{{clipboard}}` the clipboard text, and
+// `{{sh: ...}}` commands run if the prompt may run them (see `shell_vars`); a
+// prompt with another required variable, or with commands it may not run,
+// doesn't expand and the trigger stays as typed.
+//
+// The feature is opt-in (`textExpansionEnabled` in settings.json) and can be
+// switched off at any time with `set_text_expansion_enabled`, which tears the
+// monitor down instead of merely ignoring keystrokes. Applications listed in
+// `textExpansionBlocklist` never expand.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use once_cell::sync::Lazy;
+use tauri::{AppHandle, Listener};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_store::StoreExt;
+
+use crate::prompts::Prompt;
+use crate::{library, safety, shell_vars, template, InjectError};
+
+// Only the tail of what was typed is interesting; abbreviations are short.
+const BUFFER_CAPACITY: usize = 64;
+
+// Set while we are erasing a trigger and typing its expansion, so our own
+// synthetic keystrokes are not fed back into the buffer.
+static EXPANDING: AtomicBool = AtomicBool::new(false);
+
+// When the monitor last reported a keystroke, ours included. The monitor gets
+// them asynchronously, so an expansion is only over once it has gone quiet.
+static LAST_INPUT: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
+const SETTLE_TIME: Duration = Duration::from_millis(100);
+const SETTLE_LIMIT: Duration = Duration::from_secs(2);
+
+static ABBREVIATIONS: Lazy<Mutex<Vec<Abbreviation>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// The running keystroke monitor, if text expansion is enabled.
+static MONITOR: Lazy<Mutex<Option<platform::Monitor>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Clone, Debug)]
+struct Abbreviation {
+    trigger: String,
+    prompt: Prompt,
+}
+
+// Keystrokes as reported by the platform monitor, already translated through
+// the active keyboard layout.
+#[derive(Debug)]
+pub(crate) enum KeyInput {
+    Char(char),
+    Backspace,
+    // Anything that moves the caret or changes focus (Enter, arrows, clicks,
+    // shortcuts): what was typed before can no longer be trusted.
+    Reset,
+}
+
+#[derive(Default)]
+struct TypedBuffer {
+    text: String,
+}
+
+impl TypedBuffer {
+    fn push(&mut self, c: char) {
+        self.text.push(c);
+        if self.text.chars().count() > BUFFER_CAPACITY {
+            self.text.remove(0);
+        }
+    }
+
+    fn pop(&mut self) {
+        self.text.pop();
+    }
+
+    fn clear(&mut self) {
+        self.text.clear();
+    }
+
+    // Returns the longest abbreviation the buffer currently ends with, and
+    // clears the buffer so the same keystrokes can't trigger twice.
+    fn take_match(&mut self, abbreviations: &[Abbreviation]) -> Option<Abbreviation> {
+        let found = abbreviations
+            .iter()
+            .filter(|a| self.text.ends_with(&a.trigger))
+            .max_by_key(|a| a.trigger.len())
+            .cloned();
+        if found.is_some() {
+            self.clear();
+        }
+        found
+    }
+}
+
+pub fn init(app: &AppHandle) {
+    reload_abbreviations();
+
+    app.listen("prompts-updated", |_| reload_abbreviations());
+
+    if load_enabled_from_settings(app) {
+        if let Err(e) = start(app) {
+            println!("❌ Failed to start text expansion: {}", e);
+        }
+    } else {
+        println!("ℹ️  Text expansion is disabled");
+    }
+}
+
+// Kill switch for text expansion. The choice is persisted so it survives a
+// restart.
+#[tauri::command]
+pub async fn set_text_expansion_enabled(app: AppHandle, enabled: bool) -> Result<String, String> {
+    println!("🔧 Setting text expansion enabled: {}", enabled);
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("Failed to load settings: {}", e))?;
+    store.set("textExpansionEnabled", enabled);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    if enabled {
+        start(&app)?;
+        Ok("Text expansion enabled".to_string())
+    } else {
+        stop();
+        Ok("Text expansion disabled".to_string())
+    }
+}
+
+fn load_enabled_from_settings(app: &AppHandle) -> bool {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get("textExpansionEnabled"))
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false)
+}
+
+fn is_blocklisted(app: &AppHandle, app_name: &str) -> bool {
+    let Some(blocklist) = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("textExpansionBlocklist"))
+    else {
+        return false;
+    };
+
+    blocklist
+        .as_array()
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| entry.as_str())
+                .any(|entry| entry.trim().eq_ignore_ascii_case(app_name))
+        })
+        .unwrap_or(false)
+}
+
+fn reload_abbreviations() {
+    let abbreviations: Vec<Abbreviation> = library::prompts()
+        .into_iter()
+        .filter_map(|p| {
+            let trigger = p.abbreviation?.trim().to_string();
+            if trigger.is_empty() {
+                return None;
+            }
+            Some(Abbreviation { trigger, prompt: p })
+        })
+        .collect();
+
+    println!("📋 Loaded {} text expansion abbreviations", abbreviations.len());
+    *ABBREVIATIONS.lock().unwrap() = abbreviations;
+}
+
+fn start(app: &AppHandle) -> Result<(), String> {
+    let mut monitor = MONITOR.lock().unwrap();
+    if monitor.is_some() {
+        return Ok(());
+    }
+
+    let handle = app.clone();
+    let mut buffer = TypedBuffer::default();
+    *monitor = Some(platform::Monitor::start(move |input| {
+        handle_key(&handle, &mut buffer, input)
+    })?);
+
+    println!("✅ Text expansion keystroke monitor started");
+    Ok(())
+}
+
+fn stop() {
+    if let Some(monitor) = MONITOR.lock().unwrap().take() {
+        monitor.stop();
+        println!("🛑 Text expansion keystroke monitor stopped");
+    }
+}
+
+fn handle_key(app: &AppHandle, buffer: &mut TypedBuffer, input: KeyInput) {
+    *LAST_INPUT.lock().unwrap() = Instant::now();
+    if EXPANDING.load(Ordering::SeqCst) {
+        return;
+    }
+
+    match input {
+        KeyInput::Char(c) => {
+            buffer.push(c);
+            let found = buffer.take_match(&ABBREVIATIONS.lock().unwrap());
+            if let Some(abbreviation) = found {
+                expand(app, abbreviation);
+            }
+        }
+        KeyInput::Backspace => buffer.pop(),
+        KeyInput::Reset => buffer.clear(),
+    }
+}
+
+fn expand(app: &AppHandle, abbreviation: Abbreviation) {
+    if let Some(app_name) = crate::get_frontmost_app() {
+        if is_blocklisted(app, &app_name) {
+            println!("⛔ Not expanding '{}' in blocklisted app {}", abbreviation.trigger, app_name);
+            return;
+        }
+    }
+
+    // Check before erasing anything, so a refused expansion leaves the typed
+    // trigger alone.
+    if let Err(e) = safety::check_injection_target() {
+        println!("⛔ Not expanding '{}': {}", abbreviation.trigger, e);
+        return;
+    }
+
+    println!("✨ Expanding '{}' into prompt: {}", abbreviation.trigger, abbreviation.prompt.title);
+    EXPANDING.store(true, Ordering::SeqCst);
+
+    // Typing blocks for a while, so do it off the monitor thread.
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let prompt = &abbreviation.prompt;
+        // Like the bar, fill a required `clipboard` in from the clipboard.
+        let mut values = HashMap::new();
+        let needs_clipboard = template::variables(&prompt.content)
+            .iter()
+            .any(|v| v.name == "clipboard" && v.default.is_none());
+        if needs_clipboard {
+            match app.clipboard().read_text() {
+                Ok(text) => {
+                    values.insert("clipboard".to_string(), text);
+                }
+                Err(e) => println!("⚠️  Failed to read the clipboard: {}", e),
+            }
+        }
+        // Rendered before the trigger is erased, so a prompt that can't be
+        // expanded leaves what the user typed alone.
+        match shell_vars::render(prompt, &values) {
+            Ok(text) => {
+                let result = erase_trigger(&abbreviation.trigger).and_then(|_| {
+                    match crate::inject_and_report(&app, &text, Some(prompt), "expansion") {
+                        // Put back what the user typed rather than swallowing it.
+                        Err(InjectError::Vetoed(_)) => retype_trigger(&abbreviation.trigger),
+                        result => result.map(|_| ()).map_err(String::from),
+                    }
+                });
+                if let Err(e) = result {
+                    println!("❌ Text expansion failed: {}", e);
+                }
+            }
+            Err(e) => println!("⛔ Not expanding '{}': {}", abbreviation.trigger, e),
+        }
+        wait_for_monitor();
+        EXPANDING.store(false, Ordering::SeqCst);
+    });
+}
+
+// Waits until the monitor has seen the keystrokes we typed, so they are
+// dropped rather than fed into the buffer where they could trigger again.
+fn wait_for_monitor() {
+    let deadline = Instant::now() + SETTLE_LIMIT;
+    loop {
+        let quiet = LAST_INPUT.lock().unwrap().elapsed();
+        if quiet >= SETTLE_TIME || Instant::now() >= deadline {
+            return;
+        }
+        std::thread::sleep(SETTLE_TIME - quiet);
+    }
+}
+
+fn retype_trigger(trigger: &str) -> Result<(), String> {
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| format!("Failed to initialize input system: {}", e))?;
+    enigo
+        .text(trigger)
+        .map_err(|e| format!("Failed to restore abbreviation: {}", e))
+}
+
+fn erase_trigger(trigger: &str) -> Result<(), String> {
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| format!("Failed to initialize input system: {}", e))?;
+
+    for _ in trigger.chars() {
+        enigo
+            .key(Key::Backspace, Direction::Click)
+            .map_err(|e| format!("Failed to erase abbreviation: {}", e))?;
+    }
+    Ok(())
+}
+
+// Global keystroke monitoring via the X11 RECORD extension.
+#[cfg(target_os = "linux")]
+mod platform {
+    use x11rb::connection::{Connection, RequestConnection};
+    use x11rb::protocol::record::{self, ConnectionExt as _};
+    use x11rb::protocol::xproto::{self, ConnectionExt as _};
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::x11_utils::TryParse;
+
+    use super::KeyInput;
+
+    // RECORD reply category for protocol data coming from the server.
+    const FROM_SERVER: u8 = 0;
+
+    const XK_BACKSPACE: u32 = 0xff08;
+    const XK_SHIFT_L: u32 = 0xffe1;
+    const XK_SHIFT_R: u32 = 0xffe2;
+    const XK_CAPS_LOCK: u32 = 0xffe5;
+    // Control, Meta, Alt, Super and Hyper (left and right).
+    const XK_SHORTCUT_MODIFIERS: [u32; 10] = [
+        0xffe3, 0xffe4, 0xffe7, 0xffe8, 0xffe9, 0xffea, 0xffeb, 0xffec, 0xffed, 0xffee,
+    ];
+    const XK_ISO_LEVEL3_SHIFT: u32 = 0xfe03;
+
+    pub struct Monitor {
+        ctrl: RustConnection,
+        context: record::Context,
+    }
+
+    impl Monitor {
+        pub fn start<F>(mut on_key: F) -> Result<Self, String>
+        where
+            F: FnMut(KeyInput) + Send + 'static,
+        {
+            // The RECORD spec recommends one connection for control requests and
+            // another one that only reads the recorded protocol data.
+            let (ctrl, _) = x11rb::connect(None)
+                .map_err(|e| format!("Failed to connect to the X server: {}", e))?;
+            let (data, _) = x11rb::connect(None)
+                .map_err(|e| format!("Failed to connect to the X server: {}", e))?;
+
+            let has_record = ctrl
+                .extension_information(record::X11_EXTENSION_NAME)
+                .map_err(|e| format!("Failed to query X extensions: {}", e))?
+                .is_some();
+            if !has_record {
+                return Err("The X server does not support the RECORD extension".to_string());
+            }
+
+            let keymap = Keymap::load(&ctrl)?;
+
+            let context = ctrl
+                .generate_id()
+                .map_err(|e| format!("Failed to allocate a RECORD context: {}", e))?;
+            let empty = record::Range8 { first: 0, last: 0 };
+            let empty_ext = record::ExtRange {
+                major: empty,
+                minor: record::Range16 { first: 0, last: 0 },
+            };
+            let range = record::Range {
+                core_requests: empty,
+                core_replies: empty,
+                ext_requests: empty_ext,
+                ext_replies: empty_ext,
+                delivered_events: empty,
+                device_events: record::Range8 {
+                    first: xproto::KEY_PRESS_EVENT,
+                    last: xproto::BUTTON_PRESS_EVENT,
+                },
+                errors: empty,
+                client_started: false,
+                client_died: false,
+            };
+            ctrl.record_create_context(context, 0, &[record::CS::ALL_CLIENTS.into()], &[range])
+                .map_err(|e| format!("Failed to create RECORD context: {}", e))?
+                .check()
+                .map_err(|e| format!("Failed to create RECORD context: {}", e))?;
+
+            std::thread::spawn(move || {
+                let replies = match data.record_enable_context(context) {
+                    Ok(replies) => replies,
+                    Err(e) => {
+                        println!("❌ Failed to enable RECORD context: {}", e);
+                        return;
+                    }
+                };
+
+                let mut modifiers = ModifierState::default();
+                for reply in replies {
+                    let reply = match reply {
+                        Ok(reply) => reply,
+                        Err(e) => {
+                            println!("❌ Keystroke monitor connection error: {}", e);
+                            break;
+                        }
+                    };
+                    if reply.client_swapped || reply.category != FROM_SERVER {
+                        continue;
+                    }
+
+                    let mut remaining = &reply.data[..];
+                    while remaining.len() >= 32 {
+                        let (event, rest) = remaining.split_at(32);
+                        remaining = rest;
+                        if let Some(input) = translate_event(event, &keymap, &mut modifiers) {
+                            on_key(input);
+                        }
+                    }
+                }
+            });
+
+            Ok(Monitor { ctrl, context })
+        }
+
+        pub fn stop(self) {
+            // Disabling the context makes the server send EndOfData, which ends
+            // the reply iterator on the data connection.
+            let _ = self.ctrl.record_disable_context(self.context);
+            let _ = self.ctrl.record_free_context(self.context);
+            let _ = self.ctrl.flush();
+        }
+    }
+
+    #[derive(Default)]
+    struct ModifierState {
+        shift: bool,
+        // Number of held Control/Alt/Super keys; while any is down keystrokes
+        // are shortcuts rather than text.
+        shortcut_modifiers: usize,
+    }
+
+    struct Keymap {
+        min_keycode: u8,
+        keysyms_per_keycode: usize,
+        keysyms: Vec<u32>,
+    }
+
+    impl Keymap {
+        fn load(conn: &RustConnection) -> Result<Self, String> {
+            let setup = conn.setup();
+            let min_keycode = setup.min_keycode;
+            let count = setup.max_keycode - min_keycode + 1;
+            let reply = conn
+                .get_keyboard_mapping(min_keycode, count)
+                .map_err(|e| format!("Failed to read keyboard mapping: {}", e))?
+                .reply()
+                .map_err(|e| format!("Failed to read keyboard mapping: {}", e))?;
+
+            Ok(Keymap {
+                min_keycode,
+                keysyms_per_keycode: reply.keysyms_per_keycode as usize,
+                keysyms: reply.keysyms,
+            })
+        }
+
+        fn keysym(&self, keycode: u8, shifted: bool) -> u32 {
+            if keycode < self.min_keycode || self.keysyms_per_keycode == 0 {
+                return 0;
+            }
+            let base = (keycode - self.min_keycode) as usize * self.keysyms_per_keycode;
+            let lower = self.keysyms.get(base).copied().unwrap_or(0);
+            if !shifted {
+                return lower;
+            }
+            match self.keysyms.get(base + 1).copied().unwrap_or(0) {
+                0 => lower,
+                upper => upper,
+            }
+        }
+    }
+
+    fn translate_event(
+        event: &[u8],
+        keymap: &Keymap,
+        modifiers: &mut ModifierState,
+    ) -> Option<KeyInput> {
+        match event[0] {
+            xproto::KEY_PRESS_EVENT => {
+                let (event, _) = xproto::KeyPressEvent::try_parse(event).ok()?;
+                let keysym = keymap.keysym(event.detail, false);
+                match keysym {
+                    XK_SHIFT_L | XK_SHIFT_R => {
+                        modifiers.shift = true;
+                        None
+                    }
+                    XK_CAPS_LOCK | XK_ISO_LEVEL3_SHIFT => None,
+                    k if XK_SHORTCUT_MODIFIERS.contains(&k) => {
+                        modifiers.shortcut_modifiers += 1;
+                        None
+                    }
+                    _ if modifiers.shortcut_modifiers > 0 => Some(KeyInput::Reset),
+                    XK_BACKSPACE => Some(KeyInput::Backspace),
+                    _ => {
+                        let keysym = keymap.keysym(event.detail, modifiers.shift);
+                        Some(match keysym_to_char(keysym) {
+                            Some(c) => KeyInput::Char(c),
+                            None => KeyInput::Reset,
+                        })
+                    }
+                }
+            }
+            xproto::KEY_RELEASE_EVENT => {
+                let (event, _) = xproto::KeyReleaseEvent::try_parse(event).ok()?;
+                match keymap.keysym(event.detail, false) {
+                    XK_SHIFT_L | XK_SHIFT_R => modifiers.shift = false,
+                    k if XK_SHORTCUT_MODIFIERS.contains(&k) => {
+                        modifiers.shortcut_modifiers = modifiers.shortcut_modifiers.saturating_sub(1);
+                    }
+                    _ => {}
+                }
+                None
+            }
+            // A click may move the caret or focus another field.
+            xproto::BUTTON_PRESS_EVENT => Some(KeyInput::Reset),
+            _ => None,
+        }
+    }
+
+    fn keysym_to_char(keysym: u32) -> Option<char> {
+        match keysym {
+            // Latin-1 keysyms map directly onto code points.
+            0x20..=0x7e | 0xa0..=0xff => char::from_u32(keysym),
+            // Unicode keysyms carry the code point in the low bits.
+            0x0100_0000..=0x0110_ffff => char::from_u32(keysym - 0x0100_0000),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use super::KeyInput;
+
+    pub struct Monitor;
+
+    impl Monitor {
+        pub fn start<F>(_on_key: F) -> Result<Self, String>
+        where
+            F: FnMut(KeyInput) + Send + 'static,
+        {
+            Err("Text expansion is currently only supported on Linux (X11)".to_string())
+        }
+
+        pub fn stop(self) {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn abbreviation(trigger: &str) -> Abbreviation {
+        Abbreviation {
+            trigger: trigger.to_string(),
+            prompt: Prompt {
+                id: trigger.to_string(),
+                title: trigger.to_string(),
+                content: format!("content of {}", trigger),
+                color: crate::prompts::DEFAULT_COLOR.to_string(),
+                abbreviation: Some(trigger.to_string()),
+                tags: Vec::new(),
+                folder: None,
+            },
+        }
+    }
+
+    fn typed(text: &str) -> TypedBuffer {
+        let mut buffer = TypedBuffer::default();
+        text.chars().for_each(|c| buffer.push(c));
+        buffer
+    }
+
+    #[test]
+    fn matches_the_end_of_what_was_typed() {
+        let abbreviations = [abbreviation(";dbg")];
+        let mut buffer = typed("hello ;db");
+        assert!(buffer.take_match(&abbreviations).is_none());
+        buffer.push('g');
+        let found = buffer.take_match(&abbreviations).unwrap();
+        assert_eq!(found.trigger, ";dbg");
+    }
+
+    #[test]
+    fn a_match_clears_the_buffer() {
+        let abbreviations = [abbreviation(";dbg")];
+        let mut buffer = typed(";dbg");
+        assert!(buffer.take_match(&abbreviations).is_some());
+        assert!(buffer.take_match(&abbreviations).is_none());
+        assert_eq!(buffer.text, "");
+    }
+
+    #[test]
+    fn prefers_the_longest_trigger() {
+        let abbreviations = [abbreviation("g"), abbreviation(";dbg"), abbreviation("bg")];
+        let mut buffer = typed(";dbg");
+        assert_eq!(buffer.take_match(&abbreviations).unwrap().trigger, ";dbg");
+    }
+
+    #[test]
+    fn backspace_removes_the_last_character() {
+        let abbreviations = [abbreviation(";dbg")];
+        let mut buffer = typed(";dbx");
+        buffer.pop();
+        buffer.push('g');
+        assert!(buffer.take_match(&abbreviations).is_some());
+    }
+
+    #[test]
+    fn clear_forgets_everything() {
+        let abbreviations = [abbreviation(";dbg")];
+        let mut buffer = typed(";db");
+        buffer.clear();
+        buffer.push('g');
+        assert!(buffer.take_match(&abbreviations).is_none());
+    }
+
+    #[test]
+    fn keeps_only_the_tail() {
+        let mut buffer = typed(&"é".repeat(BUFFER_CAPACITY + 10));
+        buffer.push('x');
+        assert_eq!(buffer.text.chars().count(), BUFFER_CAPACITY);
+        assert!(buffer.text.ends_with("éx"));
+    }
+}
@@ -0,0 +1,64 @@
+// Focus tracker: polls the frontmost application in the background and tells
+// interested subsystems when it changes. Polling only happens while something
+// actually needs it (currently: a non-empty `shortcutBlocklist`), since asking
+// the OS for the frontmost app is not free on every platform.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{projects, shortcuts};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(750);
+
+static CURRENT_APP: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+// Where typed text would end up, for clients of `ipc`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusTargets {
+    // App that had focus when the bar was last opened; the bar hands focus back
+    // to it before injecting (macOS only).
+    last_app: Option<String>,
+    frontmost_app: Option<String>,
+    window_title: Option<String>,
+    active_project: Option<String>,
+}
+
+pub fn targets() -> FocusTargets {
+    FocusTargets {
+        last_app: crate::LAST_APP_NAME.lock().unwrap().clone(),
+        frontmost_app: crate::get_frontmost_app(),
+        window_title: crate::get_frontmost_window_title(),
+        active_project: projects::active_project().map(|path| path.display().to_string()),
+    }
+}
+
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        if shortcuts::has_blocklist(&app) {
+            let frontmost = crate::get_frontmost_app();
+            let changed = {
+                let mut current = CURRENT_APP.lock().unwrap();
+                if *current != frontmost {
+                    *current = frontmost.clone();
+                    true
+                } else {
+                    false
+                }
+            };
+            if changed {
+                println!("🪟 Frontmost app changed: {}", frontmost.as_deref().unwrap_or("<unknown>"));
+                shortcuts::on_focus_changed(&app, frontmost.as_deref());
+            }
+        } else if CURRENT_APP.lock().unwrap().take().is_some() {
+            // The blocklist was cleared; make sure nothing stays auto-paused.
+            shortcuts::on_focus_changed(&app, None);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    });
+}
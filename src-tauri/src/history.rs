@@ -0,0 +1,351 @@
+// Per-prompt revision history. Every `update_prompt` appends the new state of
+// the prompt, with when and by whom it was saved, to `history/<id>.json` in the
+// app data dir, so any earlier version can be compared with another or brought
+// back. History is kept apart from the library, so it works with every storage
+// backend.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+
+use crate::backups;
+use crate::prompts::Prompt;
+
+// Oldest revisions beyond this are dropped; revision numbers keep counting.
+const MAX_REVISIONS: usize = 100;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Revision {
+    pub revision: u32,
+    // Unix seconds. Unknown for the state a prompt had before it was first
+    // edited with history enabled, or when it was changed outside the app.
+    pub saved_at: Option<u64>,
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverted_from: Option<u32>,
+    pub prompt: Prompt,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    // "equal", "insert" or "delete".
+    kind: &'static str,
+    text: String,
+    // 1-based line numbers in the old and new content.
+    old_line: Option<usize>,
+    new_line: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptDiff {
+    from_revision: u32,
+    to_revision: u32,
+    from_title: String,
+    to_title: String,
+    lines: Vec<DiffLine>,
+}
+
+fn history_path(dir: &Path, id: &str) -> PathBuf {
+    // Ids of hand-written Markdown prompts are file names and may contain
+    // anything; keep them from escaping the directory.
+    dir.join(format!("{}.json", urlencoding::encode(id)))
+}
+
+pub fn load(dir: &Path, id: &str) -> Result<Vec<Revision>, String> {
+    let path = history_path(dir, id);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("{} is corrupted: {}", path.display(), e))
+}
+
+fn save(dir: &Path, id: &str, revisions: &[Revision]) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let contents = serde_json::to_vec_pretty(revisions)
+        .map_err(|e| format!("Failed to serialize history: {}", e))?;
+    backups::write_atomic(&history_path(dir, id), &contents)
+}
+
+// Records that `previous` was replaced by `current`. If the history doesn't
+// end with `previous` (first edit, or a change made outside the app), that
+// state is recorded first so nothing is lost.
+pub fn record(
+    dir: &Path,
+    previous: &Prompt,
+    current: &Prompt,
+    author: Option<String>,
+    reverted_from: Option<u32>,
+) -> Result<(), String> {
+    if previous == current {
+        return Ok(());
+    }
+    let mut revisions = load(dir, &current.id)?;
+    let mut next = revisions.last().map(|r| r.revision + 1).unwrap_or(1);
+
+    if revisions.last().map(|r| &r.prompt) != Some(previous) {
+        revisions.push(Revision {
+            revision: next,
+            saved_at: None,
+            author: None,
+            reverted_from: None,
+            prompt: previous.clone(),
+        });
+        next += 1;
+    }
+    revisions.push(Revision {
+        revision: next,
+        saved_at: Some(backups::unix_now()),
+        author: author.or_else(current_user),
+        reverted_from,
+        prompt: current.clone(),
+    });
+
+    let excess = revisions.len().saturating_sub(MAX_REVISIONS);
+    revisions.drain(..excess);
+    save(dir, &current.id, &revisions)
+}
+
+// Revision history of a prompt, oldest first. The current state is included
+// (as an unsaved last revision) if it isn't the latest recorded one, so the
+// list always ends with what is in the library.
+pub fn with_current(dir: &Path, current: &Prompt) -> Result<Vec<Revision>, String> {
+    let mut revisions = load(dir, &current.id)?;
+    if revisions.last().map(|r| &r.prompt) != Some(current) {
+        revisions.push(Revision {
+            revision: revisions.last().map(|r| r.revision + 1).unwrap_or(1),
+            saved_at: None,
+            author: None,
+            reverted_from: None,
+            prompt: current.clone(),
+        });
+    }
+    Ok(revisions)
+}
+
+pub fn find(revisions: &[Revision], revision: u32) -> Result<&Revision, String> {
+    revisions
+        .iter()
+        .find(|r| r.revision == revision)
+        .ok_or_else(|| format!("No revision {}", revision))
+}
+
+pub fn diff(from: &Revision, to: &Revision) -> PromptDiff {
+    // A missing final newline would make the last line differ from itself.
+    let with_newline = |text: &str| {
+        if text.is_empty() || text.ends_with('\n') {
+            text.to_string()
+        } else {
+            format!("{}\n", text)
+        }
+    };
+    let (old, new) = (with_newline(&from.prompt.content), with_newline(&to.prompt.content));
+    let text_diff = TextDiff::from_lines(&old, &new);
+    let lines = text_diff
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            kind: match change.tag() {
+                ChangeTag::Equal => "equal",
+                ChangeTag::Insert => "insert",
+                ChangeTag::Delete => "delete",
+            },
+            text: change.value().trim_end_matches('\n').to_string(),
+            old_line: change.old_index().map(|i| i + 1),
+            new_line: change.new_index().map(|i| i + 1),
+        })
+        .collect();
+
+    PromptDiff {
+        from_revision: from.revision,
+        to_revision: to.revision,
+        from_title: from.prompt.title.clone(),
+        to_title: to.prompt.title.clone(),
+        lines,
+    }
+}
+
+fn current_user() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|user| !user.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("prompt-buddy-history-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn prompt(content: &str) -> Prompt {
+        Prompt {
+            id: "p1".to_string(),
+            title: "Review".to_string(),
+            content: content.to_string(),
+            color: crate::prompts::DEFAULT_COLOR.to_string(),
+            abbreviation: None,
+            tags: Vec::new(),
+            folder: None,
+        }
+    }
+
+    fn revision(revision: u32, content: &str) -> Revision {
+        Revision {
+            revision,
+            saved_at: None,
+            author: None,
+            reverted_from: None,
+            prompt: prompt(content),
+        }
+    }
+
+    fn contents(revisions: &[Revision]) -> Vec<&str> {
+        revisions
+            .iter()
+            .map(|r| r.prompt.content.as_str())
+            .collect()
+    }
+
+    // Kinds and texts of the diff between two contents.
+    fn diff_of(old: &str, new: &str) -> Vec<(&'static str, String)> {
+        diff(&revision(1, old), &revision(2, new))
+            .lines
+            .into_iter()
+            .map(|line| (line.kind, line.text))
+            .collect()
+    }
+
+    #[test]
+    fn records_the_previous_state_first() {
+        let dir = temp_dir();
+        let author = Some("ada".to_string());
+        record(&dir, &prompt("v1"), &prompt("v2"), author.clone(), None).unwrap();
+        let revisions = load(&dir, "p1").unwrap();
+        assert_eq!(contents(&revisions), ["v1", "v2"]);
+        assert_eq!(revisions[0].revision, 1);
+        assert_eq!(revisions[0].saved_at, None);
+        assert_eq!(revisions[0].author, None);
+        assert_eq!(revisions[1].revision, 2);
+        assert!(revisions[1].saved_at.is_some());
+        assert_eq!(revisions[1].author, author);
+
+        // Continuing from the last revision adds only the new state.
+        record(&dir, &prompt("v2"), &prompt("v3"), author.clone(), Some(1)).unwrap();
+        let revisions = load(&dir, "p1").unwrap();
+        assert_eq!(contents(&revisions), ["v1", "v2", "v3"]);
+        assert_eq!(revisions[2].reverted_from, Some(1));
+
+        // Changed outside the app in between: that state is kept too.
+        record(&dir, &prompt("edited"), &prompt("v4"), author.clone(), None).unwrap();
+        let revisions = load(&dir, "p1").unwrap();
+        assert_eq!(contents(&revisions), ["v1", "v2", "v3", "edited", "v4"]);
+        assert_eq!(revisions[3].saved_at, None);
+        assert_eq!(revisions[4].revision, 5);
+
+        // Saving without a change records nothing.
+        record(&dir, &prompt("v4"), &prompt("v4"), author, None).unwrap();
+        assert_eq!(load(&dir, "p1").unwrap().len(), 5);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn keeps_the_newest_revisions() {
+        let dir = temp_dir();
+        for i in 0..MAX_REVISIONS + 10 {
+            let previous = prompt(&format!("v{}", i));
+            let current = prompt(&format!("v{}", i + 1));
+            record(&dir, &previous, &current, None, None).unwrap();
+        }
+        let revisions = load(&dir, "p1").unwrap();
+        assert_eq!(revisions.len(), MAX_REVISIONS);
+        let last = (MAX_REVISIONS + 11) as u32;
+        assert_eq!(revisions.last().unwrap().revision, last);
+        assert_eq!(revisions[0].revision, last - MAX_REVISIONS as u32 + 1);
+        assert_eq!(revisions[0].prompt.content, "v11");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn keeps_history_files_inside_the_directory() {
+        let dir = temp_dir();
+        let mut previous = prompt("v1");
+        previous.id = "../../escape".to_string();
+        let mut current = previous.clone();
+        current.content = "v2".to_string();
+        record(&dir, &previous, &current, None, None).unwrap();
+
+        let files: Vec<PathBuf> = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+        assert_eq!(files, [dir.join("..%2F..%2Fescape.json")]);
+        assert_eq!(load(&dir, "../../escape").unwrap().len(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn ends_with_the_current_state() {
+        let dir = temp_dir();
+        assert_eq!(
+            contents(&with_current(&dir, &prompt("v1")).unwrap()),
+            ["v1"]
+        );
+        record(&dir, &prompt("v1"), &prompt("v2"), None, None).unwrap();
+        assert_eq!(
+            contents(&with_current(&dir, &prompt("v2")).unwrap()),
+            ["v1", "v2"]
+        );
+        let revisions = with_current(&dir, &prompt("v3")).unwrap();
+        assert_eq!(contents(&revisions), ["v1", "v2", "v3"]);
+        assert_eq!(revisions[2].revision, 3);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn finds_revisions_by_number() {
+        let revisions = [revision(4, "a"), revision(5, "b")];
+        assert_eq!(find(&revisions, 5).unwrap().prompt.content, "b");
+        assert_eq!(find(&revisions, 1).unwrap_err(), "No revision 1");
+    }
+
+    #[test]
+    fn diffs_content_line_by_line() {
+        let from = revision(1, "Keep\nDrop\nSame");
+        let mut to = revision(2, "Keep\nAdd\nSame\n");
+        to.prompt.title = "Renamed".to_string();
+        let diff = diff(&from, &to);
+        assert_eq!((diff.from_revision, diff.to_revision), (1, 2));
+        assert_eq!(
+            (diff.from_title.as_str(), diff.to_title.as_str()),
+            ("Review", "Renamed")
+        );
+
+        let lines: Vec<(&str, &str, Option<usize>, Option<usize>)> = diff
+            .lines
+            .iter()
+            .map(|line| (line.kind, line.text.as_str(), line.old_line, line.new_line))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                ("equal", "Keep", Some(1), Some(1)),
+                ("delete", "Drop", Some(2), None),
+                ("insert", "Add", None, Some(2)),
+                // Only the final newline differs, which isn't a change.
+                ("equal", "Same", Some(3), Some(3)),
+            ]
+        );
+
+        let empty = diff_of("", "One\n");
+        assert_eq!(empty, [("insert", "One".to_string())]);
+    }
+}
@@ -0,0 +1,489 @@
+// User scripts run around every injection (`preInjectHook` and
+// `postInjectHook` in settings.json), for redaction, auditing or notifications.
+// Both are shell command lines (`sh -c` or `cmd /C`).
+//
+// The pre-injection hook gets the rendered text on stdin and its stdout is
+// typed instead; exiting non-zero vetoes the injection, with stderr as the
+// reason. The prompt and the target are in `PROMPT_BUDDY_PROMPT_ID`,
+// `PROMPT_BUDDY_PROMPT_TITLE`, `PROMPT_BUDDY_SOURCE` and
+// `PROMPT_BUDDY_TARGET_APP`. A hook that can't be started, times out or prints
+// too much vetoes too, so a broken redaction script never lets text through.
+//
+// The post-injection hook gets the outcome on stdin and runs in the
+// background, so it can't hold up or change the injection:
+//
+//     { "prompt": { "id", "title" } | null,
+//       "source": "bar" | "api" | "link" | "expansion",
+//       "targetApp": "Slack" | null, "text", "ok", "message"?, "error"? }
+//
+// Both are killed after `injectHookTimeoutSecs`.
+
+use std::io::{Read, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::json;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::prompts::Prompt;
+use crate::InjectError;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 5;
+const MAX_TIMEOUT_SECS: u64 = 300;
+
+// Most a pre-injection hook may print; anything longer is surely a mistake.
+const MAX_OUTPUT: u64 = 1024 * 1024;
+
+static HOOKS: Lazy<Mutex<InjectHooks>> = Lazy::new(|| Mutex::new(InjectHooks::default()));
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InjectHooks {
+    pre_inject: String,
+    post_inject: String,
+    timeout_secs: u64,
+}
+
+impl Default for InjectHooks {
+    fn default() -> Self {
+        InjectHooks {
+            pre_inject: String::new(),
+            post_inject: String::new(),
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+        }
+    }
+}
+
+pub fn init(app: &AppHandle) {
+    let Ok(store) = app.store("settings.json") else {
+        return;
+    };
+    let command = |key: &str| {
+        store
+            .get(key)
+            .and_then(|value| value.as_str().map(|s| s.trim().to_string()))
+            .unwrap_or_default()
+    };
+    let hooks = InjectHooks {
+        pre_inject: command("preInjectHook"),
+        post_inject: command("postInjectHook"),
+        timeout_secs: store
+            .get("injectHookTimeoutSecs")
+            .and_then(|value| value.as_u64())
+            .filter(|secs| (1..=MAX_TIMEOUT_SECS).contains(secs))
+            .unwrap_or(DEFAULT_TIMEOUT_SECS),
+    };
+    if !hooks.pre_inject.is_empty() || !hooks.post_inject.is_empty() {
+        println!("🪝 Injection hooks configured");
+    }
+    *HOOKS.lock().unwrap() = hooks;
+}
+
+#[tauri::command]
+pub async fn get_inject_hooks() -> Result<InjectHooks, String> {
+    Ok(HOOKS.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn update_inject_hooks(
+    app: AppHandle,
+    pre_inject: String,
+    post_inject: String,
+    timeout_secs: u64,
+) -> Result<InjectHooks, String> {
+    if !(1..=MAX_TIMEOUT_SECS).contains(&timeout_secs) {
+        return Err(format!(
+            "The hook timeout must be between 1 and {} seconds",
+            MAX_TIMEOUT_SECS
+        ));
+    }
+    let hooks = InjectHooks {
+        pre_inject: pre_inject.trim().to_string(),
+        post_inject: post_inject.trim().to_string(),
+        timeout_secs,
+    };
+    println!("🔧 Updating injection hooks: {:?}", hooks);
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("Failed to load settings: {}", e))?;
+    store.set("preInjectHook", hooks.pre_inject.clone());
+    store.set("postInjectHook", hooks.post_inject.clone());
+    store.set("injectHookTimeoutSecs", hooks.timeout_secs);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    *HOOKS.lock().unwrap() = hooks.clone();
+    Ok(hooks)
+}
+
+// The text to type instead of `text`, as the pre-injection hook has it.
+pub fn pre_inject(
+    text: &str,
+    prompt: Option<&Prompt>,
+    source: &str,
+) -> Result<String, InjectError> {
+    let hooks = HOOKS.lock().unwrap().clone();
+    if hooks.pre_inject.is_empty() {
+        return Ok(text.to_string());
+    }
+    let env = [
+        (
+            "PROMPT_BUDDY_PROMPT_ID",
+            prompt.map(|p| p.id.clone()).unwrap_or_default(),
+        ),
+        (
+            "PROMPT_BUDDY_PROMPT_TITLE",
+            prompt.map(|p| p.title.clone()).unwrap_or_default(),
+        ),
+        ("PROMPT_BUDDY_SOURCE", source.to_string()),
+        (
+            "PROMPT_BUDDY_TARGET_APP",
+            crate::get_frontmost_app().unwrap_or_default(),
+        ),
+    ];
+    rewrite(
+        &hooks.pre_inject,
+        text,
+        &env,
+        Duration::from_secs(hooks.timeout_secs),
+    )
+}
+
+// Runs the pre-injection hook `command` on `text`.
+fn rewrite(
+    command: &str,
+    text: &str,
+    env: &[(&str, String)],
+    timeout: Duration,
+) -> Result<String, InjectError> {
+    let output = run(
+        "pre-injection",
+        command,
+        text.as_bytes().to_vec(),
+        env,
+        timeout,
+    )
+    .map_err(InjectError::Vetoed)?;
+    if !output.status.success() {
+        let reason = match output.stderr.trim() {
+            "" => format!("The pre-injection hook exited with {}", output.status),
+            stderr => stderr.to_string(),
+        };
+        return Err(InjectError::Vetoed(reason));
+    }
+    let mut rewritten = String::from_utf8(output.stdout).map_err(|_| {
+        InjectError::Vetoed("The pre-injection hook printed invalid UTF-8".to_string())
+    })?;
+    // `echo` and friends end with a newline the prompt didn't have.
+    if !text.ends_with('\n') && rewritten.ends_with('\n') {
+        rewritten.pop();
+        if rewritten.ends_with('\r') {
+            rewritten.pop();
+        }
+    }
+    if rewritten != text {
+        println!("🪝 The pre-injection hook rewrote the text");
+    }
+    Ok(rewritten)
+}
+
+// Hands the outcome of an injection to the post-injection hook.
+pub fn post_inject(
+    text: &str,
+    prompt: Option<&Prompt>,
+    source: &str,
+    result: &Result<String, InjectError>,
+) {
+    let hooks = HOOKS.lock().unwrap().clone();
+    if hooks.post_inject.is_empty() {
+        return;
+    }
+    let mut outcome = json!({
+        "prompt": prompt.map(|p| json!({ "id": p.id, "title": p.title })),
+        "source": source,
+        // Right after typing, the target still has focus.
+        "targetApp": crate::get_frontmost_app(),
+        "text": text,
+        "ok": result.is_ok(),
+    });
+    match result {
+        Ok(message) => outcome["message"] = json!(message),
+        Err(error) => outcome["error"] = json!(error),
+    }
+    std::thread::spawn(move || {
+        let result = run(
+            "post-injection",
+            &hooks.post_inject,
+            outcome.to_string().into_bytes(),
+            &[],
+            Duration::from_secs(hooks.timeout_secs),
+        );
+        if let Err(e) = result {
+            println!("⚠️  {}", e);
+        }
+    });
+}
+
+struct Output {
+    status: ExitStatus,
+    stdout: Vec<u8>,
+    stderr: String,
+}
+
+// Runs `command` with `input` on stdin, killing it after `timeout`, and logs
+// how it went.
+fn run(
+    kind: &str,
+    command: &str,
+    input: Vec<u8>,
+    env: &[(&str, String)],
+    timeout: Duration,
+) -> Result<Output, String> {
+    println!("🪝 Running {} hook: {}", kind, command);
+    let started = Instant::now();
+    let mut child = shell(command)
+        .envs(env.iter().map(|(key, value)| (*key, value)))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start the {} hook: {}", kind, e))?;
+
+    let mut stdin = child.stdin.take();
+    std::thread::spawn(move || {
+        // A hook that doesn't read its input closes the pipe early.
+        if let Some(stdin) = stdin.as_mut() {
+            let _ = stdin.write_all(&input);
+        }
+    });
+    // Read on other threads, so a full pipe can't stall the hook and a
+    // background process holding the pipe open can't stall us.
+    let (stdout_sender, stdout) = mpsc::channel();
+    if let Some(pipe) = child.stdout.take() {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let read = pipe.take(MAX_OUTPUT + 1).read_to_end(&mut buffer);
+            let _ = stdout_sender.send(read.map(|_| buffer));
+        });
+    }
+    let (stderr_sender, stderr) = mpsc::channel();
+    if let Some(mut pipe) = child.stderr.take() {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = pipe.read_to_end(&mut buffer);
+            let _ = stderr_sender.send(String::from_utf8_lossy(&buffer).into_owned());
+        });
+    }
+
+    let deadline = started + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                let error = format!("The {} hook timed out after {:?}", kind, timeout);
+                println!("⏱️  {}", error);
+                return Err(error);
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+            Err(e) => return Err(format!("Failed to wait for the {} hook: {}", kind, e)),
+        }
+    };
+    let remaining = || deadline.saturating_duration_since(Instant::now());
+    let stdout = stdout
+        .recv_timeout(remaining())
+        .map_err(|_| format!("The {} hook left its output open", kind))?
+        .map_err(|e| format!("Failed to read the {} hook's output: {}", kind, e))?;
+    let stderr = stderr.recv_timeout(remaining()).unwrap_or_default();
+
+    println!(
+        "🪝 The {} hook exited with {} after {:?}",
+        kind,
+        status,
+        started.elapsed()
+    );
+    for line in stderr.lines().filter(|line| !line.trim().is_empty()) {
+        println!("   {}", line);
+    }
+    if stdout.len() as u64 > MAX_OUTPUT {
+        return Err(format!(
+            "The {} hook printed more than {} bytes",
+            kind, MAX_OUTPUT
+        ));
+    }
+    Ok(Output {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(target_os = "windows")]
+fn shell(command: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+
+    // No console window flashing up for every injection.
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let mut shell = Command::new("cmd");
+    shell
+        .arg("/C")
+        .raw_arg(command)
+        .creation_flags(CREATE_NO_WINDOW);
+    shell
+}
+
+// The hooks below are `sh` command lines.
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn vetoed(result: Result<String, InjectError>) -> String {
+        match result {
+            Err(InjectError::Vetoed(reason)) => reason,
+            other => panic!("expected a veto, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn types_what_the_hook_prints() {
+        assert_eq!(
+            rewrite("cat", "Hello\nworld", &[], TIMEOUT).unwrap(),
+            "Hello\nworld"
+        );
+        assert_eq!(rewrite("cat", "Hello\n", &[], TIMEOUT).unwrap(), "Hello\n");
+        assert_eq!(
+            rewrite("sed 's/secret/[redacted]/'", "my secret key", &[], TIMEOUT).unwrap(),
+            "my [redacted] key"
+        );
+        // `echo`'s newline isn't part of the text.
+        assert_eq!(
+            rewrite("echo Replaced", "Hello", &[], TIMEOUT).unwrap(),
+            "Replaced"
+        );
+        let env = [("PROMPT_BUDDY_SOURCE", "bar".to_string())];
+        assert_eq!(
+            rewrite(
+                "printf '%s' \"$PROMPT_BUDDY_SOURCE\"",
+                "Hello",
+                &env,
+                TIMEOUT
+            )
+            .unwrap(),
+            "bar"
+        );
+    }
+
+    #[test]
+    fn vetoes_on_a_failing_exit() {
+        assert_eq!(
+            vetoed(rewrite(
+                "echo 'Contains a token' >&2; exit 3",
+                "Hello",
+                &[],
+                TIMEOUT
+            )),
+            "Contains a token"
+        );
+        let reason = vetoed(rewrite("cat; exit 3", "Hello", &[], TIMEOUT));
+        assert!(
+            reason.starts_with("The pre-injection hook exited with"),
+            "{}",
+            reason
+        );
+        assert!(reason.contains('3'), "{}", reason);
+    }
+
+    #[test]
+    fn vetoes_when_the_hook_times_out() {
+        let started = Instant::now();
+        let reason = vetoed(rewrite(
+            "sleep 10",
+            "Hello",
+            &[],
+            Duration::from_millis(200),
+        ));
+        assert!(reason.contains("timed out"), "{}", reason);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // Done, but a background process still holds stdout open.
+        let started = Instant::now();
+        let reason = vetoed(rewrite(
+            "sleep 10 & echo Hi",
+            "Hello",
+            &[],
+            Duration::from_millis(500),
+        ));
+        assert!(reason.contains("left its output open"), "{}", reason);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn vetoes_large_or_invalid_output() {
+        let command = format!("head -c {} /dev/zero", MAX_OUTPUT + 1);
+        let reason = vetoed(rewrite(&command, "Hello", &[], TIMEOUT));
+        assert!(reason.contains("printed more than"), "{}", reason);
+        let command = format!("head -c {} /dev/zero", MAX_OUTPUT);
+        assert_eq!(
+            rewrite(&command, "Hello", &[], TIMEOUT).unwrap().len() as u64,
+            MAX_OUTPUT
+        );
+
+        let reason = vetoed(rewrite("printf '\\377'", "Hello", &[], TIMEOUT));
+        assert!(reason.contains("invalid UTF-8"), "{}", reason);
+    }
+
+    #[test]
+    fn hands_the_outcome_to_the_post_injection_hook() {
+        let dir = std::env::temp_dir().join(format!("prompt-buddy-hooks-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("outcome.json");
+        *HOOKS.lock().unwrap() = InjectHooks {
+            pre_inject: String::new(),
+            post_inject: format!("cat > '{}'", log.display()),
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+        };
+
+        // Without a pre-injection hook the text goes through as is.
+        assert_eq!(pre_inject("Hello", None, "api").unwrap(), "Hello");
+        post_inject("Hello", None, "api", &Ok("Injected".to_string()));
+
+        let started = Instant::now();
+        let outcome = loop {
+            let written = std::fs::read_to_string(&log)
+                .ok()
+                .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok());
+            if let Some(outcome) = written {
+                break outcome;
+            }
+            assert!(
+                started.elapsed() < TIMEOUT,
+                "the post-injection hook never ran"
+            );
+            std::thread::sleep(Duration::from_millis(20));
+        };
+        assert_eq!(outcome["prompt"], serde_json::Value::Null);
+        assert_eq!(outcome["source"], "api");
+        assert_eq!(outcome["text"], "Hello");
+        assert_eq!(outcome["ok"], true);
+        assert_eq!(outcome["message"], "Injected");
+        *HOOKS.lock().unwrap() = InjectHooks::default();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
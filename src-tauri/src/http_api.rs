@@ -0,0 +1,1004 @@
+// Opt-in REST API on http://127.0.0.1:<port> for tools that only speak HTTP
+// (`httpApiEnabled` and `httpApiPort` in settings.json). It serves the same
+// operations as the JSON-RPC socket (see `ipc`):
+//
+//     GET    /v1/prompts                 list
+//     POST   /v1/prompts                 create { title, content, tags?, folder? }
+//     GET    /v1/prompts/{id}            get
+//     PATCH  /v1/prompts/{id}            update { title?, content?, tags?, folder?, color?,
+//                                                 abbreviation? }
+//     DELETE /v1/prompts/{id}            delete
+//     POST   /v1/prompts/{id}/render     { vars? } -> { text }
+//     POST   /v1/prompts/{id}/inject     { vars? } -> { message }
+//     GET    /openapi.json               the OpenAPI description (no token needed)
+//
+// Every other request needs `Authorization: Bearer <httpApiToken>`; the token
+// is generated the first time the API is enabled. Browsers are kept out unless
+// their origin is listed in `httpApiAllowedOrigins`: requests carrying any other
+// `Origin` are refused before anything else, bodies must be JSON (which a page
+// can't send cross-origin without a preflight), and a `Host` other than
+// 127.0.0.1 or localhost is refused so DNS rebinding can't get around that.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::ipc::{self, Request};
+use crate::library;
+
+const DEFAULT_PORT: u16 = 27182;
+
+const MAX_LINE: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+const MAX_BODY: usize = 1024 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+static SERVER: Lazy<Mutex<Option<Server>>> = Lazy::new(|| Mutex::new(None));
+
+struct Server {
+    port: u16,
+    stop: Arc<AtomicBool>,
+    thread: std::thread::JoinHandle<()>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpApiSettings {
+    enabled: bool,
+    port: u16,
+    token: Option<String>,
+    allowed_origins: Vec<String>,
+    // Base URL while the server is running.
+    url: Option<String>,
+}
+
+// What a running server checks requests against.
+struct Config {
+    port: u16,
+    token: String,
+    allowed_origins: Vec<String>,
+}
+
+fn load_settings(app: &AppHandle) -> HttpApiSettings {
+    let store = app.store("settings.json").ok();
+    let get = |key: &str| store.as_ref().and_then(|s| s.get(key));
+    let port = get("httpApiPort")
+        .and_then(|value| value.as_u64())
+        .and_then(|port| u16::try_from(port).ok())
+        .filter(|port| *port != 0)
+        .unwrap_or(DEFAULT_PORT);
+    HttpApiSettings {
+        enabled: get("httpApiEnabled")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false),
+        port,
+        token: get("httpApiToken")
+            .and_then(|value| value.as_str().map(|s| s.to_string()))
+            .filter(|token| !token.is_empty()),
+        allowed_origins: get("httpApiAllowedOrigins")
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default(),
+        url: SERVER
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|server| format!("http://127.0.0.1:{}", server.port)),
+    }
+}
+
+fn new_token() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+// `scheme://host[:port]`, exactly as browsers send it in `Origin`.
+fn normalize_origin(origin: &str) -> Result<String, String> {
+    let origin = origin.trim().trim_end_matches('/');
+    let valid = origin.split_once("://").is_some_and(|(scheme, rest)| {
+        !scheme.is_empty() && !rest.is_empty() && !rest.contains(['/', '?', '#', '*', ' '])
+    });
+    if valid {
+        Ok(origin.to_lowercase())
+    } else {
+        Err(format!(
+            "'{}' is not an origin like https://example.com",
+            origin
+        ))
+    }
+}
+
+pub fn init(app: &AppHandle) {
+    let settings = load_settings(app);
+    if !settings.enabled {
+        println!("ℹ️  HTTP API is disabled");
+        return;
+    }
+    let Some(token) = settings.token else {
+        println!("⚠️  HTTP API is enabled but has no token; enable it again in the settings");
+        return;
+    };
+    let config = Config {
+        port: settings.port,
+        token,
+        allowed_origins: settings.allowed_origins,
+    };
+    if let Err(e) = start(app, config) {
+        println!("❌ {}", e);
+    }
+}
+
+fn start(app: &AppHandle, config: Config) -> Result<(), String> {
+    stop();
+    let listener = TcpListener::bind(("127.0.0.1", config.port)).map_err(|e| {
+        format!(
+            "Failed to start the HTTP API on port {}: {}",
+            config.port, e
+        )
+    })?;
+    println!("🌐 HTTP API listening on http://127.0.0.1:{}", config.port);
+
+    let app = app.clone();
+    let port = config.port;
+    let config = Arc::new(config);
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    let thread = std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            if stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let app = app.clone();
+            let config = config.clone();
+            std::thread::spawn(move || serve(&app, &config, stream));
+        }
+        println!("🌐 HTTP API stopped");
+    });
+    *SERVER.lock().unwrap() = Some(Server { port, stop, thread });
+    Ok(())
+}
+
+fn stop() {
+    if let Some(server) = SERVER.lock().unwrap().take() {
+        server.stop.store(true, Ordering::SeqCst);
+        // Wakes the accept loop up so it sees the flag, and waits for it to
+        // let go of the port.
+        let _ = TcpStream::connect(("127.0.0.1", server.port));
+        let _ = server.thread.join();
+    }
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+struct Reply {
+    status: u16,
+    body: Option<Value>,
+    headers: Vec<(&'static str, String)>,
+}
+
+impl Reply {
+    fn json(status: u16, body: Value) -> Self {
+        Reply {
+            status,
+            body: Some(body),
+            headers: Vec::new(),
+        }
+    }
+
+    fn empty(status: u16) -> Self {
+        Reply {
+            status,
+            body: None,
+            headers: Vec::new(),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Reply::json(status, json!({ "error": message.into() }))
+    }
+
+    fn with_header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Content Too Large",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Content",
+        431 => "Request Header Fields Too Large",
+        501 => "Not Implemented",
+        _ => "Internal Server Error",
+    }
+}
+
+fn serve(app: &AppHandle, config: &Config, stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let reply = match read_request(&stream) {
+        Ok(request) => respond(app, config, &request),
+        Err(reply) => reply,
+    };
+    let mut writer = &stream;
+    let _ = write_reply(&mut writer, reply);
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<String, Reply> {
+    let mut line = String::new();
+    reader
+        .take(MAX_LINE)
+        .read_line(&mut line)
+        .map_err(|_| Reply::error(400, "Malformed request"))?;
+    if !line.ends_with('\n') {
+        return Err(Reply::error(431, "Request line or header too long"));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn read_request(stream: &TcpStream) -> Result<HttpRequest, Reply> {
+    let mut reader = BufReader::new(stream);
+    let request_line = read_line(&mut reader)?;
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(target), Some(version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(Reply::error(400, "Malformed request line"));
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(Reply::error(400, "Only HTTP/1.x is supported"));
+    }
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(&mut reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(Reply::error(431, "Too many headers"));
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| Reply::error(400, "Malformed header"))?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+    let mut request = HttpRequest {
+        method: method.to_string(),
+        path: target.split('?').next().unwrap_or_default().to_string(),
+        headers,
+        body: Vec::new(),
+    };
+
+    if request.header("Transfer-Encoding").is_some() {
+        return Err(Reply::error(501, "Chunked bodies are not supported"));
+    }
+    let length = match request.header("Content-Length") {
+        Some(length) => length
+            .parse::<usize>()
+            .map_err(|_| Reply::error(400, "Invalid Content-Length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err(Reply::error(413, "Request body too large"));
+    }
+    request.body = vec![0; length];
+    reader
+        .read_exact(&mut request.body)
+        .map_err(|_| Reply::error(400, "Incomplete request body"))?;
+    Ok(request)
+}
+
+fn write_reply(writer: &mut impl Write, reply: Reply) -> std::io::Result<()> {
+    let body = reply.body.map(|body| body.to_string()).unwrap_or_default();
+    let mut head = format!("HTTP/1.1 {} {}\r\n", reply.status, reason(reply.status));
+    if !body.is_empty() {
+        head.push_str("Content-Type: application/json\r\n");
+    }
+    head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    head.push_str("Cache-Control: no-store\r\nConnection: close\r\n");
+    for (name, value) in reply.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    writer.write_all(head.as_bytes())?;
+    writer.write_all(body.as_bytes())?;
+    writer.flush()
+}
+
+// Compares in constant time so the token can't be guessed byte by byte.
+fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn respond(app: &AppHandle, config: &Config, request: &HttpRequest) -> Reply {
+    let origin = match check_caller(config, request) {
+        Ok(origin) => origin,
+        Err(reply) => return reply,
+    };
+    let reply = if request.method == "OPTIONS" {
+        Reply::empty(204)
+            .with_header("Access-Control-Allow-Methods", "GET, POST, PATCH, DELETE")
+            .with_header(
+                "Access-Control-Allow-Headers",
+                "Authorization, Content-Type",
+            )
+            .with_header("Access-Control-Max-Age", "600")
+    } else {
+        match parse_call(config, request) {
+            Ok(call) => execute(app, config, call),
+            Err(reply) => reply,
+        }
+    };
+    match origin {
+        Some(origin) => reply
+            .with_header("Access-Control-Allow-Origin", origin)
+            .with_header("Vary", "Origin"),
+        None => reply,
+    }
+}
+
+// Refuses requests that aren't addressed to this machine or come from a page
+// outside the allowed origins, and returns the origin to answer, if any.
+fn check_caller(config: &Config, request: &HttpRequest) -> Result<Option<String>, Reply> {
+    let host = request.header("Host").unwrap_or_default();
+    let local_hosts = [
+        format!("127.0.0.1:{}", config.port),
+        format!("localhost:{}", config.port),
+    ];
+    if !local_hosts
+        .iter()
+        .any(|local| host.eq_ignore_ascii_case(local))
+    {
+        return Err(Reply::error(403, "Unexpected Host header"));
+    }
+
+    // Pages from anywhere but the allowed origins get nothing, not even an
+    // error they could read.
+    match request.header("Origin") {
+        Some(origin) => {
+            let allowed = normalize_origin(origin)
+                .is_ok_and(|origin| config.allowed_origins.contains(&origin));
+            if !allowed {
+                return Err(Reply::error(403, "Origin not allowed"));
+            }
+            Ok(Some(origin.to_string()))
+        }
+        None => Ok(None),
+    }
+}
+
+// What an admitted request asks for.
+enum Call {
+    OpenApi,
+    // An `ipc` method, on the prompt with the given id if any.
+    Ipc {
+        method: &'static str,
+        id: Option<String>,
+        params: Value,
+    },
+}
+
+// Checks the token and the body of a request and works out what it asks for.
+fn parse_call(config: &Config, request: &HttpRequest) -> Result<Call, Reply> {
+    if request.method == "GET" && request.path == "/openapi.json" {
+        return Ok(Call::OpenApi);
+    }
+
+    let authorized = request
+        .header("Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| same_token(token.trim(), &config.token));
+    if !authorized {
+        return Err(Reply::error(401, "Missing or invalid bearer token")
+            .with_header("WWW-Authenticate", "Bearer"));
+    }
+
+    let params = parse_body(request)?;
+    let segments: Vec<&str> = request
+        .path
+        .trim_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let (method, id) = match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["v1", "prompts"]) => ("prompts.list", None),
+        ("POST", ["v1", "prompts"]) => ("prompts.create", None),
+        ("GET", ["v1", "prompts", id]) => ("prompts.get", Some(id)),
+        ("PATCH", ["v1", "prompts", id]) => ("prompts.update", Some(id)),
+        ("DELETE", ["v1", "prompts", id]) => ("prompts.delete", Some(id)),
+        ("POST", ["v1", "prompts", id, "render"]) => ("prompts.render", Some(id)),
+        ("POST", ["v1", "prompts", id, "inject"]) => ("prompts.inject", Some(id)),
+        (_, ["v1", "prompts", ..]) => return Err(Reply::error(405, "Method not allowed")),
+        _ => return Err(Reply::error(404, "Not found")),
+    };
+    let id = id.map(|id| {
+        urlencoding::decode(id)
+            .map(|id| id.into_owned())
+            .unwrap_or_default()
+    });
+    Ok(Call::Ipc { method, id, params })
+}
+
+fn execute(app: &AppHandle, config: &Config, call: Call) -> Reply {
+    let (method, id, mut params) = match call {
+        Call::OpenApi => return Reply::json(200, openapi(config.port)),
+        Call::Ipc { method, id, params } => (method, id, params),
+    };
+    if let Some(id) = id {
+        // Only ids here: a title could name several prompts.
+        if library::current_prompt(&id).is_err() {
+            return Reply::error(404, format!("No prompt with id '{}'", id));
+        }
+        params["prompt"] = Value::from(id);
+    }
+    let request = match ipc::parse(method, params) {
+        Ok(request) => request,
+        Err(error) => return Reply::error(400, error.message),
+    };
+    let status = match request {
+        Request::Create { .. } => 201,
+        Request::Delete { .. } => 204,
+        _ => 200,
+    };
+    let wrap = match request {
+        Request::Render { .. } => Some("text"),
+        Request::Inject { .. } => Some("message"),
+        _ => None,
+    };
+    match ipc::execute(app, request) {
+        Ok(_) if status == 204 => Reply::empty(204),
+        Ok(result) => match wrap {
+            Some(key) => Reply::json(status, json!({ key: result })),
+            None => Reply::json(status, result),
+        },
+        Err(e) => Reply::error(422, e),
+    }
+}
+
+// The JSON object in the body; an empty body is an empty object.
+fn parse_body(request: &HttpRequest) -> Result<Value, Reply> {
+    if request.body.is_empty() {
+        return Ok(json!({}));
+    }
+    let is_json = request
+        .header("Content-Type")
+        .is_some_and(|value| value.to_lowercase().starts_with("application/json"));
+    if !is_json {
+        return Err(Reply::error(415, "Bodies must be application/json"));
+    }
+    match serde_json::from_slice(&request.body) {
+        Ok(Value::Object(body)) => Ok(Value::Object(body)),
+        Ok(_) => Err(Reply::error(400, "The body must be a JSON object")),
+        Err(e) => Err(Reply::error(400, format!("Invalid JSON: {}", e))),
+    }
+}
+
+// `{ "application/json": { "schema": { "$ref": ... } } }` for a schema below.
+fn json_content(schema: &str) -> Value {
+    let schema = json!({ "$ref": format!("#/components/schemas/{}", schema) });
+    json!({ "application/json": { "schema": schema } })
+}
+
+fn openapi(port: u16) -> Value {
+    let prompt_id = json!({
+        "name": "id",
+        "in": "path",
+        "required": true,
+        "schema": { "type": "string" }
+    });
+    let error = json!({ "$ref": "#/components/responses/Error" });
+    let prompt = json!({
+        "description": "The prompt",
+        "content": json_content("Prompt")
+    });
+    let vars = json!({
+        "content": json_content("Variables")
+    });
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Prompt Buddy",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "The prompt library of the running app. Prompt content may contain \
+                            {{name}} and {{name|default}} variables."
+        },
+        "servers": [{ "url": format!("http://127.0.0.1:{}", port) }],
+        "security": [{ "bearer": [] }],
+        "paths": {
+            "/v1/prompts": {
+                "get": {
+                    "summary": "List all prompts, in bar order",
+                    "responses": {
+                        "200": {
+                            "description": "The prompts",
+                            "content": { "application/json": { "schema": {
+                                "type": "array",
+                                "items": { "$ref": "#/components/schemas/Prompt" }
+                            } } }
+                        },
+                        "401": error
+                    }
+                },
+                "post": {
+                    "summary": "Add a prompt at the end of the library",
+                    "requestBody": {
+                        "required": true,
+                        "content": json_content("NewPrompt")
+                    },
+                    "responses": { "201": prompt, "400": error, "401": error, "422": error }
+                }
+            },
+            "/v1/prompts/{id}": {
+                "parameters": [prompt_id],
+                "get": {
+                    "summary": "Get a prompt",
+                    "responses": { "200": prompt, "401": error, "404": error }
+                },
+                "patch": {
+                    "summary": "Change some fields of a prompt",
+                    "requestBody": {
+                        "required": true,
+                        "content": json_content("PromptChanges")
+                    },
+                    "responses": {
+                        "200": prompt,
+                        "400": error,
+                        "401": error,
+                        "404": error,
+                        "422": error
+                    }
+                },
+                "delete": {
+                    "summary": "Move a prompt to the trash",
+                    "responses": {
+                        "204": { "description": "Deleted" },
+                        "401": error,
+                        "404": error
+                    }
+                }
+            },
+            "/v1/prompts/{id}/render": {
+                "parameters": [prompt_id],
+                "post": {
+                    "summary": "Fill in the variables of a prompt",
+                    "requestBody": vars,
+                    "responses": {
+                        "200": {
+                            "description": "The rendered text",
+                            "content": { "application/json": { "schema": {
+                                "type": "object",
+                                "properties": { "text": { "type": "string" } }
+                            } } }
+                        },
+                        "401": error,
+                        "404": error,
+                        "422": error
+                    }
+                }
+            },
+            "/v1/prompts/{id}/inject": {
+                "parameters": [prompt_id],
+                "post": {
+                    "summary": "Render a prompt and type it into the focused window",
+                    "requestBody": vars,
+                    "responses": {
+                        "200": {
+                            "description": "Injected",
+                            "content": { "application/json": { "schema": {
+                                "type": "object",
+                                "properties": { "message": { "type": "string" } }
+                            } } }
+                        },
+                        "401": error,
+                        "404": error,
+                        "422": error
+                    }
+                }
+            }
+        },
+        "components": {
+            "securitySchemes": {
+                "bearer": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "httpApiToken from settings.json, shown in the settings window"
+                }
+            },
+            "responses": {
+                "Error": {
+                    "description": "What went wrong",
+                    "content": { "application/json": { "schema": {
+                        "type": "object",
+                        "properties": { "error": { "type": "string" } }
+                    } } }
+                }
+            },
+            "schemas": {
+                "Prompt": {
+                    "type": "object",
+                    "required": ["id", "title", "content", "color"],
+                    "properties": {
+                        "id": { "type": "string" },
+                        "title": { "type": "string" },
+                        "content": { "type": "string" },
+                        "color": { "type": "string" },
+                        "abbreviation": { "type": "string" },
+                        "tags": { "type": "array", "items": { "type": "string" } },
+                        "folder": {
+                            "type": "string",
+                            "description": "Slash-separated, e.g. Work/Reviews"
+                        }
+                    }
+                },
+                "NewPrompt": {
+                    "type": "object",
+                    "required": ["title", "content"],
+                    "properties": {
+                        "title": { "type": "string" },
+                        "content": { "type": "string" },
+                        "tags": { "type": "array", "items": { "type": "string" } },
+                        "folder": { "type": "string" }
+                    }
+                },
+                "PromptChanges": {
+                    "type": "object",
+                    "description": "Fields left out stay as they are. An empty folder is \
+                                    the top level and an empty abbreviation removes it.",
+                    "properties": {
+                        "title": { "type": "string" },
+                        "content": { "type": "string" },
+                        "tags": { "type": "array", "items": { "type": "string" } },
+                        "folder": { "type": "string" },
+                        "color": { "type": "string" },
+                        "abbreviation": { "type": "string" }
+                    }
+                },
+                "Variables": {
+                    "type": "object",
+                    "properties": {
+                        "vars": {
+                            "type": "object",
+                            "additionalProperties": { "type": "string" },
+                            "description": "Values by variable name; variables with a \
+                                            default may be left out"
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[tauri::command]
+pub async fn get_http_api_settings(app: AppHandle) -> Result<HttpApiSettings, String> {
+    Ok(load_settings(&app))
+}
+
+// Saves the settings and starts, restarts or stops the server to match.
+#[tauri::command]
+pub async fn update_http_api_settings(
+    app: AppHandle,
+    enabled: bool,
+    port: u16,
+    allowed_origins: Vec<String>,
+) -> Result<HttpApiSettings, String> {
+    if port == 0 {
+        return Err("Port must be between 1 and 65535".to_string());
+    }
+    let allowed_origins = allowed_origins
+        .iter()
+        .filter(|origin| !origin.trim().is_empty())
+        .map(|origin| normalize_origin(origin))
+        .collect::<Result<Vec<_>, _>>()?;
+    let token = load_settings(&app).token.unwrap_or_else(new_token);
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("Failed to load settings: {}", e))?;
+    store.set("httpApiEnabled", enabled);
+    store.set("httpApiPort", port);
+    store.set("httpApiAllowedOrigins", allowed_origins.clone());
+    if enabled {
+        store.set("httpApiToken", token.clone());
+    }
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    println!("🔧 HTTP API enabled: {} (port {})", enabled, port);
+    if enabled {
+        start(
+            &app,
+            Config {
+                port,
+                token,
+                allowed_origins,
+            },
+        )?;
+    } else {
+        stop();
+    }
+    Ok(load_settings(&app))
+}
+
+// Replaces the token, locking out every client that has the old one.
+#[tauri::command]
+pub async fn regenerate_http_api_token(app: AppHandle) -> Result<HttpApiSettings, String> {
+    let token = new_token();
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("Failed to load settings: {}", e))?;
+    store.set("httpApiToken", token);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+    println!("🔑 Generated a new HTTP API token");
+    if SERVER.lock().unwrap().is_some() {
+        init(&app);
+    }
+    Ok(load_settings(&app))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn config() -> Config {
+        Config {
+            port: 27182,
+            token: TOKEN.to_string(),
+            allowed_origins: vec!["https://tools.example.com".to_string()],
+        }
+    }
+
+    fn request(method: &str, path: &str, headers: &[(&str, &str)], body: &str) -> HttpRequest {
+        HttpRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    // A request from a local tool with the right token.
+    fn authorized(method: &str, path: &str, body: &str) -> HttpRequest {
+        let bearer = format!("Bearer {}", TOKEN);
+        request(
+            method,
+            path,
+            &[
+                ("Host", "127.0.0.1:27182"),
+                ("Authorization", &bearer),
+                ("Content-Type", "application/json"),
+            ],
+            body,
+        )
+    }
+
+    fn refused<T>(result: Result<T, Reply>) -> Option<u16> {
+        result.err().map(|reply| reply.status)
+    }
+
+    #[test]
+    fn compares_tokens() {
+        assert!(same_token(TOKEN, TOKEN));
+        assert!(!same_token("0123456789abcdeF", TOKEN));
+        assert!(!same_token("0123456789abcde", TOKEN));
+        assert!(!same_token("", TOKEN));
+    }
+
+    #[test]
+    fn refuses_foreign_hosts() {
+        for host in [
+            None,
+            Some("evil.example.com"),
+            Some("evil.example.com:27182"),
+            Some("127.0.0.1"),
+            Some("127.0.0.1:8080"),
+            Some("localhost.evil.example.com:27182"),
+        ] {
+            let headers: Vec<(&str, &str)> = host.map(|host| ("Host", host)).into_iter().collect();
+            let request = request("GET", "/v1/prompts", &headers, "");
+            assert_eq!(
+                refused(check_caller(&config(), &request)),
+                Some(403),
+                "{:?}",
+                host
+            );
+        }
+        for host in ["127.0.0.1:27182", "LocalHost:27182"] {
+            let request = request("GET", "/v1/prompts", &[("Host", host)], "");
+            assert!(
+                matches!(check_caller(&config(), &request), Ok(None)),
+                "{}",
+                host
+            );
+        }
+    }
+
+    #[test]
+    fn refuses_origins_off_the_allowlist() {
+        for origin in [
+            "https://evil.example.com",
+            "https://tools.example.com.evil.example.com",
+            "http://tools.example.com",
+            "null",
+            "",
+        ] {
+            let request = request(
+                "OPTIONS",
+                "/v1/prompts",
+                &[("Host", "localhost:27182"), ("Origin", origin)],
+                "",
+            );
+            assert_eq!(
+                refused(check_caller(&config(), &request)),
+                Some(403),
+                "{}",
+                origin
+            );
+        }
+        let request = request(
+            "OPTIONS",
+            "/v1/prompts",
+            &[
+                ("Host", "localhost:27182"),
+                ("Origin", "https://Tools.Example.com/"),
+            ],
+            "",
+        );
+        assert!(check_caller(&config(), &request).is_ok_and(|origin| origin.is_some()));
+    }
+
+    #[test]
+    fn refuses_unauthenticated_requests() {
+        let wrong = format!("Bearer {}x", TOKEN);
+        let lowercase = format!("bearer {}", TOKEN);
+        let bare = TOKEN.to_string();
+        for authorization in [
+            None,
+            Some(wrong.as_str()),
+            Some(lowercase.as_str()),
+            Some(bare.as_str()),
+            Some("Bearer "),
+        ] {
+            for (method, path) in [
+                ("GET", "/v1/prompts"),
+                ("POST", "/v1/prompts/abc/inject"),
+                ("DELETE", "/v1/prompts/abc"),
+                ("GET", "/nowhere"),
+                ("POST", "/openapi.json"),
+                ("GET", "/openapi.json/"),
+            ] {
+                let mut headers = vec![("Host", "127.0.0.1:27182")];
+                headers.extend(authorization.map(|value| ("Authorization", value)));
+                let request = request(method, path, &headers, "");
+                assert_eq!(
+                    refused(parse_call(&config(), &request)),
+                    Some(401),
+                    "{} {} with {:?}",
+                    method,
+                    path,
+                    authorization
+                );
+            }
+        }
+        let request = request("GET", "/openapi.json", &[("Host", "127.0.0.1:27182")], "");
+        assert!(matches!(parse_call(&config(), &request), Ok(Call::OpenApi)));
+    }
+
+    #[test]
+    fn refuses_bodies_that_are_not_json() {
+        let bearer = format!("Bearer {}", TOKEN);
+        let form = request(
+            "POST",
+            "/v1/prompts/abc/inject",
+            &[
+                ("Host", "127.0.0.1:27182"),
+                ("Authorization", &bearer),
+                ("Content-Type", "text/plain"),
+            ],
+            "{\"vars\": {}}",
+        );
+        assert_eq!(refused(parse_call(&config(), &form)), Some(415));
+        let untyped = request(
+            "POST",
+            "/v1/prompts",
+            &[("Host", "127.0.0.1:27182"), ("Authorization", &bearer)],
+            "title=x",
+        );
+        assert_eq!(refused(parse_call(&config(), &untyped)), Some(415));
+        for body in ["not json", "[1, 2]", "\"text\""] {
+            let request = authorized("POST", "/v1/prompts", body);
+            assert_eq!(
+                refused(parse_call(&config(), &request)),
+                Some(400),
+                "{}",
+                body
+            );
+        }
+    }
+
+    #[test]
+    fn routes_authorized_requests() {
+        let call = parse_call(
+            &config(),
+            &authorized(
+                "POST",
+                "/v1/prompts/a%20b/inject",
+                "{\"vars\": {\"x\": \"1\"}}",
+            ),
+        );
+        assert!(matches!(
+            call,
+            Ok(Call::Ipc { method: "prompts.inject", id: Some(ref id), ref params })
+                if id == "a b" && params["vars"]["x"] == "1"
+        ));
+        let call = parse_call(&config(), &authorized("GET", "/v1/prompts/", ""));
+        assert!(matches!(
+            call,
+            Ok(Call::Ipc {
+                method: "prompts.list",
+                id: None,
+                ..
+            })
+        ));
+        assert_eq!(
+            refused(parse_call(
+                &config(),
+                &authorized("PUT", "/v1/prompts/abc", "")
+            )),
+            Some(405)
+        );
+        assert_eq!(
+            refused(parse_call(&config(), &authorized("GET", "/v2/prompts", ""))),
+            Some(404)
+        );
+    }
+}
@@ -0,0 +1,583 @@
+// JSON-RPC 2.0 server for editor plugins, scripts and the command-line
+// companion (see `cli`). The app listens on `ipc/prompt-buddy.sock` in its data
+// dir. Access control is left to file permissions: the `ipc` directory is
+// private to the current user (0700), so whoever can open the socket is that
+// user. Each message is one JSON object, or a batch array, per line.
+//
+//     prompts.list                                          -> [Prompt]
+//     prompts.get     { prompt }                            -> Prompt
+//     prompts.create  { title, content, tags?, folder? }    -> Prompt
+//     prompts.update  { prompt, title?, content?, tags?, folder?, color?, abbreviation? }
+//                                                           -> Prompt
+//     prompts.delete  { prompt }                            -> null
+//     prompts.search  { query }                             -> [SearchHit]
+//     prompts.render  { prompt, vars? }                     -> string
+//     prompts.inject  { prompt, vars? }                     -> string
+//     library.export  { format, path, ids? }                -> number of prompts
+//     library.import  { format, path, strategy }            -> ImportPreview
+//     shortcuts.status                                      -> ShortcutStatus
+//     focus.targets                                         -> FocusTargets
+//     links.open      { url }                               -> null
+//     events.subscribe, events.unsubscribe                  -> null
+//
+// `prompt` is an id or a title, and params are always named. Once subscribed,
+// a connection is also sent the notifications `promptsUpdated` ({ count }) and
+// `injectionResult` ({ ok, message?, error? }) for every injection, whoever
+// started it. Failing methods answer with code -32000 and the reason.
+//
+// Windows has no listener yet, so the CLI works on the store files there.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::bundle::{self, BundleFormat, MergeStrategy};
+use crate::prompts::{self, Prompt};
+use crate::{deep_link, focus, library, shell_vars, shortcuts};
+
+const SOCKET_DIR: &str = "ipc";
+const SOCKET_NAME: &str = "prompt-buddy.sock";
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+// A method that ran and failed.
+pub const METHOD_FAILED: i64 = -32000;
+
+// Must list every `method` name of `Request`.
+const METHODS: &[&str] = &[
+    "prompts.list",
+    "prompts.get",
+    "prompts.create",
+    "prompts.update",
+    "prompts.delete",
+    "prompts.search",
+    "prompts.render",
+    "prompts.inject",
+    "library.export",
+    "library.import",
+    "shortcuts.status",
+    "focus.targets",
+    "links.open",
+    "events.subscribe",
+    "events.unsubscribe",
+];
+
+// A call, as the `method` and `params` members of a JSON-RPC request.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params")]
+pub enum Request {
+    #[serde(rename = "prompts.list")]
+    List {},
+    #[serde(rename = "prompts.get")]
+    Get { prompt: String },
+    #[serde(rename = "prompts.create")]
+    Create {
+        title: String,
+        content: String,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        folder: Option<String>,
+    },
+    // Fields left out stay as they are. An empty folder is the top level and an
+    // empty abbreviation removes it.
+    #[serde(rename = "prompts.update")]
+    Update {
+        prompt: String,
+        title: Option<String>,
+        content: Option<String>,
+        tags: Option<Vec<String>>,
+        folder: Option<String>,
+        color: Option<String>,
+        abbreviation: Option<String>,
+    },
+    #[serde(rename = "prompts.delete")]
+    Delete { prompt: String },
+    #[serde(rename = "prompts.search")]
+    Search { query: String },
+    #[serde(rename = "prompts.render")]
+    Render {
+        prompt: String,
+        #[serde(default)]
+        vars: HashMap<String, String>,
+    },
+    #[serde(rename = "prompts.inject")]
+    Inject {
+        prompt: String,
+        #[serde(default)]
+        vars: HashMap<String, String>,
+    },
+    #[serde(rename = "library.export")]
+    Export {
+        format: BundleFormat,
+        path: String,
+        #[serde(default)]
+        ids: Option<Vec<String>>,
+    },
+    #[serde(rename = "library.import")]
+    Import {
+        format: BundleFormat,
+        path: String,
+        strategy: MergeStrategy,
+    },
+    #[serde(rename = "shortcuts.status")]
+    ShortcutStatus {},
+    #[serde(rename = "focus.targets")]
+    FocusTargets {},
+    // A `promptbuddy://` link, handled as if the app had been opened with it.
+    #[serde(rename = "links.open")]
+    OpenLink { url: String },
+    #[serde(rename = "events.subscribe")]
+    Subscribe {},
+    #[serde(rename = "events.unsubscribe")]
+    Unsubscribe {},
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+// The JSON-RPC request for `request`.
+pub fn encode(request: &Request, id: Value) -> Result<Value, String> {
+    let mut call =
+        serde_json::to_value(request).map_err(|e| format!("Failed to encode request: {}", e))?;
+    call["jsonrpc"] = Value::from("2.0");
+    call["id"] = id;
+    Ok(call)
+}
+
+// Where the app listens for the data dir `data_dir`.
+pub fn socket_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(SOCKET_DIR).join(SOCKET_NAME)
+}
+
+// Finds a prompt by id or, failing that, by title (case-insensitive).
+pub fn find_prompt(prompts: &[Prompt], query: &str) -> Result<Prompt, String> {
+    if let Some(prompt) = prompts.iter().find(|p| p.id == query) {
+        return Ok(prompt.clone());
+    }
+    let matches: Vec<&Prompt> = prompts
+        .iter()
+        .filter(|p| p.title.to_lowercase() == query.to_lowercase())
+        .collect();
+    match matches.as_slice() {
+        [prompt] => Ok((*prompt).clone()),
+        [] => Err(format!("No prompt with id or title '{}'", query)),
+        _ => Err(format!(
+            "{} prompts are titled '{}'; use an id instead",
+            matches.len(),
+            query
+        )),
+    }
+}
+
+pub fn new_prompt(title: String, content: String, tags: &[String], folder: Option<&str>) -> Prompt {
+    Prompt {
+        id: uuid::Uuid::new_v4().to_string(),
+        title,
+        content,
+        color: prompts::DEFAULT_COLOR.to_string(),
+        abbreviation: None,
+        tags: prompts::normalize_tags(tags),
+        folder: folder.and_then(prompts::normalize_folder),
+    }
+}
+
+fn to_value(value: impl Serialize) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("Failed to serialize response: {}", e))
+}
+
+// The `Request` for a call of `method`; missing params are the same as none.
+pub fn parse(method: &str, params: Value) -> Result<Request, RpcError> {
+    if !METHODS.contains(&method) {
+        return Err(RpcError {
+            code: METHOD_NOT_FOUND,
+            message: format!("Unknown method '{}'", method),
+        });
+    }
+    let params = match params {
+        Value::Null => Value::Object(Default::default()),
+        params => params,
+    };
+    serde_json::from_value(serde_json::json!({ "method": method, "params": params })).map_err(|e| {
+        RpcError {
+            code: INVALID_PARAMS,
+            message: format!("Invalid params for {}: {}", method, e),
+        }
+    })
+}
+
+// Runs a call that doesn't depend on the connection it came in on. Shared
+// with the HTTP API (see `http_api`).
+pub fn execute(app: &AppHandle, request: Request) -> Result<Value, String> {
+    match request {
+        Request::List {} => to_value(library::prompts()),
+        Request::Get { prompt } => to_value(find_prompt(&library::prompts(), &prompt)?),
+        Request::Create {
+            title,
+            content,
+            tags,
+            folder,
+        } => {
+            let prompt = new_prompt(title, content, &tags, folder.as_deref());
+            let created = prompt.clone();
+            library::update_all(app, &format!("Add “{}”", prompt.title), |prompts| {
+                prompts.push(prompt);
+                Ok(())
+            })?;
+            to_value(created)
+        }
+        Request::Update {
+            prompt,
+            title,
+            content,
+            tags,
+            folder,
+            color,
+            abbreviation,
+        } => {
+            let current = find_prompt(&library::prompts(), &prompt)?;
+            let abbreviation = match abbreviation {
+                Some(abbreviation) if abbreviation.trim().is_empty() => None,
+                Some(abbreviation) => Some(abbreviation.trim().to_string()),
+                None => current.abbreviation,
+            };
+            let updated = Prompt {
+                id: current.id,
+                title: title.unwrap_or(current.title),
+                content: content.unwrap_or(current.content),
+                color: color.unwrap_or(current.color),
+                abbreviation,
+                tags: tags.unwrap_or(current.tags),
+                folder: folder.map_or(current.folder, |folder| prompts::normalize_folder(&folder)),
+            };
+            let id = updated.id.clone();
+            library::edit_prompt(app, updated, None)?;
+            to_value(library::current_prompt(&id)?)
+        }
+        Request::Delete { prompt } => {
+            let prompt = find_prompt(&library::prompts(), &prompt)?;
+            library::update_all(app, &format!("Delete “{}”", prompt.title), |prompts| {
+                prompts.retain(|p| p.id != prompt.id);
+                Ok(())
+            })?;
+            Ok(Value::Null)
+        }
+        Request::Search { query } => to_value(library::search(&query)?),
+        Request::Render { prompt, vars } => {
+            let prompt = find_prompt(&library::prompts(), &prompt)?;
+            to_value(shell_vars::render(&prompt, &vars)?)
+        }
+        Request::Inject { prompt, vars } => {
+            let prompt = find_prompt(&library::prompts(), &prompt)?;
+            let text = shell_vars::render(&prompt, &vars)?;
+            println!("⌨️  Injecting “{}” for an API client", prompt.title);
+            crate::inject_and_report(app, &text, Some(&prompt), "api")?;
+            to_value(format!("Injected “{}”", prompt.title))
+        }
+        Request::Export { format, path, ids } => {
+            to_value(bundle::export(format, Path::new(&path), ids.as_deref())?)
+        }
+        Request::Import {
+            format,
+            path,
+            strategy,
+        } => to_value(bundle::import(app, format, Path::new(&path), strategy)?),
+        Request::ShortcutStatus {} => to_value(shortcuts::status(app)),
+        Request::FocusTargets {} => to_value(focus::targets()),
+        Request::OpenLink { url } => {
+            deep_link::open(app, &url)?;
+            Ok(Value::Null)
+        }
+        Request::Subscribe {} | Request::Unsubscribe {} => {
+            Err("Notifications are only available on the socket".to_string())
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use server::start;
+
+#[cfg(not(unix))]
+pub fn start(_app: &AppHandle) {
+    println!("ℹ️  The JSON-RPC socket is not available on this platform");
+}
+
+#[cfg(unix)]
+mod server {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use once_cell::sync::Lazy;
+    use tauri::{Listener, Manager};
+
+    use super::*;
+
+    // A client that stops reading must not hold up everyone else.
+    const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+    static NEXT_CONNECTION: AtomicU64 = AtomicU64::new(1);
+
+    // Connections that asked for notifications.
+    static SUBSCRIBERS: Lazy<Mutex<Vec<Connection>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+    #[derive(Clone, Debug, Serialize)]
+    struct Notification<'a> {
+        jsonrpc: &'a str,
+        method: &'a str,
+        params: Value,
+    }
+
+    impl Response {
+        fn new(id: Value, result: Result<Value, RpcError>) -> Self {
+            let (result, error) = match result {
+                Ok(result) => (Some(result), None),
+                Err(error) => (None, Some(error)),
+            };
+            Response {
+                jsonrpc: "2.0".to_string(),
+                id,
+                result,
+                error,
+            }
+        }
+    }
+
+    // Parses one message that is not a batch. Notifications (no `id`) give
+    // `None` as the id.
+    fn decode(message: Value) -> (Option<Value>, Result<Request, RpcError>) {
+        let error = |code, message: String| Err(RpcError { code, message });
+        let Value::Object(mut message) = message else {
+            return (
+                None,
+                error(INVALID_REQUEST, "Requests must be objects".to_string()),
+            );
+        };
+        let id = message.remove("id");
+        if message.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+            return (
+                id,
+                error(INVALID_REQUEST, "jsonrpc must be \"2.0\"".to_string()),
+            );
+        }
+        let Some(method) = message
+            .get("method")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+        else {
+            return (id, error(INVALID_REQUEST, "Missing method".to_string()));
+        };
+        let params = message.remove("params").unwrap_or(Value::Null);
+        let request = parse(&method, params);
+        (id, request)
+    }
+
+    #[derive(Clone)]
+    struct Connection {
+        id: u64,
+        // Shared by the connection's thread and notifications.
+        writer: Arc<Mutex<UnixStream>>,
+    }
+
+    impl Connection {
+        fn send(&self, message: &Value) -> std::io::Result<()> {
+            let mut line = message.to_string();
+            line.push('\n');
+            self.writer.lock().unwrap().write_all(line.as_bytes())
+        }
+    }
+
+    // Creates `dir` if needed and makes it private to the current user. Fails if
+    // it belongs to someone else.
+    fn private_dir(dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Failed to restrict {}: {}", dir.display(), e))
+    }
+
+    pub fn start(app: &AppHandle) {
+        let data_dir = match app.path().app_data_dir() {
+            Ok(dir) => dir,
+            Err(e) => {
+                println!("⚠️  JSON-RPC socket unavailable: {}", e);
+                return;
+            }
+        };
+        let path = socket_path(&data_dir);
+        if let Some(dir) = path.parent() {
+            if let Err(e) = private_dir(dir) {
+                println!("⚠️  JSON-RPC socket unavailable: {}", e);
+                return;
+            }
+        }
+        if UnixStream::connect(&path).is_ok() {
+            println!("⚠️  Another instance is listening on {}", path.display());
+            return;
+        }
+        // Left behind by an instance that didn't shut down cleanly.
+        let _ = std::fs::remove_file(&path);
+
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            Err(e) => {
+                println!("⚠️  Failed to listen on {}: {}", path.display(), e);
+                return;
+            }
+        };
+        if let Err(e) = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)) {
+            println!("⚠️  Failed to restrict {}: {}", path.display(), e);
+            return;
+        }
+        println!("🔌 Listening for JSON-RPC requests on {}", path.display());
+
+        app.listen("prompts-updated", |_| {
+            notify(
+                "promptsUpdated",
+                serde_json::json!({ "count": library::prompts().len() }),
+            );
+        });
+        app.listen("injection-result", |event| {
+            notify(
+                "injectionResult",
+                serde_json::from_str(event.payload()).unwrap_or(Value::Null),
+            );
+        });
+
+        let app = app.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let app = app.clone();
+                std::thread::spawn(move || serve(&app, stream));
+            }
+        });
+    }
+
+    fn serve(app: &AppHandle, stream: UnixStream) {
+        let Ok(writer) = stream.try_clone() else {
+            return;
+        };
+        let _ = writer.set_write_timeout(Some(WRITE_TIMEOUT));
+        let connection = Connection {
+            id: NEXT_CONNECTION.fetch_add(1, Ordering::SeqCst),
+            writer: Arc::new(Mutex::new(writer)),
+        };
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(reply) = reply(app, &connection, &line) {
+                if connection.send(&reply).is_err() {
+                    break;
+                }
+            }
+        }
+        SUBSCRIBERS
+            .lock()
+            .unwrap()
+            .retain(|c| c.id != connection.id);
+    }
+
+    // The answer to one line, if it needs one.
+    fn reply(app: &AppHandle, connection: &Connection, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => {
+                let error = RpcError {
+                    code: PARSE_ERROR,
+                    message: format!("Parse error: {}", e),
+                };
+                return to_value(Response::new(Value::Null, Err(error))).ok();
+            }
+        };
+        match message {
+            Value::Array(batch) if batch.is_empty() => {
+                let error = RpcError {
+                    code: INVALID_REQUEST,
+                    message: "Empty batch".to_string(),
+                };
+                to_value(Response::new(Value::Null, Err(error))).ok()
+            }
+            Value::Array(batch) => {
+                let replies: Vec<Value> = batch
+                    .into_iter()
+                    .filter_map(|message| call(app, connection, message))
+                    .collect();
+                (!replies.is_empty()).then_some(Value::Array(replies))
+            }
+            message => call(app, connection, message),
+        }
+    }
+
+    fn call(app: &AppHandle, connection: &Connection, message: Value) -> Option<Value> {
+        let (id, request) = decode(message);
+        let result = request.and_then(|request| {
+            handle(app, connection, request).map_err(|message| RpcError {
+                code: METHOD_FAILED,
+                message,
+            })
+        });
+        // Notifications get no answer, not even an error.
+        to_value(Response::new(id?, result)).ok()
+    }
+
+    fn handle(app: &AppHandle, connection: &Connection, request: Request) -> Result<Value, String> {
+        match request {
+            Request::Subscribe {} => {
+                let mut subscribers = SUBSCRIBERS.lock().unwrap();
+                if !subscribers.iter().any(|c| c.id == connection.id) {
+                    subscribers.push(connection.clone());
+                }
+                Ok(Value::Null)
+            }
+            Request::Unsubscribe {} => {
+                SUBSCRIBERS
+                    .lock()
+                    .unwrap()
+                    .retain(|c| c.id != connection.id);
+                Ok(Value::Null)
+            }
+            request => execute(app, request),
+        }
+    }
+
+    // Sends a notification to every subscriber, dropping those that are gone.
+    fn notify(method: &str, params: Value) {
+        let notification = Notification {
+            jsonrpc: "2.0",
+            method,
+            params,
+        };
+        let Ok(message) = to_value(notification) else {
+            return;
+        };
+        SUBSCRIBERS
+            .lock()
+            .unwrap()
+            .retain(|connection| connection.send(&message).is_ok());
+    }
+}
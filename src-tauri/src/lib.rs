@@ -1,4 +1,5 @@
 use tauri::{AppHandle, Listener, Manager, Emitter};
+use std::collections::HashMap;
 use std::process::Command;
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
@@ -7,6 +8,36 @@ use enigo::{Enigo, Keyboard, Settings};
 use tauri::{WebviewWindowBuilder, WebviewUrl, LogicalPosition};
 use tauri_plugin_store::StoreExt;
 
+mod agent_rules;
+mod backups;
+mod bundle;
+pub mod cli;
+mod dbus;
+mod deep_link;
+mod expansion;
+mod focus;
+mod history;
+mod hooks;
+mod http_api;
+pub mod ipc;
+mod library;
+mod markdown_store;
+mod mcp;
+mod native_host;
+mod migrations;
+mod organize;
+mod projects;
+mod prompts;
+mod safety;
+mod shell_vars;
+mod shortcuts;
+mod snippet_import;
+mod sqlite_store;
+mod tag_query;
+mod template;
+mod trash;
+mod undo;
+
 // Store the name of the application that was active **before** the prompt bar
 // was shown. This lets us switch focus back to that application after the user
 // clicks a prompt pill so the text is inserted into the correct window.
@@ -15,6 +46,31 @@ static LAST_APP_NAME: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None
 // Store the current toggle shortcut for dynamic updates
 static CURRENT_TOGGLE_SHORTCUT: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
 
+// How the toggle shortcut behaves: a press flips visibility, or the bar is only
+// shown while the shortcut is held down ("hold-to-peek").
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ToggleMode {
+    Toggle,
+    Hold,
+}
+
+impl ToggleMode {
+    fn from_setting(value: &str) -> Option<Self> {
+        match value {
+            "toggle" => Some(ToggleMode::Toggle),
+            "hold" => Some(ToggleMode::Hold),
+            _ => None,
+        }
+    }
+}
+
+static TOGGLE_MODE: Lazy<Mutex<ToggleMode>> = Lazy::new(|| Mutex::new(ToggleMode::Toggle));
+
+// Index of the prompt pill the user is currently highlighting in the bar. In
+// hold mode, releasing the toggle shortcut injects this prompt, much like an
+// app switcher selects the highlighted window on release.
+static HIGHLIGHTED_PROMPT: Lazy<Mutex<Option<usize>>> = Lazy::new(|| Mutex::new(None));
+
 #[cfg(target_os = "macos")]
 fn get_frontmost_app() -> Option<String> {
     let output = Command::new("osascript")
@@ -41,13 +97,145 @@ fn activate_app(app_name: &str) -> bool {
         .unwrap_or(false)
 }
 
-// Stub helpers for non-macOS platforms so compilation still succeeds.
-#[cfg(not(target_os = "macos"))]
+// The window the window manager reports as active, on X11.
+#[cfg(target_os = "linux")]
+fn x11_active_window() -> Option<(x11rb::rust_connection::RustConnection, u32)> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+    let (conn, screen_num) = x11rb::connect(None).ok()?;
+    let root = conn.setup().roots[screen_num].root;
+    let active_atom = conn.intern_atom(false, b"_NET_ACTIVE_WINDOW").ok()?.reply().ok()?.atom;
+    let active = conn
+        .get_property(false, root, active_atom, AtomEnum::WINDOW, 0, 1)
+        .ok()?
+        .reply()
+        .ok()?;
+    let window = active.value32()?.next()?;
+    if window == 0 {
+        return None;
+    }
+    Some((conn, window))
+}
+
+// On X11 the frontmost application is the WM_CLASS of the active window.
+#[cfg(target_os = "linux")]
+fn get_frontmost_app() -> Option<String> {
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+    let (conn, window) = x11_active_window()?;
+    let class = conn
+        .get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 1024)
+        .ok()?
+        .reply()
+        .ok()?;
+    // WM_CLASS holds "instance\0class\0"; the class is the human-friendly name.
+    let mut parts = class.value.split(|b| *b == 0).filter(|part| !part.is_empty());
+    let instance = parts.next()?;
+    let name = parts.next().unwrap_or(instance);
+    Some(String::from_utf8_lossy(name).into_owned())
+}
+
+// Title of the frontmost window, which editors fill with the open project.
+#[cfg(target_os = "macos")]
+fn get_frontmost_window_title() -> Option<String> {
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg("tell application \"System Events\" to get name of front window of (first application process whose frontmost is true)")
+        .output()
+        .ok()?;
+    let title = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !title.is_empty()).then_some(title)
+}
+
+#[cfg(target_os = "linux")]
+fn get_frontmost_window_title() -> Option<String> {
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+    let (conn, window) = x11_active_window()?;
+    let utf8 = conn.intern_atom(false, b"UTF8_STRING").ok()?.reply().ok()?.atom;
+    let net_wm_name = conn.intern_atom(false, b"_NET_WM_NAME").ok()?.reply().ok()?.atom;
+    let name = conn
+        .get_property(false, window, net_wm_name, utf8, 0, 1024)
+        .ok()?
+        .reply()
+        .ok()?;
+    let name = if name.value.is_empty() {
+        conn.get_property(false, window, AtomEnum::WM_NAME, AtomEnum::STRING, 0, 1024)
+            .ok()?
+            .reply()
+            .ok()?
+    } else {
+        name
+    };
+    let title = String::from_utf8_lossy(&name.value).trim().to_string();
+    (!title.is_empty()).then_some(title)
+}
+
+// Stub helpers for other platforms so compilation still succeeds.
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
 fn get_frontmost_app() -> Option<String> { None }
 
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn get_frontmost_window_title() -> Option<String> { None }
+
 #[cfg(not(target_os = "macos"))]
 fn activate_app(_app_name: &str) -> bool { false }
 
+// Tells the user why the app can't start. Started from the dock or a desktop
+// entry there is no terminal, and on Windows not even a console, to read
+// stderr in.
+#[cfg(target_os = "macos")]
+fn show_fatal_error(message: &str) {
+    let message = message.replace('\\', "\\\\").replace('"', "\\\"");
+    let _ = Command::new("osascript")
+        .arg("-e")
+        .arg(format!(
+            "display alert \"Prompt Buddy can't start\" message \"{}\" as critical",
+            message
+        ))
+        .output();
+}
+
+#[cfg(target_os = "linux")]
+fn show_fatal_error(message: &str) {
+    let zenity = Command::new("zenity")
+        .args([
+            "--error",
+            "--no-markup",
+            "--title",
+            "Prompt Buddy can't start",
+            "--text",
+        ])
+        .arg(message)
+        .status();
+    if zenity.is_err() {
+        let _ = Command::new("kdialog")
+            .args(["--title", "Prompt Buddy can't start", "--error"])
+            .arg(message)
+            .status();
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn show_fatal_error(message: &str) {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONERROR, MB_OK};
+
+    let wide = |s: &str| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let (text, caption) = (wide(message), wide("Prompt Buddy can't start"));
+    unsafe {
+        MessageBoxW(
+            std::ptr::null_mut(),
+            text.as_ptr(),
+            caption.as_ptr(),
+            MB_OK | MB_ICONERROR,
+        );
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn show_fatal_error(_message: &str) {}
+
 
 
 #[derive(Clone, serde::Serialize)]
@@ -122,8 +310,69 @@ async fn toggle_window_visibility(app: tauri::AppHandle) -> Result<String, Strin
     }
 }
 
+// Why an injection did not happen. Serialized to the frontend as
+// `{ kind, message }` so it can tell a refused injection from a failed one.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+enum InjectError {
+    EmptyText,
+    InputUnavailable(String),
+    // A password field or other secure input context has focus.
+    SecureInput(String),
+    // The frontmost application is in the user's protected apps list.
+    ProtectedApp(String),
+    // The pre-injection hook refused the text (see `hooks`).
+    Vetoed(String),
+    // The prompt's variables or shell commands could not be filled in.
+    RenderFailed(String),
+    TypingFailed(String),
+}
+
+impl std::fmt::Display for InjectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InjectError::EmptyText => write!(f, "Cannot inject empty text"),
+            InjectError::InputUnavailable(msg) => write!(f, "Failed to initialize input system: {}", msg),
+            InjectError::SecureInput(msg) => write!(f, "Refusing to inject into secure input: {}", msg),
+            InjectError::ProtectedApp(msg) => write!(f, "Refusing to inject into protected app: {}", msg),
+            InjectError::Vetoed(msg) => write!(f, "Injection vetoed: {}", msg),
+            InjectError::RenderFailed(msg) => write!(f, "{}", msg),
+            InjectError::TypingFailed(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<InjectError> for String {
+    fn from(error: InjectError) -> Self {
+        error.to_string()
+    }
+}
+
+// Outcome of an injection, broadcast as `injection-result` (see `ipc`).
+#[derive(Debug, Clone, serde::Serialize)]
+struct InjectionResult {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<InjectError>,
+}
+
+// The variables the bar has to ask for before it can inject `content`.
+#[tauri::command]
+async fn get_prompt_variables(content: String) -> Result<Vec<template::Variable>, String> {
+    Ok(template::variables(&content))
+}
+
+// Types `text`, or the prompt `prompt_id` rendered with `vars` (its shell
+// variables included, see `shell_vars`) when it is one.
 #[tauri::command]
-async fn inject_text(text: String) -> Result<String, String> {
+async fn inject_text(
+    app: AppHandle,
+    text: String,
+    prompt_id: Option<String>,
+    vars: Option<HashMap<String, String>>,
+) -> Result<String, InjectError> {
     println!("🚀 Starting text injection...");
     println!("📝 Text to inject: '{}'", text);
     println!("📏 Text length: {} characters", text.len());
@@ -141,25 +390,79 @@ async fn inject_text(text: String) -> Result<String, String> {
         }
     }
     
+    // Project prompts are in the bar too.
+    let prompt = prompt_id.and_then(|id| {
+        let project = projects::bar_prompts().into_iter().map(|bar| bar.prompt);
+        library::prompts().into_iter().chain(project).find(|p| p.id == id)
+    });
+    let text = match &prompt {
+        Some(prompt) => shell_vars::render(prompt, &vars.unwrap_or_default()).map_err(|e| {
+            println!("❌ {}", e);
+            InjectError::RenderFailed(e)
+        })?,
+        None => text,
+    };
+    inject_and_report(&app, &text, prompt.as_ref(), "bar")
+}
+
+// Injects `text`, running the injection hooks around it, and tells listeners
+// how it went. `source` says who asked: "bar", "api", "link" or
+// "expansion".
+fn inject_and_report(
+    app: &AppHandle,
+    text: &str,
+    prompt: Option<&prompts::Prompt>,
+    source: &str,
+) -> Result<String, InjectError> {
+    let (text, result) = match hooks::pre_inject(text, prompt, source) {
+        Ok(text) => {
+            let result = inject_text_internal(&text);
+            (text, result)
+        }
+        Err(error) => {
+            println!("⛔ {}", error);
+            (text.to_string(), Err(error))
+        }
+    };
+    hooks::post_inject(&text, prompt, source, &result);
+    let report = InjectionResult {
+        ok: result.is_ok(),
+        message: result.as_ref().ok().cloned(),
+        error: result.as_ref().err().cloned(),
+    };
+    if let Err(e) = app.emit("injection-result", report) {
+        println!("⚠️  Failed to emit injection-result event: {}", e);
+    }
+    result
+}
+
+// Types `text` into whatever currently has keyboard focus. Only called from
+// `inject_and_report`, so the hooks always run.
+fn inject_text_internal(text: &str) -> Result<String, InjectError> {
     if text.is_empty() {
-        let error_msg = "❌ Cannot inject empty text";
-        println!("{}", error_msg);
-        return Err(error_msg.to_string());
+        println!("❌ {}", InjectError::EmptyText);
+        return Err(InjectError::EmptyText);
     }
     
     let mut enigo = Enigo::new(&Settings::default()).map_err(|e| {
-        let error_msg = format!("❌ Failed to initialize input system: {}", e);
-        println!("{}", error_msg);
-        error_msg
+        let error = InjectError::InputUnavailable(e.to_string());
+        println!("❌ {}", error);
+        error
     })?;
     
     println!("⏱️  Waiting 300ms after activating previous window...");
     std::thread::sleep(std::time::Duration::from_millis(300));
+
+    // Only now is the target focused, so this is the moment to check it.
+    if let Err(error) = safety::check_injection_target() {
+        println!("⛔ {}", error);
+        return Err(error);
+    }
     
     println!("⌨️  Attempting to type text...");
     
     // Try to type the text
-    match enigo.text(&text) {
+    match enigo.text(text) {
         Ok(_) => {
             println!("✅ Text injection completed successfully");
             Ok("Text injected successfully".to_string())
@@ -167,7 +470,7 @@ async fn inject_text(text: String) -> Result<String, String> {
         Err(e) => {
             let error_msg = format!("❌ Text injection failed: {}. This usually means no text field is currently focused or the app needs accessibility permissions.", e);
             println!("{}", error_msg);
-            Err("No active text field found or missing accessibility permissions. Please:\n1. Click on a text field to focus it\n2. Check System Preferences > Security & Privacy > Privacy > Accessibility".to_string())
+            Err(InjectError::TypingFailed("No active text field found or missing accessibility permissions. Please:\n1. Click on a text field to focus it\n2. Check System Preferences > Security & Privacy > Privacy > Accessibility".to_string()))
         }
     }
 }
@@ -230,8 +533,8 @@ async fn hide_popup(app: AppHandle) {
 }
 
 #[tauri::command]
-async fn capture_frontmost_app() -> Result<(), String> {
-    remember_current_app();
+async fn capture_frontmost_app(app: AppHandle) -> Result<(), String> {
+    remember_current_app(&app);
     Ok(())
 }
 
@@ -264,9 +567,17 @@ async fn activate_last_app() -> Result<(), String> {
 #[tauri::command]
 async fn update_toggle_shortcut(app: AppHandle, new_shortcut: String) -> Result<String, String> {
     println!("🔧 Updating toggle shortcut to: {}", new_shortcut);
-    
+
+    // Validate before touching the current registration so a typo doesn't
+    // leave the user without any toggle shortcut.
+    let normalized = shortcuts::normalize_shortcut(&new_shortcut).map_err(|e| {
+        println!("❌ {}", e);
+        e
+    })?;
+
     // First, unregister the current toggle shortcut if one exists
-    if let Some(current_shortcut) = CURRENT_TOGGLE_SHORTCUT.lock().unwrap().clone() {
+    let previous = CURRENT_TOGGLE_SHORTCUT.lock().unwrap().clone();
+    if let Some(current_shortcut) = previous.as_ref().filter(|_| !shortcuts::grabs_suspended()) {
         println!("🗑️  Unregistering previous shortcut: {}", current_shortcut);
         if let Ok(shortcut) = current_shortcut.parse::<Shortcut>() {
             if let Err(e) = app.global_shortcut().unregister(shortcut) {
@@ -274,41 +585,59 @@ async fn update_toggle_shortcut(app: AppHandle, new_shortcut: String) -> Result<
             }
         }
     }
-    
-    // Parse and register the new shortcut
-    match new_shortcut.parse::<Shortcut>() {
-        Ok(shortcut) => {
-            // Clone for use in closure
-            let shortcut_for_log = new_shortcut.clone();
-            
-            // Register the new toggle shortcut
-            match app.global_shortcut().on_shortcut(shortcut, move |_app, _shortcut, _state| {
-                if _state.state() == ShortcutState::Pressed {
-                    println!("🎯 Custom toggle shortcut pressed: {}", shortcut_for_log);
-                    toggle_window_visibility_internal(_app);
-                }
-            }) {
-                Ok(_) => {
-                    // Update the stored current shortcut
-                    *CURRENT_TOGGLE_SHORTCUT.lock().unwrap() = Some(new_shortcut.clone());
-                    println!("✅ Successfully registered new toggle shortcut: {}", new_shortcut);
-                    Ok(format!("Toggle shortcut updated to: {}", new_shortcut))
-                }
-                Err(e) => {
-                    let error_msg = format!("Failed to register new shortcut '{}': {}", new_shortcut, e);
-                    println!("❌ {}", error_msg);
-                    Err(error_msg)
+
+    // While shortcuts are paused just remember the new one; it gets grabbed
+    // when they are resumed.
+    if shortcuts::grabs_suspended() {
+        *CURRENT_TOGGLE_SHORTCUT.lock().unwrap() = Some(normalized.clone());
+        println!("⏸️  Shortcuts are paused, {} will be registered on resume", normalized);
+        return Ok(format!("Toggle shortcut updated to: {}", normalized));
+    }
+
+    match shortcuts::register_toggle_shortcut(&app, &normalized) {
+        Ok(_) => {
+            println!("✅ Successfully registered new toggle shortcut: {}", normalized);
+            Ok(format!("Toggle shortcut updated to: {}", normalized))
+        }
+        Err(error_msg) => {
+            println!("❌ {}", error_msg);
+            if let Some(current_shortcut) = previous {
+                println!("↩️  Restoring previous shortcut: {}", current_shortcut);
+                if let Err(e) = shortcuts::register_toggle_shortcut(&app, &current_shortcut) {
+                    println!("❌ {}", e);
                 }
             }
+            Err(error_msg)
         }
-        Err(e) => {
-            let error_msg = format!("Invalid shortcut format '{}': {}", new_shortcut, e);
+    }
+}
+
+#[tauri::command]
+async fn update_toggle_mode(mode: String) -> Result<String, String> {
+    println!("🔧 Updating toggle mode to: {}", mode);
+
+    match ToggleMode::from_setting(&mode) {
+        Some(toggle_mode) => {
+            *TOGGLE_MODE.lock().unwrap() = toggle_mode;
+            *HIGHLIGHTED_PROMPT.lock().unwrap() = None;
+            Ok(format!("Toggle mode updated to: {}", mode))
+        }
+        None => {
+            let error_msg = format!("Unknown toggle mode '{}', expected 'toggle' or 'hold'", mode);
             println!("❌ {}", error_msg);
             Err(error_msg)
         }
     }
 }
 
+// Called by the bar whenever the highlighted pill changes (hover or keyboard
+// navigation) so a hold-mode release knows which prompt to inject.
+#[tauri::command]
+async fn set_highlighted_prompt(index: Option<usize>) -> Result<(), String> {
+    *HIGHLIGHTED_PROMPT.lock().unwrap() = index;
+    Ok(())
+}
+
 // 从设置中读取快捷键配置
 async fn load_toggle_shortcut_from_settings(app: &AppHandle) -> String {
     match app.store("settings.json") {
@@ -334,6 +663,69 @@ async fn load_toggle_shortcut_from_settings(app: &AppHandle) -> String {
     "ctrl+space".to_string()
 }
 
+// 从设置中读取切换模式（toggle / hold）
+async fn load_toggle_mode_from_settings(app: &AppHandle) -> ToggleMode {
+    if let Ok(store) = app.store("settings.json") {
+        if let Some(value) = store.get("toggleMode") {
+            match value.as_str().and_then(ToggleMode::from_setting) {
+                Some(mode) => {
+                    println!("📋 Loaded toggle mode from settings: {:?}", mode);
+                    return mode;
+                }
+                None => println!("⚠️  Ignoring invalid toggle mode in settings: {}", value),
+            }
+        }
+    }
+
+    ToggleMode::Toggle
+}
+
+// Entry point for every toggle shortcut event. In toggle mode only key presses
+// matter; in hold mode the press shows the bar and the release hides it again,
+// injecting the highlighted prompt if there is one.
+fn handle_toggle_shortcut(app: &AppHandle, state: ShortcutState) {
+    let mode = *TOGGLE_MODE.lock().unwrap();
+    match (mode, state) {
+        (ToggleMode::Toggle, ShortcutState::Pressed) => toggle_window_visibility_internal(app),
+        (ToggleMode::Toggle, ShortcutState::Released) => {}
+        (ToggleMode::Hold, ShortcutState::Pressed) => {
+            let Some(window) = app.get_webview_window("main") else {
+                println!("❌ Could not find main window");
+                return;
+            };
+            // Key repeat may deliver several presses while the shortcut is held.
+            if window.is_visible().unwrap_or(false) {
+                return;
+            }
+            *HIGHLIGHTED_PROMPT.lock().unwrap() = None;
+            remember_current_app(app);
+
+            println!("👁️  Peeking prompt picker bar (hold mode)");
+            if let Err(e) = window.show() {
+                println!("❌ Failed to show window: {}", e);
+            } else {
+                let _ = window.set_focus();
+            }
+        }
+        (ToggleMode::Hold, ShortcutState::Released) => {
+            let Some(window) = app.get_webview_window("main") else {
+                return;
+            };
+            println!("🫥 Toggle shortcut released, hiding prompt picker bar");
+            if let Err(e) = window.hide() {
+                println!("❌ Failed to hide window: {}", e);
+            }
+
+            if let Some(prompt_index) = HIGHLIGHTED_PROMPT.lock().unwrap().take() {
+                println!("🚀 Injecting highlighted prompt on release: {}", prompt_index);
+                if let Err(e) = window.emit("inject-prompt", prompt_index) {
+                    println!("❌ Failed to emit inject-prompt event: {}", e);
+                }
+            }
+        }
+    }
+}
+
 // 提取窗口切换逻辑为独立函数，便于复用
 fn toggle_window_visibility_internal(app: &AppHandle) {
     if let Some(window) = app.get_webview_window("main") {
@@ -350,7 +742,7 @@ fn toggle_window_visibility_internal(app: &AppHandle) {
                     // Before showing the window we record the app
                     // that is currently frontmost so we can switch
                     // back to it later when the user selects a prompt.
-                    remember_current_app();
+                    remember_current_app(app);
 
                     println!("👁️  Showing prompt picker bar");
                     if let Err(e) = window.show() {
@@ -364,7 +756,7 @@ fn toggle_window_visibility_internal(app: &AppHandle) {
             Err(e) => {
                 println!("❌ Failed to get window visibility: {}", e);
                 // Capture frontmost app before stealing focus
-                remember_current_app();
+                remember_current_app(app);
 
                 println!("🔄 Attempting to show window anyway...");
                 if let Err(e) = window.show() {
@@ -381,86 +773,147 @@ fn toggle_window_visibility_internal(app: &AppHandle) {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let builder = tauri::Builder::default();
+    // Windows has no socket for `cli` to forward links over, so a second copy
+    // hands its arguments to this one and exits. It must be the first plugin.
+    #[cfg(target_os = "windows")]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+        match deep_link::url_from_args(argv.into_iter().skip(1)) {
+            Some(url) => {
+                if let Err(e) = deep_link::open(app, &url) {
+                    println!("❌ {}", e);
+                }
+            }
+            None => println!("ℹ️  Prompt Buddy is already running"),
+        }
+    }));
+    builder
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_window_state::Builder::default().build())
-                        .invoke_handler(tauri::generate_handler![greet, inject_text, check_accessibility_permissions, toggle_window_visibility, show_popup, hide_popup, capture_frontmost_app, activate_last_app, update_toggle_shortcut])
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            inject_text,
+            get_prompt_variables,
+            check_accessibility_permissions,
+            toggle_window_visibility,
+            show_popup,
+            hide_popup,
+            capture_frontmost_app,
+            activate_last_app,
+            update_toggle_shortcut,
+            update_toggle_mode,
+            set_highlighted_prompt,
+            expansion::set_text_expansion_enabled,
+            library::get_prompts,
+            library::save_prompts,
+            library::update_prompt,
+            library::create_prompt,
+            library::delete_prompt,
+            library::move_prompt,
+            library::search_prompts,
+            library::prompt_history,
+            library::diff_prompt_revisions,
+            library::revert_prompt,
+            library::get_library_error,
+            library::list_backups,
+            library::restore_backup,
+            library::list_trash,
+            library::restore_from_trash,
+            library::purge_trash,
+            library::undo,
+            library::redo,
+            library::get_undo_state,
+            organize::list_folders,
+            organize::rename_folder,
+            organize::move_folder,
+            organize::move_prompts_to_folder,
+            organize::list_tags,
+            organize::filter_prompts,
+            organize::apply_tags,
+            organize::get_bar_prompts,
+            projects::get_active_project,
+            http_api::get_http_api_settings,
+            http_api::update_http_api_settings,
+            http_api::regenerate_http_api_token,
+            deep_link::get_pending_link,
+            deep_link::resolve_pending_link,
+            deep_link::get_deep_link_trusted_apps,
+            deep_link::update_deep_link_trusted_apps,
+            bundle::export_library,
+            bundle::preview_import,
+            bundle::import_library,
+            agent_rules::export_agent_rules,
+            library::get_storage_settings,
+            library::set_storage_backend,
+            shortcuts::validate_shortcut,
+            shortcuts::test_register_shortcut,
+            shortcuts::record_shortcut,
+            shortcuts::set_shortcuts_paused,
+            shortcuts::get_shortcuts_pause_status,
+            shortcuts::update_pause_shortcut,
+            safety::get_protected_apps,
+            safety::update_protected_apps,
+            hooks::get_inject_hooks,
+            hooks::update_inject_hooks,
+            shell_vars::get_shell_settings,
+            shell_vars::update_shell_settings,
+            shell_vars::get_prompt_shell_commands,
+            shell_vars::set_prompt_shell_commands,
+        ])
         .setup(|app| {
+            // Stores must be at the current schema before anything reads them.
+            if let Err(e) = migrations::run(app.handle()) {
+                eprintln!("❌ Cannot start: {}", e);
+                show_fatal_error(&e);
+                std::process::exit(1);
+            }
+
             println!("🔧 Setting up global shortcuts with handlers...");
             
             // Load toggle shortcut from settings
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
+                let toggle_mode = load_toggle_mode_from_settings(&app_handle).await;
+                *TOGGLE_MODE.lock().unwrap() = toggle_mode;
+
                 let toggle_shortcut = load_toggle_shortcut_from_settings(&app_handle).await;
                 println!("🎯 Registering main toggle shortcut: {}", toggle_shortcut);
                 
-                match toggle_shortcut.parse::<Shortcut>() {
-                    Ok(main_shortcut) => {
-                        let shortcut_str = toggle_shortcut.clone();
-                        match app_handle.global_shortcut().on_shortcut(main_shortcut, move |_app, _shortcut, _state| {
-                            // Only act on key *press* events so the shortcut truly toggles.
-                            if _state.state() == ShortcutState::Pressed {
-                                println!("🎯 Global shortcut ({}) pressed!", shortcut_str);
-                                toggle_window_visibility_internal(_app);
-                            }
-                        }) {
-                            Ok(_) => {
-                                // Store the current shortcut for future updates
-                                *CURRENT_TOGGLE_SHORTCUT.lock().unwrap() = Some(toggle_shortcut.clone());
-                                println!("✅ Main shortcut ({}) registered successfully!", toggle_shortcut);
-                            }
-                            Err(e) => {
-                                println!("❌ Failed to register main shortcut {}: {}", toggle_shortcut, e);
-                                println!("⚠️  You can still use the app manually, but the toggle shortcut won't work");
-                            }
-                        }
+                match shortcuts::register_toggle_shortcut(&app_handle, &toggle_shortcut) {
+                    Ok(_) => {
+                        println!("✅ Main shortcut ({}) registered successfully!", toggle_shortcut);
                     }
                     Err(e) => {
-                        println!("❌ Failed to parse main shortcut {}: {}", toggle_shortcut, e);
+                        println!("❌ {}", e);
+                        println!("⚠️  You can still use the app manually, but the toggle shortcut won't work");
                     }
                 }
             });
             
+            safety::init(app.handle());
+            hooks::init(app.handle());
+            shell_vars::init(app.handle());
+            library::init(app.handle());
+            ipc::start(app.handle());
+            http_api::init(app.handle());
+            dbus::start(app.handle());
+            expansion::init(app.handle());
+            deep_link::init(app.handle());
+
             // Register prompt injection shortcuts with handlers
             println!("🎯 Registering prompt injection shortcuts...");
-            let mut successful_shortcuts = 0;
-            for i in 1..=9 {
-                let shortcut_str = format!("cmd+alt+{}", i);
-                let prompt_index = i - 1; // Convert to 0-based index
-                
-                match shortcut_str.parse::<Shortcut>() {
-                    Ok(shortcut) => {
-                        match app.handle().global_shortcut().on_shortcut(shortcut, move |app, _shortcut, _state| {
-                            if _state.state() == ShortcutState::Pressed {
-                                println!("🚀 Prompt shortcut triggered: Cmd+Alt+{}", i);
-                                // Emit event to frontend to trigger injection
-                                if let Some(window) = app.get_webview_window("main") {
-                                    if let Err(e) = window.emit("inject-prompt", prompt_index) {
-                                        println!("❌ Failed to emit inject-prompt event: {}", e);
-                                    } else {
-                                        println!("✅ Emitted inject-prompt event for index: {}", prompt_index);
-                                    }
-                                }
-                            }
-                        }) {
-                            Ok(_) => {
-                                println!("✅ Registered: {}", shortcut_str);
-                                successful_shortcuts += 1;
-                            }
-                            Err(e) => {
-                                println!("❌ Failed to register {}: {} (probably conflicts with another app)", shortcut_str, e);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        println!("❌ Failed to parse shortcut {}: {}", shortcut_str, e);
-                    }
-                }
+            let successful_shortcuts = shortcuts::register_prompt_shortcuts(app.handle());
+
+            let pause_shortcut = shortcuts::load_pause_shortcut_from_settings(app.handle());
+            match shortcuts::register_pause_shortcut(app.handle(), &pause_shortcut) {
+                Ok(_) => println!("✅ Pause shortcut ({}) registered", pause_shortcut),
+                Err(e) => println!("❌ {}", e),
             }
-            
+            focus::start(app.handle());
+
             if successful_shortcuts == 0 {
                 println!("⚠️  No prompt shortcuts could be registered - they may conflict with existing shortcuts");
                 println!("💡 You can still use the app's interface to select and inject prompts");
@@ -478,7 +931,7 @@ pub fn run() {
                 // Record the currently frontmost application BEFORE we bring
                 // the prompt bar to the foreground. This way we can return
                 // focus to it when the user clicks a prompt.
-                remember_current_app();
+                remember_current_app(app.handle());
 
                 println!("👁️  Showing bar on first launch");
                 let _ = window.show();
@@ -486,16 +939,33 @@ pub fn run() {
             
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // macOS delivers `promptbuddy://` links to the running app.
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = event {
+                for url in urls {
+                    if let Err(e) = deep_link::open(app, url.as_str()) {
+                        println!("❌ {}", e);
+                    }
+                }
+            }
+            #[cfg(not(target_os = "macos"))]
+            let _ = (app, event);
+        });
 }
 
 // Helper that records the currently frontmost application so we can restore
-// focus later. Only meaningful on macOS.
-fn remember_current_app() {
+// focus later (only meaningful on macOS), and picks the active project from its
+// window title.
+fn remember_current_app(app: &AppHandle) {
+    let name = get_frontmost_app();
+    projects::on_window_captured(app, name.as_deref(), get_frontmost_window_title().as_deref());
+
     #[cfg(target_os = "macos")]
     {
-        if let Some(name) = get_frontmost_app() {
+        if let Some(name) = name {
             println!("💾 Remembering current frontmost app: {}", name);
             *LAST_APP_NAME.lock().unwrap() = Some(name);
         }
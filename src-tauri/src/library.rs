@@ -0,0 +1,928 @@
+// The prompt library is owned by the backend: windows read and write it through
+// the commands below and every change is broadcast as `prompts-updated`, so the
+// bar and editors reload no matter who made the change.
+//
+// Where prompts are persisted is pluggable (`storageBackend` in settings.json):
+// - `sqlite` (default): `prompts.db` in the app data dir, with full-text search;
+//   `prompts.json` is imported into it once on first launch;
+// - `json`: the `prompts` key of `prompts.json` (the original format);
+// - `markdown`: one `.md` file per prompt in `markdownDirectory`, watched for
+//   edits made outside the app.
+//
+// If the library can't be read it is never replaced with something else: it
+// loads empty, refuses writes and reports `library-error` until the user
+// restores a backup (see `backups`).
+//
+// Every write goes through `modify`, which moves deleted prompts to the trash
+// (see `trash`) and records the change for `undo`/`redo` (see `undo`).
+
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+use crate::backups::{self, BackupInfo};
+use crate::history::{self, PromptDiff, Revision};
+use crate::markdown_store::MarkdownStore;
+use crate::migrations;
+use crate::prompts::{self, Prompt};
+use crate::sqlite_store::SqliteStore;
+use crate::trash::{self, TrashedPrompt};
+use crate::undo::{self, UndoState};
+
+// Editors tend to write a file in several steps; wait for things to settle.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(250);
+
+pub trait PromptStore: Send {
+    fn load(&self) -> Result<Vec<Prompt>, String>;
+    fn save(&mut self, prompts: &[Prompt]) -> Result<(), String>;
+
+    // Persists the single prompt at `index` of the updated library. Backends
+    // that can write one record at a time override this.
+    fn save_prompt(&mut self, prompts: &[Prompt], _index: usize) -> Result<(), String> {
+        self.save(prompts)
+    }
+
+    // Moves unreadable data out of the way before a restore overwrites it.
+    fn quarantine(&mut self, _backup_dir: &Path) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Backends without an index fall back to a plain substring scan.
+    fn search(&self, query: &str) -> Result<Vec<SearchHit>, String> {
+        Ok(substring_search(&self.load()?, query))
+    }
+
+    // Directory to watch for changes made outside the app, if any.
+    fn watch_path(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub prompt: Prompt,
+    // Position in the bar.
+    pub index: usize,
+    // Excerpt with the matched terms wrapped in `<mark>`/`</mark>`. The rest
+    // of the text is not escaped, so render it as text, not HTML.
+    pub snippet: String,
+    // Higher is better; only meaningful relative to other hits of one search.
+    pub score: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    Sqlite,
+    Json,
+    Markdown,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageSettings {
+    backend: StorageBackend,
+    markdown_directory: String,
+}
+
+struct Library {
+    store: Box<dyn PromptStore>,
+    prompts: Vec<Prompt>,
+    // Why the backend couldn't be read; writes are refused while set.
+    load_error: Option<String>,
+}
+
+static LIBRARY: Lazy<Mutex<Option<Library>>> = Lazy::new(|| Mutex::new(None));
+
+// Kept alive for as long as the current backend needs watching.
+static WATCHER: Lazy<Mutex<Option<RecommendedWatcher>>> = Lazy::new(|| Mutex::new(None));
+
+// The original storage: the `prompts` key of `prompts.json`. The file is read
+// and written directly rather than through the store plugin, which neither
+// writes atomically nor reports a damaged file.
+struct JsonStore {
+    path: PathBuf,
+}
+
+impl JsonStore {
+    fn open(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join("prompts.json"),
+        }
+    }
+}
+
+impl PromptStore for JsonStore {
+    fn load(&self) -> Result<Vec<Prompt>, String> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let raw = std::fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?;
+        let value: serde_json::Value = serde_json::from_str(&raw)
+            .map_err(|e| format!("{} is corrupted: {}", self.path.display(), e))?;
+        match value.get("prompts") {
+            Some(prompts) => serde_json::from_value(prompts.clone())
+                .map_err(|e| format!("{} is corrupted: {}", self.path.display(), e)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn save(&mut self, prompts: &[Prompt]) -> Result<(), String> {
+        let value = serde_json::json!({
+            "schemaVersion": migrations::prompts_schema_version(),
+            "prompts": prompts,
+        });
+        let contents = serde_json::to_string_pretty(&value)
+            .map_err(|e| format!("Failed to serialize prompts: {}", e))?;
+        backups::write_atomic(&self.path, contents.as_bytes())
+    }
+
+    fn quarantine(&mut self, backup_dir: &Path) -> Result<(), String> {
+        if self.path.exists() {
+            let moved = backups::quarantine(backup_dir, &self.path)?;
+            println!("🗄️  Moved unreadable {} to {}", self.path.display(), moved.display());
+        }
+        Ok(())
+    }
+}
+
+pub fn init(app: &AppHandle) {
+    let settings = load_storage_settings(app);
+    println!("📚 Opening prompt library ({:?} backend)", settings.backend);
+    if let Err(e) = open(app, &settings) {
+        println!("❌ Failed to open prompt library: {}", e);
+        println!("↩️  Falling back to the JSON backend");
+        let fallback = StorageSettings {
+            backend: StorageBackend::Json,
+            ..settings
+        };
+        if let Err(e) = open(app, &fallback) {
+            println!("❌ Failed to open JSON prompt library: {}", e);
+        }
+    }
+
+    if let Err(e) = purge_expired_trash(app) {
+        println!("⚠️  Failed to purge the trash: {}", e);
+    }
+}
+
+// Opens the configured backend of the library in `data_dir` directly, for when
+// the app isn't running (see `cli`). Writes through it skip undo and the trash.
+pub fn open_offline(data_dir: &Path) -> Result<Box<dyn PromptStore>, String> {
+    let settings_path = data_dir.join("settings.json");
+    let settings: serde_json::Map<String, serde_json::Value> =
+        match std::fs::read_to_string(&settings_path) {
+            Ok(raw) => serde_json::from_str(&raw)
+                .map_err(|e| format!("{} is corrupted: {}", settings_path.display(), e))?,
+            Err(_) => serde_json::Map::new(),
+        };
+    let settings = storage_settings(|key| settings.get(key).cloned(), data_dir);
+    open_store(data_dir, &settings)
+}
+
+// Current prompts, in bar order.
+pub fn prompts() -> Vec<Prompt> {
+    LIBRARY
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|library| library.prompts.clone())
+        .unwrap_or_default()
+}
+
+// Replaces the whole library, persists it and tells every window.
+pub fn replace_all(app: &AppHandle, label: &str, prompts: Vec<Prompt>) -> Result<(), String> {
+    modify(app, label, |library| {
+        library.store.save(&prompts)?;
+        library.prompts = prompts;
+        Ok(())
+    })
+}
+
+// Applies `edit` to a copy of the library and saves the result as one
+// undoable step named `label`.
+pub fn update_all(
+    app: &AppHandle,
+    label: &str,
+    edit: impl FnOnce(&mut Vec<Prompt>) -> Result<(), String>,
+) -> Result<(), String> {
+    modify(app, label, |library| {
+        let mut updated = library.prompts.clone();
+        edit(&mut updated)?;
+        if updated != library.prompts {
+            library.store.save(&updated)?;
+            library.prompts = updated;
+        }
+        Ok(())
+    })
+}
+
+// Runs a write against the library and records it as an undoable step named
+// `label`.
+fn modify(
+    app: &AppHandle,
+    label: &str,
+    write: impl FnOnce(&mut Library) -> Result<(), String>,
+) -> Result<(), String> {
+    let (before, after) = apply(app, write)?;
+    if before != after {
+        undo::record(undo::Mutation {
+            label: label.to_string(),
+            before,
+            after,
+        });
+    }
+    Ok(())
+}
+
+// Runs a write against the library: refused while the library failed to load,
+// preceded by a (throttled) backup, followed by moving removed prompts to the
+// trash, and broadcast afterwards. Returns the library before and after.
+fn apply(
+    app: &AppHandle,
+    write: impl FnOnce(&mut Library) -> Result<(), String>,
+) -> Result<(Vec<Prompt>, Vec<Prompt>), String> {
+    let backup_dir = backup_dir(app)?;
+    let trash_path = trash_path(app)?;
+    let (before, after) = {
+        let mut guard = LIBRARY.lock().unwrap();
+        let library = guard
+            .as_mut()
+            .ok_or_else(|| "Prompt library is not initialized".to_string())?;
+        if let Some(e) = &library.load_error {
+            return Err(format!(
+                "The prompt library could not be loaded ({}). Restore a backup before making changes.",
+                e
+            ));
+        }
+        if let Err(e) = backups::snapshot(&backup_dir, &library.prompts, false) {
+            println!("⚠️  Failed to back up prompts: {}", e);
+        }
+        let before = library.prompts.clone();
+        write(library)?;
+        (before, library.prompts.clone())
+    };
+    if let Err(e) = trash::sync(&trash_path, &before, &after) {
+        println!("⚠️  Failed to update the trash: {}", e);
+    }
+    notify_changed(app);
+    Ok((before, after))
+}
+
+fn notify_changed(app: &AppHandle) {
+    if let Err(e) = app.emit("prompts-updated", ()) {
+        println!("⚠️  Failed to emit prompts-updated event: {}", e);
+    }
+}
+
+fn load_storage_settings(app: &AppHandle) -> StorageSettings {
+    let store = app.store("settings.json").ok();
+    let data_dir = data_dir(app).unwrap_or_default();
+    storage_settings(|key| store.as_ref().and_then(|s| s.get(key)), &data_dir)
+}
+
+fn storage_settings(
+    get: impl Fn(&str) -> Option<serde_json::Value>,
+    data_dir: &Path,
+) -> StorageSettings {
+    let backend = get("storageBackend")
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or(StorageBackend::Sqlite);
+    let markdown_directory = get("markdownDirectory")
+        .and_then(|value| value.as_str().map(|s| s.to_string()))
+        .filter(|dir| !dir.trim().is_empty())
+        .unwrap_or_else(|| {
+            data_dir
+                .join("prompts")
+                .to_string_lossy()
+                .into_owned()
+        });
+
+    StorageSettings {
+        backend,
+        markdown_directory,
+    }
+}
+
+fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+fn database_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(data_dir(app)?.join("prompts.db"))
+}
+
+fn backup_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(data_dir(app)?.join("backups"))
+}
+
+fn history_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(data_dir(app)?.join("history"))
+}
+
+fn trash_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(data_dir(app)?.join("trash.json"))
+}
+
+fn purge_expired_trash(app: &AppHandle) -> Result<(), String> {
+    let retention_days = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("trashRetentionDays"))
+        .and_then(|value| value.as_u64())
+        .unwrap_or(trash::DEFAULT_RETENTION_DAYS);
+    trash::purge_expired(&trash_path(app)?, retention_days)
+}
+
+fn open_store(data_dir: &Path, settings: &StorageSettings) -> Result<Box<dyn PromptStore>, String> {
+    match settings.backend {
+        StorageBackend::Sqlite => {
+            let mut store = SqliteStore::open(&data_dir.join("prompts.db"))?;
+            if !store.json_imported()? {
+                let prompts = JsonStore::open(data_dir).load()?;
+                if !prompts.is_empty() && store.load()?.is_empty() {
+                    println!("📦 Importing {} prompts from prompts.json", prompts.len());
+                    store.save(&prompts)?;
+                }
+                store.mark_json_imported()?;
+            }
+            Ok(Box::new(store))
+        }
+        StorageBackend::Json => Ok(Box::new(JsonStore::open(data_dir))),
+        StorageBackend::Markdown => Ok(Box::new(MarkdownStore::open(PathBuf::from(
+            &settings.markdown_directory,
+        ))?)),
+    }
+}
+
+fn open(app: &AppHandle, settings: &StorageSettings) -> Result<(), String> {
+    let (store, open_error) = match open_store(&data_dir(app)?, settings) {
+        Ok(store) => (store, None),
+        Err(e) if settings.backend == StorageBackend::Sqlite && database_path(app)?.exists() => {
+            (recover_database(app)?, Some(e))
+        }
+        Err(e) => return Err(e),
+    };
+    let (prompts, load_error) = match open_error {
+        Some(e) => (Vec::new(), Some(e)),
+        None => match store.load() {
+            Ok(prompts) => (prompts, None),
+            Err(e) => (Vec::new(), Some(e)),
+        },
+    };
+    let watch_path = store.watch_path();
+
+    match &load_error {
+        Some(e) => {
+            println!("❌ Prompt library is unreadable: {}", e);
+            if let Err(e) = app.emit("library-error", e) {
+                println!("⚠️  Failed to emit library-error event: {}", e);
+            }
+        }
+        None => {
+            println!("📚 Loaded {} prompts", prompts.len());
+            if let Err(e) = backups::snapshot(&backup_dir(app)?, &prompts, false) {
+                println!("⚠️  Failed to back up prompts: {}", e);
+            }
+        }
+    }
+
+    *LIBRARY.lock().unwrap() = Some(Library {
+        store,
+        prompts,
+        load_error,
+    });
+    *WATCHER.lock().unwrap() = match watch_path {
+        Some(path) => Some(watch(app, path)?),
+        None => None,
+    };
+    Ok(())
+}
+
+// Moves an unreadable `prompts.db` aside and starts an empty one in its place.
+// The stale `prompts.json` is deliberately not re-imported: the user picks what
+// to restore.
+fn recover_database(app: &AppHandle) -> Result<Box<dyn PromptStore>, String> {
+    let path = database_path(app)?;
+    let moved = backups::quarantine(&backup_dir(app)?, &path)?;
+    println!("🗄️  Moved unreadable {} to {}", path.display(), moved.display());
+
+    let store = SqliteStore::open(&path)?;
+    store.mark_json_imported()?;
+    Ok(Box::new(store))
+}
+
+fn watch(app: &AppHandle, path: PathBuf) -> Result<RecommendedWatcher, String> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+    watcher
+        .watch(&path, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+    println!("👀 Watching {} for prompt changes", path.display());
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        // The loop ends when the watcher (and with it the sender) is dropped.
+        while let Ok(event) = rx.recv() {
+            if let Err(e) = event {
+                println!("⚠️  File watcher error: {}", e);
+                continue;
+            }
+            while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+            reload(&app);
+        }
+    });
+
+    Ok(watcher)
+}
+
+// Re-reads the backend and broadcasts if anything changed. Our own writes come
+// back through the watcher too, but they match the cache and are ignored.
+fn reload(app: &AppHandle) {
+    let changed = {
+        let mut guard = LIBRARY.lock().unwrap();
+        let Some(library) = guard.as_mut() else {
+            return;
+        };
+        match library.store.load() {
+            Ok(prompts) if prompts != library.prompts || library.load_error.is_some() => {
+                // An unreadable library had nothing in it to delete.
+                let before = library
+                    .load_error
+                    .is_none()
+                    .then(|| library.prompts.clone());
+                library.prompts = prompts.clone();
+                library.load_error = None;
+                Some((before, prompts))
+            }
+            Ok(_) => None,
+            Err(e) => {
+                println!("⚠️  Failed to reload prompts: {}", e);
+                None
+            }
+        }
+    };
+
+    if let Some((before, after)) = changed {
+        println!("🔄 Prompts changed on disk, reloading");
+        // Prompts deleted outside the app (a file removed, a `git pull`) can
+        // be restored like any other.
+        if let Some(before) = before {
+            let synced = trash_path(app).and_then(|path| trash::sync(&path, &before, &after));
+            if let Err(e) = synced {
+                println!("⚠️  Failed to update the trash: {}", e);
+            }
+        }
+        notify_changed(app);
+    }
+}
+
+#[tauri::command]
+pub async fn get_prompts() -> Result<Vec<Prompt>, String> {
+    Ok(prompts())
+}
+
+#[tauri::command]
+pub async fn save_prompts(app: AppHandle, prompts: Vec<Prompt>) -> Result<(), String> {
+    println!("💾 Saving {} prompts", prompts.len());
+    replace_all(&app, "Save prompts", prompts)
+}
+
+// Adds a prompt at the end of the library and returns it.
+#[tauri::command]
+pub async fn create_prompt(
+    app: AppHandle,
+    title: Option<String>,
+    content: Option<String>,
+) -> Result<Prompt, String> {
+    let prompt = Prompt {
+        id: uuid::Uuid::new_v4().to_string(),
+        title: title.unwrap_or_else(|| "New Prompt".to_string()),
+        content: content.unwrap_or_default(),
+        color: prompts::DEFAULT_COLOR.to_string(),
+        abbreviation: None,
+        tags: Vec::new(),
+        folder: None,
+    };
+    println!("➕ Creating prompt {}", prompt.id);
+    let created = prompt.clone();
+    update_all(&app, "Create prompt", |prompts| {
+        prompts.push(prompt);
+        Ok(())
+    })?;
+    Ok(created)
+}
+
+// Moves a prompt to the trash.
+#[tauri::command]
+pub async fn delete_prompt(app: AppHandle, id: String) -> Result<(), String> {
+    let prompt = current_prompt(&id)?;
+    println!("🗑️  Deleting prompt {}", id);
+    update_all(&app, &format!("Delete “{}”", prompt.title), |prompts| {
+        prompts.retain(|p| p.id != id);
+        Ok(())
+    })
+}
+
+// Moves a prompt to `to_index` (clamped to the end of the library).
+#[tauri::command]
+pub async fn move_prompt(app: AppHandle, id: String, to_index: usize) -> Result<(), String> {
+    let prompt = current_prompt(&id)?;
+    println!("↔️  Moving prompt {} to position {}", id, to_index + 1);
+    update_all(&app, &format!("Move “{}”", prompt.title), |prompts| {
+        let from = prompts
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| format!("No prompt with id '{}'", id))?;
+        let prompt = prompts.remove(from);
+        prompts.insert(to_index.min(prompts.len()), prompt);
+        Ok(())
+    })
+}
+
+// `author` defaults to the OS user.
+#[tauri::command]
+pub async fn update_prompt(
+    app: AppHandle,
+    prompt: Prompt,
+    author: Option<String>,
+) -> Result<(), String> {
+    println!("💾 Updating prompt {}", prompt.id);
+    edit_prompt(&app, prompt, author)
+}
+
+// Saves an edited prompt, normalizing its folder and tags.
+pub fn edit_prompt(app: &AppHandle, prompt: Prompt, author: Option<String>) -> Result<(), String> {
+    let prompt = Prompt {
+        folder: prompt.folder.as_deref().and_then(prompts::normalize_folder),
+        tags: prompts::normalize_tags(&prompt.tags),
+        ..prompt
+    };
+    update_one(app, prompt, author, None)
+}
+
+// Saves one prompt and records the change in its history.
+fn update_one(
+    app: &AppHandle,
+    prompt: Prompt,
+    author: Option<String>,
+    reverted_from: Option<u32>,
+) -> Result<(), String> {
+    let history_dir = history_dir(app)?;
+    let label = match reverted_from {
+        Some(revision) => format!("Revert “{}” to revision {}", prompt.title, revision),
+        None => format!("Edit “{}”", prompt.title),
+    };
+    modify(app, &label, |library| {
+        let index = library
+            .prompts
+            .iter()
+            .position(|p| p.id == prompt.id)
+            .ok_or_else(|| format!("No prompt with id '{}'", prompt.id))?;
+
+        let mut updated = library.prompts.clone();
+        let previous = std::mem::replace(&mut updated[index], prompt.clone());
+        library.store.save_prompt(&updated, index)?;
+        library.prompts = updated;
+
+        if let Err(e) = history::record(&history_dir, &previous, &prompt, author, reverted_from) {
+            println!("⚠️  Failed to record history for {}: {}", prompt.id, e);
+        }
+        Ok(())
+    })
+}
+
+pub fn current_prompt(id: &str) -> Result<Prompt, String> {
+    prompts()
+        .into_iter()
+        .find(|p| p.id == id)
+        .ok_or_else(|| format!("No prompt with id '{}'", id))
+}
+
+// Revisions of a prompt, oldest first, ending with its current state.
+#[tauri::command]
+pub async fn prompt_history(app: AppHandle, id: String) -> Result<Vec<Revision>, String> {
+    history::with_current(&history_dir(&app)?, &current_prompt(&id)?)
+}
+
+#[tauri::command]
+pub async fn diff_prompt_revisions(
+    app: AppHandle,
+    id: String,
+    from: u32,
+    to: u32,
+) -> Result<PromptDiff, String> {
+    let revisions = history::with_current(&history_dir(&app)?, &current_prompt(&id)?)?;
+    Ok(history::diff(
+        history::find(&revisions, from)?,
+        history::find(&revisions, to)?,
+    ))
+}
+
+// Brings back an earlier revision. This is itself a new revision, so a revert
+// can be reverted too.
+#[tauri::command]
+pub async fn revert_prompt(
+    app: AppHandle,
+    id: String,
+    revision: u32,
+    author: Option<String>,
+) -> Result<(), String> {
+    println!("⏪ Reverting prompt {} to revision {}", id, revision);
+    let revisions = history::with_current(&history_dir(&app)?, &current_prompt(&id)?)?;
+    let mut prompt = history::find(&revisions, revision)?.prompt.clone();
+    prompt.id = id;
+    update_one(&app, prompt, author, Some(revision))
+}
+
+#[tauri::command]
+pub async fn search_prompts(query: String) -> Result<Vec<SearchHit>, String> {
+    search(&query)
+}
+
+pub fn search(query: &str) -> Result<Vec<SearchHit>, String> {
+    let guard = LIBRARY.lock().unwrap();
+    let library = guard
+        .as_ref()
+        .ok_or_else(|| "Prompt library is not initialized".to_string())?;
+    library.store.search(query)
+}
+
+// Why the library couldn't be loaded, if it couldn't.
+#[tauri::command]
+pub async fn get_library_error() -> Result<Option<String>, String> {
+    Ok(LIBRARY
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|library| library.load_error.clone()))
+}
+
+#[tauri::command]
+pub async fn list_backups(app: AppHandle) -> Result<Vec<BackupInfo>, String> {
+    Ok(backups::list(&backup_dir(&app)?))
+}
+
+// Replaces the library with a backup. The current library is backed up first,
+// or moved aside if it couldn't be read, so a restore can itself be undone.
+#[tauri::command]
+pub async fn restore_backup(app: AppHandle, name: String) -> Result<(), String> {
+    println!("🗄️  Restoring backup {}", name);
+    let backup_dir = backup_dir(&app)?;
+    let prompts = backups::load(&backup_dir, &name)?;
+    let before = {
+        let mut guard = LIBRARY.lock().unwrap();
+        let library = guard
+            .as_mut()
+            .ok_or_else(|| "Prompt library is not initialized".to_string())?;
+        let before = if library.load_error.is_some() {
+            library.store.quarantine(&backup_dir)?;
+            None
+        } else {
+            backups::snapshot(&backup_dir, &library.prompts, true)?;
+            Some(library.prompts.clone())
+        };
+        library.store.save(&prompts)?;
+        library.prompts = prompts.clone();
+        library.load_error = None;
+        before
+    };
+
+    // Restoring over an unreadable library has nothing to go back to.
+    match before {
+        Some(before) => {
+            if let Err(e) = trash::sync(&trash_path(&app)?, &before, &prompts) {
+                println!("⚠️  Failed to update the trash: {}", e);
+            }
+            undo::record(undo::Mutation {
+                label: "Restore backup".to_string(),
+                before,
+                after: prompts,
+            });
+        }
+        None => undo::clear(),
+    }
+    notify_changed(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_storage_settings(app: AppHandle) -> Result<StorageSettings, String> {
+    Ok(load_storage_settings(&app))
+}
+
+// Switches storage backends. If the new backend has no prompts yet, the
+// current library is copied into it so switching never loses anything.
+#[tauri::command]
+pub async fn set_storage_backend(
+    app: AppHandle,
+    backend: StorageBackend,
+    markdown_directory: Option<String>,
+) -> Result<StorageSettings, String> {
+    println!("🔧 Switching storage backend to {:?}", backend);
+
+    let mut settings = load_storage_settings(&app);
+    settings.backend = backend;
+    if let Some(dir) = markdown_directory.filter(|dir| !dir.trim().is_empty()) {
+        settings.markdown_directory = dir;
+    }
+
+    let current = prompts();
+    let mut store = open_store(&data_dir(&app)?, &settings)?;
+    if store.load()?.is_empty() && !current.is_empty() {
+        println!("📦 Copying {} prompts into the new backend", current.len());
+        store.save(&current)?;
+    }
+    drop(store);
+
+    let settings_store = app
+        .store("settings.json")
+        .map_err(|e| format!("Failed to load settings: {}", e))?;
+    settings_store.set("storageBackend", serde_json::to_value(settings.backend).unwrap_or_default());
+    settings_store.set("markdownDirectory", settings.markdown_directory.clone());
+    settings_store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    open(&app, &settings)?;
+    // Recorded steps belong to the previous backend's library.
+    undo::clear();
+    notify_changed(&app);
+    Ok(settings)
+}
+
+// Deleted prompts, most recently deleted first. Expired ones are purged first.
+#[tauri::command]
+pub async fn list_trash(app: AppHandle) -> Result<Vec<TrashedPrompt>, String> {
+    purge_expired_trash(&app)?;
+    let mut items = trash::load(&trash_path(&app)?)?;
+    items.reverse();
+    Ok(items)
+}
+
+// Puts a deleted prompt back where it was, as far as the library still allows.
+#[tauri::command]
+pub async fn restore_from_trash(app: AppHandle, id: String) -> Result<Prompt, String> {
+    let item = trash::load(&trash_path(&app)?)?
+        .into_iter()
+        .find(|item| item.prompt.id == id)
+        .ok_or_else(|| format!("No prompt with id '{}' in the trash", id))?;
+    println!("♻️  Restoring prompt {} from the trash", id);
+    let prompt = item.prompt.clone();
+    update_all(&app, &format!("Restore “{}”", prompt.title), |prompts| {
+        if prompts.iter().any(|p| p.id == id) {
+            return Err(format!("A prompt with id '{}' already exists", id));
+        }
+        prompts.insert(item.index.min(prompts.len()), item.prompt);
+        Ok(())
+    })?;
+    Ok(prompt)
+}
+
+// Permanently deletes one prompt from the trash, or empties it when `id` is
+// missing. This can't be undone.
+#[tauri::command]
+pub async fn purge_trash(app: AppHandle, id: Option<String>) -> Result<(), String> {
+    match &id {
+        Some(id) => println!("🗑️  Purging prompt {} from the trash", id),
+        None => println!("🗑️  Emptying the trash"),
+    }
+    trash::purge(&trash_path(&app)?, id.as_deref())
+}
+
+// Recorded steps only apply to the library they were recorded on. If it was
+// changed some other way since (edited outside the app, backend switched) they
+// are dropped.
+const UNDO_STALE: &str = "The prompt library was changed elsewhere; there is nothing left to undo or redo";
+
+#[tauri::command]
+pub async fn undo(app: AppHandle) -> Result<UndoState, String> {
+    let mutation = undo::pop_undo().ok_or_else(|| "Nothing to undo".to_string())?;
+    if prompts() != mutation.after {
+        undo::clear();
+        return Err(UNDO_STALE.to_string());
+    }
+    println!("↩️  Undoing {}", mutation.label);
+    if let Err(e) = restore_state(&app, &mutation.before) {
+        undo::push_undo(mutation);
+        return Err(e);
+    }
+    undo::push_redo(mutation);
+    Ok(undo::state())
+}
+
+#[tauri::command]
+pub async fn redo(app: AppHandle) -> Result<UndoState, String> {
+    let mutation = undo::pop_redo().ok_or_else(|| "Nothing to redo".to_string())?;
+    if prompts() != mutation.before {
+        undo::clear();
+        return Err(UNDO_STALE.to_string());
+    }
+    println!("↪️  Redoing {}", mutation.label);
+    if let Err(e) = restore_state(&app, &mutation.after) {
+        undo::push_redo(mutation);
+        return Err(e);
+    }
+    undo::push_undo(mutation);
+    Ok(undo::state())
+}
+
+#[tauri::command]
+pub async fn get_undo_state() -> Result<UndoState, String> {
+    Ok(undo::state())
+}
+
+// Puts the library back into a recorded state without recording a new step.
+fn restore_state(app: &AppHandle, prompts: &[Prompt]) -> Result<(), String> {
+    apply(app, |library| {
+        library.store.save(prompts)?;
+        library.prompts = prompts.to_vec();
+        Ok(())
+    })
+    .map(|_| ())
+}
+
+fn substring_search(prompts: &[Prompt], query: &str) -> Vec<SearchHit> {
+    let terms: Vec<String> = query.split_whitespace().map(|t| t.to_lowercase()).collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    let mut hits: Vec<SearchHit> = prompts
+        .iter()
+        .enumerate()
+        .filter_map(|(index, prompt)| {
+            let title = prompt.title.to_lowercase();
+            let content = prompt.content.to_lowercase();
+            let tags = prompt.tags.join(" ").to_lowercase();
+
+            let mut score = 0.0;
+            for term in &terms {
+                let term = term.as_str();
+                let weight = if title.contains(term) {
+                    10.0
+                } else if tags.contains(term) {
+                    5.0
+                } else if content.contains(term) {
+                    1.0
+                } else {
+                    return None;
+                };
+                score += weight;
+            }
+            Some(SearchHit {
+                prompt: prompt.clone(),
+                index,
+                snippet: highlight(&prompt.content, &terms[0]),
+                score,
+            })
+        })
+        .collect();
+
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits
+}
+
+// Marks the first occurrence of `term` in `text` and trims to a short window
+// around it.
+fn highlight(text: &str, term: &str) -> String {
+    const CONTEXT: usize = 40;
+
+    let lower = text.to_lowercase();
+    // Lowercasing can change byte lengths; only highlight when it didn't.
+    let Some(start) = lower.find(term).filter(|&start| {
+        lower.len() == text.len()
+            && text.is_char_boundary(start)
+            && text.is_char_boundary(start + term.len())
+    }) else {
+        return text.chars().take(CONTEXT * 2).collect();
+    };
+    let end = start + term.len();
+
+    let before: String = {
+        let chars: Vec<char> = text[..start].chars().collect();
+        let skip = chars.len().saturating_sub(CONTEXT);
+        let prefix = if skip > 0 { "…" } else { "" };
+        format!("{}{}", prefix, chars[skip..].iter().collect::<String>())
+    };
+    let after: String = {
+        let rest = &text[end..];
+        let mut after: String = rest.chars().take(CONTEXT).collect();
+        if after.len() < rest.len() {
+            after.push('…');
+        }
+        after
+    };
+    format!("{}<mark>{}</mark>{}", before, &text[start..end], after)
+}
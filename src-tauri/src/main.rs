@@ -2,5 +2,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // `prompt-buddy <command>` is the command-line companion, see `cli`.
+    if let Some(code) = prompt_picker_lib::cli::run_from_args() {
+        std::process::exit(code);
+    }
     prompt_picker_lib::run()
 }
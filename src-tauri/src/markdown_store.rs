@@ -0,0 +1,425 @@
+// Markdown storage backend: one `.md` file per prompt, with YAML front matter
+// for everything but the content, so a library can be grepped, reviewed and
+// committed like any other text.
+//
+//     ---
+//     id: 6f1c...
+//     title: Code review
+//     color: from-blue-500 to-cyan-500
+//     shortcut: cmd+alt+1
+//     tags: [review]
+//     folder: Work/Reviews
+//     ---
+//
+//     Review the following code...
+//
+// `shortcut` is the bar slot (cmd+alt+1-9) and decides the order; prompts
+// past the ninth carry an `order` instead, and files with neither come last,
+// sorted by file name. Files written by hand may leave out
+// any field; a missing `id` or `title` falls back to the file stem.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backups;
+use crate::library::PromptStore;
+use crate::prompts::{self, Prompt, DEFAULT_COLOR};
+use crate::shortcuts;
+
+// Number of prompts reachable through cmd+alt+1-9.
+const SHORTCUT_SLOTS: usize = 9;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FrontMatter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    shortcut: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    order: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    abbreviation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    folder: Option<String>,
+}
+
+struct PromptFile {
+    path: PathBuf,
+    prompt: Prompt,
+    position: Option<usize>,
+}
+
+pub struct MarkdownStore {
+    dir: PathBuf,
+    // Ids of the prompts last loaded or saved. Saving only removes files of
+    // these, so a file that turned up in the meantime (a `git pull`, a copy
+    // from another machine) isn't deleted before the library has seen it.
+    known: Mutex<HashSet<String>>,
+}
+
+impl MarkdownStore {
+    pub fn open(dir: PathBuf) -> Result<Self, String> {
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        Ok(Self {
+            dir,
+            known: Mutex::new(HashSet::new()),
+        })
+    }
+
+    // Every parseable prompt file in the directory, in bar order.
+    fn read_all(&self) -> Result<Vec<PromptFile>, String> {
+        let entries = fs::read_dir(&self.dir)
+            .map_err(|e| format!("Failed to read {}: {}", self.dir.display(), e))?;
+
+        let mut files = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !is_prompt_file(&path) {
+                continue;
+            }
+            match read_prompt_file(&path) {
+                Ok(file) => files.push(file),
+                // Leave broken files alone so a half-finished edit doesn't
+                // take the whole library down.
+                Err(e) => println!("⚠️  Skipping {}: {}", path.display(), e),
+            }
+        }
+
+        files.sort_by(|a, b| {
+            let position = |f: &PromptFile| f.position.unwrap_or(usize::MAX);
+            position(a).cmp(&position(b)).then_with(|| a.path.cmp(&b.path))
+        });
+        Ok(files)
+    }
+
+    fn unused_path(
+        &self,
+        title: &str,
+        existing: &HashMap<String, PathBuf>,
+        used: &HashSet<PathBuf>,
+    ) -> PathBuf {
+        let base = slugify(title);
+        let taken = |path: &PathBuf| {
+            used.contains(path) || existing.values().any(|p| p == path) || path.exists()
+        };
+
+        let mut path = self.dir.join(format!("{}.md", base));
+        let mut n = 2;
+        while taken(&path) {
+            path = self.dir.join(format!("{}-{}.md", base, n));
+            n += 1;
+        }
+        path
+    }
+}
+
+impl PromptStore for MarkdownStore {
+    fn load(&self) -> Result<Vec<Prompt>, String> {
+        let mut seen = HashSet::new();
+        let prompts: Vec<Prompt> = self
+            .read_all()?
+            .into_iter()
+            .filter(|file| {
+                let unique = seen.insert(file.prompt.id.clone());
+                if !unique {
+                    println!("⚠️  Ignoring {}: duplicate id '{}'", file.path.display(), file.prompt.id);
+                }
+                unique
+            })
+            .map(|file| file.prompt)
+            .collect();
+        *self.known.lock().unwrap() = seen;
+        Ok(prompts)
+    }
+
+    fn save(&mut self, prompts: &[Prompt]) -> Result<(), String> {
+        // Keep prompts in the files they already live in, even if renamed.
+        let existing: HashMap<String, PathBuf> = self
+            .read_all()?
+            .into_iter()
+            .map(|file| (file.prompt.id, file.path))
+            .collect();
+
+        let mut used: HashSet<PathBuf> = HashSet::new();
+        for (index, prompt) in prompts.iter().enumerate() {
+            let path = match existing.get(&prompt.id) {
+                Some(path) if !used.contains(path) => path.clone(),
+                _ => self.unused_path(&prompt.title, &existing, &used),
+            };
+
+            let contents = render(prompt, index)?;
+            // Untouched files are not rewritten, so saving doesn't churn
+            // mtimes or wake up the watcher for nothing.
+            if fs::read_to_string(&path).ok().as_deref() != Some(contents.as_str()) {
+                backups::write_atomic(&path, contents.as_bytes())?;
+            }
+            used.insert(path);
+        }
+
+        let mut known = self.known.lock().unwrap();
+        let removed = existing
+            .iter()
+            .filter(|(id, path)| known.contains(*id) && !used.contains(*path));
+        for (_, path) in removed {
+            fs::remove_file(path)
+                .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        }
+        *known = prompts.iter().map(|p| p.id.clone()).collect();
+        Ok(())
+    }
+
+    fn watch_path(&self) -> Option<PathBuf> {
+        Some(self.dir.clone())
+    }
+}
+
+fn is_prompt_file(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.starts_with('.'))
+        .unwrap_or(true);
+    !hidden && path.is_file() && path.extension().and_then(|ext| ext.to_str()) == Some("md")
+}
+
+fn read_prompt_file(path: &Path) -> Result<PromptFile, String> {
+    let raw = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let (front, content) = parse(&raw)?;
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("prompt")
+        .to_string();
+
+    let position = match front.shortcut.as_deref() {
+        Some(shortcut) => shortcut_slot(shortcut),
+        None => front.order,
+    };
+    let prompt = Prompt {
+        id: front.id.unwrap_or_else(|| stem.clone()),
+        title: front.title.unwrap_or(stem),
+        content,
+        color: front.color.unwrap_or_else(|| DEFAULT_COLOR.to_string()),
+        abbreviation: front.abbreviation,
+        tags: front.tags,
+        folder: front.folder.as_deref().and_then(prompts::normalize_folder),
+    };
+    Ok(PromptFile {
+        path: path.to_path_buf(),
+        prompt,
+        position,
+    })
+}
+
+// Splits a file into its front matter and content. Files without front matter
+// are plain prompts.
+fn parse(raw: &str) -> Result<(FrontMatter, String), String> {
+    let raw = raw.replace("\r\n", "\n");
+    let Some(rest) = raw.strip_prefix("---\n") else {
+        return Ok((FrontMatter::default(), raw.trim_end_matches('\n').to_string()));
+    };
+
+    let (yaml, body) = if let Some(body) = rest.strip_prefix("---\n") {
+        ("", body)
+    } else if let Some(end) = rest.find("\n---\n") {
+        (&rest[..end], &rest[end + "\n---\n".len()..])
+    } else if let Some(yaml) = rest.strip_suffix("\n---") {
+        (yaml, "")
+    } else {
+        return Err("front matter is not closed with ---".to_string());
+    };
+
+    let front: FrontMatter = if yaml.trim().is_empty() {
+        FrontMatter::default()
+    } else {
+        serde_yaml::from_str(yaml).map_err(|e| format!("invalid front matter: {}", e))?
+    };
+
+    let body = body.strip_prefix('\n').unwrap_or(body);
+    let content = body.strip_suffix('\n').unwrap_or(body).to_string();
+    Ok((front, content))
+}
+
+fn render(prompt: &Prompt, index: usize) -> Result<String, String> {
+    let has_slot = index < SHORTCUT_SLOTS;
+    let front = FrontMatter {
+        id: Some(prompt.id.clone()),
+        title: Some(prompt.title.clone()),
+        color: Some(prompt.color.clone()),
+        shortcut: has_slot.then(|| shortcuts::prompt_shortcut(index)),
+        order: (!has_slot).then_some(index),
+        tags: prompt.tags.clone(),
+        abbreviation: prompt.abbreviation.clone(),
+        folder: prompt.folder.clone(),
+    };
+    let yaml = serde_yaml::to_string(&front)
+        .map_err(|e| format!("Failed to serialize front matter: {}", e))?;
+    Ok(format!("---\n{}---\n\n{}\n", yaml, prompt.content))
+}
+
+// Maps `cmd+alt+3` (in any accepted spelling) to slot 2.
+fn shortcut_slot(shortcut: &str) -> Option<usize> {
+    let normalized = shortcuts::normalize_shortcut(shortcut).ok()?;
+    (0..SHORTCUT_SLOTS).find(|&i| shortcuts::prompt_shortcut(i) == normalized)
+}
+
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.trim_end_matches('-').chars().take(60).collect();
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "prompt".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(id: &str, title: &str) -> Prompt {
+        Prompt {
+            id: id.to_string(),
+            title: title.to_string(),
+            content: format!("Content of {}", title),
+            color: DEFAULT_COLOR.to_string(),
+            abbreviation: None,
+            tags: Vec::new(),
+            folder: None,
+        }
+    }
+
+    fn temp_store() -> MarkdownStore {
+        let dir = std::env::temp_dir().join(format!("prompt-buddy-md-{}", uuid::Uuid::new_v4()));
+        MarkdownStore::open(dir).unwrap()
+    }
+
+    #[test]
+    fn parses_front_matter_and_content() {
+        let (front, content) = parse(
+            "---\nid: abc\ntitle: Review\ntags: [rust, review]\nshortcut: cmd+alt+2\n---\n\nLine one\nLine two\n",
+        )
+        .unwrap();
+        assert_eq!(front.id.as_deref(), Some("abc"));
+        assert_eq!(front.title.as_deref(), Some("Review"));
+        assert_eq!(front.tags, ["rust", "review"]);
+        assert_eq!(front.shortcut.as_deref(), Some("cmd+alt+2"));
+        assert_eq!(content, "Line one\nLine two");
+    }
+
+    #[test]
+    fn parses_files_without_front_matter() {
+        let (front, content) = parse("Just a prompt\n\n").unwrap();
+        assert!(front.id.is_none() && front.title.is_none());
+        assert_eq!(content, "Just a prompt");
+        // A rule further down is content.
+        let (_, content) = parse("Intro\n---\nMore").unwrap();
+        assert_eq!(content, "Intro\n---\nMore");
+    }
+
+    #[test]
+    fn parses_empty_and_trailing_front_matter() {
+        let (front, content) = parse("---\n---\nBody").unwrap();
+        assert!(front.id.is_none());
+        assert_eq!(content, "Body");
+        let (front, content) = parse("---\ntitle: Only\n---").unwrap();
+        assert_eq!(front.title.as_deref(), Some("Only"));
+        assert_eq!(content, "");
+    }
+
+    #[test]
+    fn parses_windows_line_endings() {
+        let (front, content) = parse("---\r\ntitle: Win\r\n---\r\n\r\nA\r\nB\r\n").unwrap();
+        assert_eq!(front.title.as_deref(), Some("Win"));
+        assert_eq!(content, "A\nB");
+    }
+
+    #[test]
+    fn rejects_broken_front_matter() {
+        assert!(parse("---\ntitle: Open\n\nBody").is_err());
+        assert!(parse("---\ntitle: [unclosed\n---\nBody").is_err());
+        assert!(parse("---\ntags: not-a-list\n---\nBody").is_err());
+    }
+
+    #[test]
+    fn renders_what_it_parses() {
+        let mut original = prompt("abc", "Review: \"quotes\" & #hash");
+        original.content = "---\nContent with a rule\n".to_string();
+        original.tags = vec!["rust".to_string()];
+        original.folder = Some("Work/Reviews".to_string());
+        let (front, content) = parse(&render(&original, 2).unwrap()).unwrap();
+        assert_eq!(front.id.as_deref(), Some("abc"));
+        assert_eq!(front.title.as_deref(), Some(original.title.as_str()));
+        assert_eq!(front.shortcut.as_deref(), Some("cmd+alt+3"));
+        assert_eq!(front.order, None);
+        assert_eq!(front.tags, original.tags);
+        assert_eq!(front.folder, original.folder);
+        assert_eq!(content, original.content);
+
+        let (front, _) = parse(&render(&original, 12).unwrap()).unwrap();
+        assert_eq!(front.shortcut, None);
+        assert_eq!(front.order, Some(12));
+    }
+
+    #[test]
+    fn maps_shortcuts_to_slots() {
+        assert_eq!(shortcut_slot("cmd+alt+1"), Some(0));
+        assert_eq!(shortcut_slot("Option+Command+9"), Some(8));
+        assert_eq!(shortcut_slot("ctrl+alt+1"), None);
+        assert_eq!(shortcut_slot("nonsense"), None);
+    }
+
+    #[test]
+    fn slugifies_titles() {
+        assert_eq!(slugify("Code Review: Rust!"), "code-review-rust");
+        assert_eq!(slugify("  Ünïcode  титул "), "ünïcode-титул");
+        assert_eq!(slugify("!!!"), "prompt");
+        assert_eq!(slugify(&"a".repeat(80)).len(), 60);
+    }
+
+    #[test]
+    fn saves_and_loads_in_order() {
+        let mut store = temp_store();
+        let prompts: Vec<Prompt> = (0..11)
+            .map(|i| prompt(&format!("id{}", i), &format!("Prompt {}", i)))
+            .collect();
+        store.save(&prompts).unwrap();
+        assert_eq!(store.load().unwrap(), prompts);
+        fs::remove_dir_all(&store.dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_files_it_has_not_seen() {
+        let mut store = temp_store();
+        store
+            .save(&[prompt("a", "Alpha"), prompt("b", "Beta")])
+            .unwrap();
+        // A file arrives behind the store's back, e.g. from a `git pull`.
+        fs::write(store.dir.join("pulled.md"), "---\nid: c\n---\nPulled").unwrap();
+
+        store.save(&[prompt("a", "Alpha")]).unwrap();
+        let ids: Vec<String> = store.load().unwrap().into_iter().map(|p| p.id).collect();
+        assert_eq!(ids, ["a", "c"]);
+        assert!(!store.dir.join("beta.md").exists());
+        fs::remove_dir_all(&store.dir).unwrap();
+    }
+}
@@ -0,0 +1,306 @@
+// Model Context Protocol server on stdin/stdout (`prompt-buddy mcp`), so MCP
+// clients such as Cursor or Claude Desktop can use the library:
+//
+//     { "mcpServers": { "prompt-buddy": { "command": "prompt-buddy", "args": ["mcp"] } } }
+//
+// Every prompt is an MCP prompt named after its title, with its template
+// variables as arguments (required unless they have a default), and the tools
+// `search_prompts` and `add_prompt` search and grow the library. Like the rest
+// of the command line, calls go to the running app when there is one and to
+// the store files otherwise (see `cli`).
+//
+// Messages are JSON-RPC 2.0, one per line, and stdout carries nothing else
+// (see `cli::protocol_output`).
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::cli;
+use crate::ipc::{self, Request, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR};
+use crate::prompts::Prompt;
+use crate::template;
+
+// Newest first; a client asking for another version is offered the newest.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+// Longest prompt description, taken from the first line of the content.
+const DESCRIPTION_LENGTH: usize = 120;
+
+// Serves until stdin is closed.
+pub fn serve() -> Result<(), String> {
+    eprintln!("prompt-buddy: serving MCP on stdio");
+    let mut output = cli::protocol_output()?;
+    for line in std::io::stdin().lock().lines() {
+        let line = line.map_err(|e| format!("Failed to read stdin: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = reply(&line) {
+            writeln!(output, "{}", response)
+                .and_then(|_| output.flush())
+                .map_err(|e| format!("Failed to write stdout: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+// The answer to one message, if it needs one.
+fn reply(line: &str) -> Option<Value> {
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(e) => return Some(error(Value::Null, PARSE_ERROR, e.to_string())),
+    };
+    let id = message.get("id").cloned();
+    let method = message.get("method").and_then(Value::as_str);
+    if message.get("jsonrpc") != Some(&json!("2.0")) || method.is_none() {
+        // Answers to requests we never send, or garbage.
+        return id.map(|id| error(id, INVALID_REQUEST, "Not a JSON-RPC 2.0 request".into()));
+    }
+    // Notifications such as `notifications/initialized` need nothing from us.
+    let id = id?;
+    let params = message.get("params").cloned().unwrap_or(json!({}));
+    Some(match call(method.unwrap_or_default(), params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error(id, code, message),
+    })
+}
+
+fn error(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn call(method: &str, params: Value) -> Result<Value, (i64, String)> {
+    match method {
+        "initialize" => {
+            let requested = params["protocolVersion"].as_str().unwrap_or_default();
+            let version = PROTOCOL_VERSIONS
+                .iter()
+                .find(|v| **v == requested)
+                .unwrap_or(&PROTOCOL_VERSIONS[0]);
+            Ok(json!({
+                "protocolVersion": version,
+                "capabilities": { "prompts": {}, "tools": {} },
+                "serverInfo": { "name": "prompt-buddy", "version": env!("CARGO_PKG_VERSION") },
+            }))
+        }
+        "ping" => Ok(json!({})),
+        "prompts/list" => {
+            let prompts: Vec<Value> = named(load()?)
+                .iter()
+                .map(|(name, prompt)| describe(name, prompt))
+                .collect();
+            Ok(json!({ "prompts": prompts }))
+        }
+        "prompts/get" => {
+            let name = string_param(&params, "name")?;
+            let arguments: HashMap<String, String> =
+                serde_json::from_value(params.get("arguments").cloned().unwrap_or(json!({})))
+                    .map_err(|e| (INVALID_PARAMS, format!("Invalid arguments: {}", e)))?;
+            let prompts = load()?;
+            let prompt = match named(prompts.clone()).into_iter().find(|(n, _)| *n == name) {
+                Some((_, prompt)) => prompt,
+                // Ids and titles work too.
+                None => ipc::find_prompt(&prompts, &name).map_err(|e| (INVALID_PARAMS, e))?,
+            };
+            // Rendered where the prompt lives, as it may run shell commands.
+            let text = cli::execute(Request::Render {
+                prompt: prompt.id.clone(),
+                vars: arguments,
+            })
+            .map_err(|e| (INVALID_PARAMS, e))?;
+            Ok(json!({
+                "description": prompt.title,
+                "messages": [{ "role": "user", "content": { "type": "text", "text": text } }],
+            }))
+        }
+        "tools/list" => Ok(json!({ "tools": tools() })),
+        "tools/call" => {
+            let name = string_param(&params, "name")?;
+            let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+            let result = match name.as_str() {
+                "search_prompts" => search(arguments),
+                "add_prompt" => add(arguments),
+                _ => return Err((INVALID_PARAMS, format!("Unknown tool {}", name))),
+            };
+            // Failed tools are results, so the model gets to see why.
+            Ok(match result {
+                Ok(text) => json!({ "content": [{ "type": "text", "text": text }] }),
+                Err(e) => json!({ "content": [{ "type": "text", "text": e }], "isError": true }),
+            })
+        }
+        _ => Err((METHOD_NOT_FOUND, format!("Unknown method {}", method))),
+    }
+}
+
+fn string_param(params: &Value, name: &str) -> Result<String, (i64, String)> {
+    params[name]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| (INVALID_PARAMS, format!("Missing {}", name)))
+}
+
+fn load() -> Result<Vec<Prompt>, (i64, String)> {
+    cli::execute(Request::List {})
+        .and_then(|value| serde_json::from_value(value).map_err(|e| e.to_string()))
+        .map_err(|e| (ipc::METHOD_FAILED, e))
+}
+
+// MCP names for `prompts`, in bar order: the title in kebab case, numbered
+// when titles collide.
+fn named(prompts: Vec<Prompt>) -> Vec<(String, Prompt)> {
+    let mut named: Vec<(String, Prompt)> = Vec::with_capacity(prompts.len());
+    for prompt in prompts {
+        let base = slug(&prompt.title);
+        let mut name = base.clone();
+        let mut n = 2;
+        while named.iter().any(|(existing, _)| *existing == name) {
+            name = format!("{}-{}", base, n);
+            n += 1;
+        }
+        named.push((name, prompt));
+    }
+    named
+}
+
+fn slug(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "prompt".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+fn describe(name: &str, prompt: &Prompt) -> Value {
+    let arguments: Vec<Value> = template::variables(&prompt.content)
+        .into_iter()
+        .map(|variable| {
+            let mut argument = json!({
+                "name": variable.name,
+                "required": variable.default.is_none(),
+            });
+            if let Some(default) = variable.default {
+                argument["description"] = json!(format!("Defaults to “{}”", default));
+            }
+            argument
+        })
+        .collect();
+    let first_line = prompt.content.lines().find(|l| !l.trim().is_empty());
+    let mut description: String = first_line
+        .unwrap_or_default()
+        .trim()
+        .chars()
+        .take(DESCRIPTION_LENGTH)
+        .collect();
+    if first_line.is_some_and(|l| l.trim().chars().count() > DESCRIPTION_LENGTH) {
+        description.push('…');
+    }
+    json!({
+        "name": name,
+        "title": prompt.title,
+        "description": description,
+        "arguments": arguments,
+    })
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "search_prompts",
+            "title": "Search prompts",
+            "description": "Full-text search of the Prompt Buddy library. Returns the names \
+                            to use with prompts/get, best matches first.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "query": { "type": "string", "description": "Search terms" } },
+                "required": ["query"],
+            },
+        },
+        {
+            "name": "add_prompt",
+            "title": "Add prompt",
+            "description": "Adds a prompt to the Prompt Buddy library. {{name}} and \
+                            {{name|default}} in the content become arguments.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "title": { "type": "string" },
+                    "content": { "type": "string" },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "folder": { "type": "string", "description": "e.g. Work/Reviews" },
+                },
+                "required": ["title", "content"],
+            },
+        },
+    ])
+}
+
+fn search(arguments: Value) -> Result<String, String> {
+    let query = arguments["query"]
+        .as_str()
+        .filter(|query| !query.trim().is_empty())
+        .ok_or_else(|| "Missing query".to_string())?;
+    let hits = cli::execute(Request::Search {
+        query: query.to_string(),
+    })?;
+    let named = named(load().map_err(|(_, e)| e)?);
+    let hits: Vec<Value> = hits
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|hit| {
+            let id = hit["prompt"]["id"].as_str()?;
+            let (name, prompt) = named.iter().find(|(_, p)| p.id == id)?;
+            Some(json!({ "name": name, "title": prompt.title, "snippet": hit["snippet"] }))
+        })
+        .collect();
+    if hits.is_empty() {
+        return Ok(format!("No prompts match “{}”", query));
+    }
+    serde_json::to_string_pretty(&hits).map_err(|e| e.to_string())
+}
+
+fn add(arguments: Value) -> Result<String, String> {
+    #[derive(serde::Deserialize)]
+    struct Arguments {
+        title: String,
+        content: String,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        folder: Option<String>,
+    }
+    let Arguments {
+        title,
+        content,
+        tags,
+        folder,
+    } = serde_json::from_value(arguments).map_err(|e| format!("Invalid arguments: {}", e))?;
+    if title.trim().is_empty() {
+        return Err("The title is empty".to_string());
+    }
+    let created: Prompt = serde_json::from_value(cli::execute(Request::Create {
+        title,
+        content,
+        tags,
+        folder,
+    })?)
+    .map_err(|e| e.to_string())?;
+    let name = named(load().map_err(|(_, e)| e)?)
+        .into_iter()
+        .find(|(_, p)| p.id == created.id)
+        .map(|(name, _)| name)
+        .unwrap_or_else(|| created.id.clone());
+    Ok(format!("Added “{}” as prompt {}", created.title, name))
+}
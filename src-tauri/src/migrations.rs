@@ -0,0 +1,325 @@
+// Schema versions for the on-disk stores, and the runner that upgrades them at
+// startup before anything else reads them.
+//
+// `prompts.json` and `settings.json` carry a top-level `schemaVersion` key (a
+// file without one is version 0); `prompts.db` uses SQLite's `user_version`
+// and is migrated by `sqlite_store`. Each step backs up the file it is about to
+// change. A file written by a newer build is never touched: startup is aborted
+// instead, since running on top of data we don't understand risks losing it.
+// Files that aren't valid JSON at all are skipped here and left to the
+// corruption handling of whoever reads them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Map, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::backups;
+use crate::prompts::{self, Prompt};
+use crate::sqlite_store;
+
+const VERSION_KEY: &str = "schemaVersion";
+
+// Upgrades a store object by exactly one version.
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+struct StoreFile {
+    name: &'static str,
+    // `migrations[n]` upgrades version n to n + 1; the current version is the
+    // number of migrations.
+    migrations: &'static [Migration],
+    // Contents written when the file doesn't exist yet (fresh install).
+    initial: fn() -> Map<String, Value>,
+}
+
+const STORES: &[StoreFile] = &[
+    StoreFile {
+        name: "prompts.json",
+        migrations: &[prompts_v1],
+        initial: initial_prompts,
+    },
+    StoreFile {
+        name: "settings.json",
+        migrations: &[settings_v1],
+        initial: Map::new,
+    },
+];
+
+// Current schema version of `prompts.json`.
+pub fn prompts_schema_version() -> u64 {
+    STORES[0].migrations.len() as u64
+}
+
+pub fn run(app: &AppHandle) -> Result<(), String> {
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    run_offline(&data_dir)
+}
+
+// Upgrades the stores in `data_dir` directly, for when the app isn't running
+// (see `cli`); fails on files written by a newer build just like startup.
+pub fn run_offline(data_dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(data_dir)
+        .map_err(|e| format!("Failed to create {}: {}", data_dir.display(), e))?;
+
+    for store in STORES {
+        migrate_store(&data_dir.join(store.name), store)?;
+    }
+    sqlite_store::migrate(&data_dir.join("prompts.db"))
+}
+
+fn migrate_store(path: &Path, store: &StoreFile) -> Result<(), String> {
+    let current = store.migrations.len() as u64;
+
+    if !path.exists() {
+        let mut object = (store.initial)();
+        object.insert(VERSION_KEY.to_string(), json!(current));
+        println!("📄 Creating {} (schema v{})", store.name, current);
+        return write(path, &object);
+    }
+
+    let raw = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut object = match serde_json::from_str::<Value>(&raw) {
+        Ok(Value::Object(object)) => object,
+        _ => {
+            println!("⚠️  {} is unreadable, not migrating it", path.display());
+            return Ok(());
+        }
+    };
+
+    let version = match object.get(VERSION_KEY) {
+        None => 0,
+        Some(value) => value
+            .as_u64()
+            .ok_or_else(|| format!("{} has an invalid {}: {}", store.name, VERSION_KEY, value))?,
+    };
+    if version > current {
+        return Err(format!(
+            "{} uses schema v{}, but this version of Prompt Buddy only understands up to v{}. \
+             Update Prompt Buddy, or restore a backup of the file.",
+            path.display(),
+            version,
+            current
+        ));
+    }
+
+    for (from, migration) in store.migrations.iter().enumerate().skip(version as usize) {
+        let backup = backup(path, from as u64)?;
+        println!(
+            "🔄 Migrating {} v{} → v{} (backup: {})",
+            store.name,
+            from,
+            from + 1,
+            backup.display()
+        );
+        migration(&mut object)?;
+        object.insert(VERSION_KEY.to_string(), json!(from + 1));
+        write(path, &object)?;
+    }
+    Ok(())
+}
+
+// Copies `path` next to itself as `<name>.v<version>-<unix time>.bak`.
+pub fn backup(path: &Path, version: u64) -> Result<PathBuf, String> {
+    let timestamp = backups::unix_now();
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("store");
+    let backup = path.with_file_name(format!("{}.v{}-{}.bak", name, version, timestamp));
+    fs::copy(path, &backup)
+        .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+    Ok(backup)
+}
+
+fn write(path: &Path, object: &Map<String, Value>) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(object)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    backups::write_atomic(path, contents.as_bytes())
+}
+
+fn initial_prompts() -> Map<String, Value> {
+    let mut object = Map::new();
+    object.insert("prompts".to_string(), json!(prompts::default_prompts()));
+    object
+}
+
+// v1: every prompt has all required fields. Unversioned files were re-seeded
+// with the defaults whenever the list was empty, so an empty list becomes the
+// defaults here once; from v1 on an empty library stays empty.
+fn prompts_v1(object: &mut Map<String, Value>) -> Result<(), String> {
+    let entries = match object.remove("prompts") {
+        Some(Value::Array(entries)) => entries,
+        Some(Value::Null) | None => Vec::new(),
+        Some(other) => return Err(format!("prompts.json: `prompts` is not a list: {}", other)),
+    };
+
+    let mut prompts: Vec<Prompt> = Vec::new();
+    for (i, entry) in entries.into_iter().enumerate() {
+        let Value::Object(mut fields) = entry else {
+            println!("⚠️  Dropping prompt #{}: not an object", i + 1);
+            continue;
+        };
+        let mut fill = |key: &str, value: String| {
+            if !fields.get(key).map(Value::is_string).unwrap_or(false) {
+                fields.insert(key.to_string(), json!(value));
+            }
+        };
+        fill("id", uuid::Uuid::new_v4().to_string());
+        fill("title", format!("Prompt {}", i + 1));
+        fill("content", String::new());
+        fill("color", prompts::DEFAULT_COLOR.to_string());
+
+        let prompt = serde_json::from_value(Value::Object(fields))
+            .map_err(|e| format!("prompts.json: prompt #{} is invalid: {}", i + 1, e))?;
+        prompts.push(prompt);
+    }
+    if prompts.is_empty() {
+        prompts = prompts::default_prompts();
+    }
+
+    object.insert("prompts".to_string(), json!(prompts));
+    Ok(())
+}
+
+// v1: only introduces the version stamp.
+fn settings_v1(_object: &mut Map<String, Value>) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("prompt-buddy-migrations-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn prompts_store() -> &'static StoreFile {
+        STORES
+            .iter()
+            .find(|store| store.name == "prompts.json")
+            .unwrap()
+    }
+
+    fn read(path: &Path) -> Map<String, Value> {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    // The `.bak` files next to `path`.
+    fn backups_of(path: &Path) -> Vec<PathBuf> {
+        fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|backup| {
+                backup
+                    .extension()
+                    .is_some_and(|extension| extension == "bak")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn upgrades_unversioned_prompts() {
+        let dir = temp_dir();
+        let path = dir.join("prompts.json");
+        let original = r#"{"prompts": [{"id": "a", "content": "Hello", "tags": ["x"]}, {"title": 3}, "junk"]}"#;
+        fs::write(&path, original).unwrap();
+
+        migrate_store(&path, prompts_store()).unwrap();
+
+        let object = read(&path);
+        assert_eq!(object[VERSION_KEY], json!(prompts_schema_version()));
+        let prompts: Vec<Prompt> = serde_json::from_value(object["prompts"].clone()).unwrap();
+        assert_eq!(prompts.len(), 2);
+        assert_eq!(prompts[0].id, "a");
+        assert_eq!(prompts[0].title, "Prompt 1");
+        assert_eq!(prompts[0].content, "Hello");
+        assert_eq!(prompts[0].color, prompts::DEFAULT_COLOR);
+        assert_eq!(prompts[0].tags, ["x"]);
+        assert!(!prompts[1].id.is_empty());
+        assert_eq!(prompts[1].title, "Prompt 2");
+        assert_eq!(prompts[1].content, "");
+
+        let backups = backups_of(&path);
+        assert_eq!(backups.len(), 1);
+        let name = backups[0].file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("prompts.json.v0-"), "{}", name);
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), original);
+
+        // Already current: nothing to do, no new backup.
+        migrate_store(&path, prompts_store()).unwrap();
+        assert_eq!(backups_of(&path).len(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn seeds_an_empty_unversioned_list_once() {
+        let dir = temp_dir();
+        let path = dir.join("prompts.json");
+        fs::write(&path, r#"{"prompts": []}"#).unwrap();
+
+        migrate_store(&path, prompts_store()).unwrap();
+        let object = read(&path);
+        assert_eq!(object["prompts"], json!(prompts::default_prompts()));
+
+        // From v1 on, an emptied library stays empty.
+        let mut emptied = object.clone();
+        emptied.insert("prompts".to_string(), json!([]));
+        write(&path, &emptied).unwrap();
+        migrate_store(&path, prompts_store()).unwrap();
+        assert_eq!(read(&path)["prompts"], json!([]));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn creates_missing_stores_at_the_current_version() {
+        let dir = temp_dir();
+        let path = dir.join("prompts.json");
+
+        migrate_store(&path, prompts_store()).unwrap();
+        let object = read(&path);
+        assert_eq!(object[VERSION_KEY], json!(prompts_schema_version()));
+        assert_eq!(object["prompts"], json!(prompts::default_prompts()));
+        assert!(backups_of(&path).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn refuses_newer_schemas_without_touching_the_file() {
+        let dir = temp_dir();
+        let path = dir.join("prompts.json");
+        let newer = format!(
+            r#"{{"{}": {}, "prompts": [], "somethingNew": true}}"#,
+            VERSION_KEY,
+            prompts_schema_version() + 1
+        );
+        fs::write(&path, &newer).unwrap();
+
+        let error = migrate_store(&path, prompts_store()).unwrap_err();
+        assert!(error.contains("Update Prompt Buddy"), "{}", error);
+        assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+        assert!(backups_of(&path).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn leaves_unreadable_files_alone() {
+        let dir = temp_dir();
+        let path = dir.join("prompts.json");
+        fs::write(&path, "{ not json").unwrap();
+
+        migrate_store(&path, prompts_store()).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ not json");
+        assert!(backups_of(&path).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
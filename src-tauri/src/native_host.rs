@@ -0,0 +1,358 @@
+// Native messaging host for the companion browser extension, which fills
+// prompts into web apps (ChatGPT, Claude.ai, ...) by setting the textarea
+// directly instead of typing into it.
+//
+//     prompt-buddy native-host install --extension-id <id>... [--browser <browser>]...
+//     prompt-buddy native-host manifest --browser <browser> --extension-id <id>...
+//     prompt-buddy native-host serve
+//
+// `install` writes a launcher script and a host manifest for each browser
+// (Chrome, Chromium, Brave and Edge take the 32-letter extension ids, Firefox
+// the others), by default for every browser found on this machine. A manifest
+// covers all profiles of its browser. `manifest` only prints one. The browser
+// runs `serve` through the launcher, since manifests can't pass arguments.
+//
+// Messages are JSON with a 4-byte length in native byte order, both ways:
+//
+//     { "id": 1, "method": "prompts.render", "params": { "prompt": "Review", "vars": {} } }
+//  -> { "id": 1, "result": "..." }  or  { "id": 1, "error": "..." }
+//
+// The methods are the read-only ones of `ipc` (`prompts.list`, `prompts.get`,
+// `prompts.search`, `prompts.render`) plus `prompts.variables { prompt }`,
+// which lists the variables to ask for. Like the rest of the command line they
+// go to the running app when there is one and to the store files otherwise.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::cli;
+use crate::ipc::{self, Request};
+use crate::prompts::Prompt;
+use crate::template;
+
+// Lowercase letters, digits, dots and underscores, as Chrome requires.
+pub const HOST_NAME: &str = "com.promptbuddy.host";
+
+// Browsers drop hosts that send more than 1 MB; we hold the browser to the same.
+const MAX_MESSAGE: usize = 1024 * 1024;
+
+const METHODS: &[&str] = &[
+    "prompts.list",
+    "prompts.get",
+    "prompts.search",
+    "prompts.render",
+    "prompts.variables",
+];
+
+// Serves until the browser closes stdin.
+pub fn serve() -> Result<(), String> {
+    eprintln!("prompt-buddy: serving native messages");
+    let mut output = cli::protocol_output()?;
+    let mut input = std::io::stdin().lock();
+    while let Some(message) = read_message(&mut input)? {
+        let response = match serde_json::from_slice::<Value>(&message) {
+            Ok(message) => reply(message),
+            Err(e) => json!({ "id": null, "error": format!("Invalid JSON: {}", e) }),
+        };
+        write_message(&mut output, &response)?;
+    }
+    Ok(())
+}
+
+// The next message, or `None` once the browser is gone.
+fn read_message(input: &mut impl Read) -> Result<Option<Vec<u8>>, String> {
+    let mut length = [0u8; 4];
+    match input.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(format!("Failed to read a message: {}", e)),
+    }
+    let length = u32::from_ne_bytes(length) as usize;
+    if length > MAX_MESSAGE {
+        return Err(format!("Message of {} bytes is too large", length));
+    }
+    let mut message = vec![0u8; length];
+    input
+        .read_exact(&mut message)
+        .map_err(|e| format!("Failed to read a message: {}", e))?;
+    Ok(Some(message))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> Result<(), String> {
+    let mut encoded = message.to_string().into_bytes();
+    if encoded.len() > MAX_MESSAGE {
+        let error = json!({
+            "id": message["id"],
+            "error": format!("The answer is larger than {} bytes", MAX_MESSAGE),
+        });
+        encoded = error.to_string().into_bytes();
+    }
+    output
+        .write_all(&(encoded.len() as u32).to_ne_bytes())
+        .and_then(|_| output.write_all(&encoded))
+        .and_then(|_| output.flush())
+        .map_err(|e| format!("Failed to write a message: {}", e))
+}
+
+fn reply(message: Value) -> Value {
+    let id = message.get("id").cloned().unwrap_or(Value::Null);
+    let method = message["method"].as_str().unwrap_or_default();
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    match call(method, params) {
+        Ok(result) => json!({ "id": id, "result": result }),
+        Err(e) => json!({ "id": id, "error": e }),
+    }
+}
+
+fn call(method: &str, params: Value) -> Result<Value, String> {
+    if !METHODS.contains(&method) {
+        return Err(format!("Unknown method '{}'", method));
+    }
+    if method == "prompts.variables" {
+        let prompt = params["prompt"]
+            .as_str()
+            .ok_or_else(|| "Missing prompt".to_string())?;
+        let prompts: Vec<Prompt> =
+            serde_json::from_value(cli::execute(Request::List {})?).map_err(|e| e.to_string())?;
+        let prompt = ipc::find_prompt(&prompts, prompt)?;
+        let variables: Vec<Value> = template::variables(&prompt.content)
+            .into_iter()
+            .map(|v| json!({ "name": v.name, "default": v.default }))
+            .collect();
+        return Ok(Value::Array(variables));
+    }
+    let request = ipc::parse(method, params).map_err(|e| e.message)?;
+    cli::execute(request)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Browser {
+    Chrome,
+    Chromium,
+    Brave,
+    Edge,
+    Firefox,
+}
+
+const BROWSERS: &[Browser] = &[
+    Browser::Chrome,
+    Browser::Chromium,
+    Browser::Brave,
+    Browser::Edge,
+    Browser::Firefox,
+];
+
+impl Browser {
+    pub fn from_name(name: &str) -> Option<Self> {
+        BROWSERS
+            .iter()
+            .copied()
+            .find(|browser| browser.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Browser::Chrome => "chrome",
+            Browser::Chromium => "chromium",
+            Browser::Brave => "brave",
+            Browser::Edge => "edge",
+            Browser::Firefox => "firefox",
+        }
+    }
+
+    // Whether `id` names an extension of this browser: Chromium-based
+    // browsers use 32 letters from a to p, Firefox uses an email-like id or a
+    // braced UUID.
+    pub fn accepts(self, id: &str) -> bool {
+        let chromium_id = id.len() == 32 && id.bytes().all(|b| (b'a'..=b'p').contains(&b));
+        chromium_id != (self == Browser::Firefox)
+    }
+
+    fn manifest(self, launcher: &Path, extension_ids: &[String]) -> Result<Value, String> {
+        let ids: Vec<&String> = extension_ids.iter().filter(|id| self.accepts(id)).collect();
+        if ids.is_empty() {
+            return Err(format!("No {} extension id given", self.name()));
+        }
+        let mut manifest = json!({
+            "name": HOST_NAME,
+            "description": "Prompt Buddy",
+            "path": launcher,
+            "type": "stdio",
+        });
+        if self == Browser::Firefox {
+            manifest["allowed_extensions"] = json!(ids);
+        } else {
+            let origins: Vec<String> = ids
+                .iter()
+                .map(|id| format!("chrome-extension://{}/", id))
+                .collect();
+            manifest["allowed_origins"] = json!(origins);
+        }
+        Ok(manifest)
+    }
+
+    // The browser's own directory, to tell whether it is installed.
+    fn profile_dir(self) -> Option<PathBuf> {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        if cfg!(target_os = "macos") {
+            let support = home?.join("Library").join("Application Support");
+            Some(match self {
+                Browser::Chrome => support.join("Google").join("Chrome"),
+                Browser::Chromium => support.join("Chromium"),
+                Browser::Brave => support.join("BraveSoftware").join("Brave-Browser"),
+                Browser::Edge => support.join("Microsoft Edge"),
+                Browser::Firefox => support.join("Mozilla"),
+            })
+        } else if cfg!(windows) {
+            let local = std::env::var_os("LOCALAPPDATA").map(PathBuf::from);
+            let roaming = std::env::var_os("APPDATA").map(PathBuf::from);
+            Some(match self {
+                Browser::Chrome => local?.join("Google").join("Chrome"),
+                Browser::Chromium => local?.join("Chromium"),
+                Browser::Brave => local?.join("BraveSoftware").join("Brave-Browser"),
+                Browser::Edge => local?.join("Microsoft").join("Edge"),
+                Browser::Firefox => roaming?.join("Mozilla").join("Firefox"),
+            })
+        } else {
+            let config = std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .filter(|dir| dir.is_absolute())
+                .or_else(|| home.clone().map(|home| home.join(".config")));
+            Some(match self {
+                Browser::Chrome => config?.join("google-chrome"),
+                Browser::Chromium => config?.join("chromium"),
+                Browser::Brave => config?.join("BraveSoftware").join("Brave-Browser"),
+                Browser::Edge => config?.join("microsoft-edge"),
+                Browser::Firefox => home?.join(".mozilla"),
+            })
+        }
+    }
+
+    // Where the browser looks for the manifest. Windows browsers look in the
+    // registry instead, which points at a manifest in our data dir.
+    #[cfg(not(windows))]
+    fn manifest_path(self, _data_dir: &Path) -> Result<PathBuf, String> {
+        let dir = self
+            .profile_dir()
+            .ok_or_else(|| "Failed to find the home directory".to_string())?;
+        let hosts = if self == Browser::Firefox && cfg!(target_os = "linux") {
+            "native-messaging-hosts"
+        } else {
+            "NativeMessagingHosts"
+        };
+        Ok(dir.join(hosts).join(format!("{}.json", HOST_NAME)))
+    }
+
+    #[cfg(windows)]
+    fn manifest_path(self, data_dir: &Path) -> Result<PathBuf, String> {
+        Ok(data_dir
+            .join("native-messaging")
+            .join(format!("{}.json", self.name())))
+    }
+
+    #[cfg(windows)]
+    fn registry_key(self) -> String {
+        let vendor = match self {
+            Browser::Chrome => r"Google\Chrome",
+            Browser::Chromium => "Chromium",
+            Browser::Brave => r"BraveSoftware\Brave-Browser",
+            Browser::Edge => r"Microsoft\Edge",
+            Browser::Firefox => "Mozilla",
+        };
+        format!(
+            r"HKCU\Software\{}\NativeMessagingHosts\{}",
+            vendor, HOST_NAME
+        )
+    }
+}
+
+// The browsers found on this machine.
+pub fn installed_browsers() -> Vec<Browser> {
+    BROWSERS
+        .iter()
+        .copied()
+        .filter(|browser| browser.profile_dir().is_some_and(|dir| dir.is_dir()))
+        .collect()
+}
+
+// The manifest `install` would write for `browser`.
+pub fn manifest(browser: Browser, extension_ids: &[String]) -> Result<String, String> {
+    let launcher = launcher_path(&cli::data_dir()?);
+    serde_json::to_string_pretty(&browser.manifest(&launcher, extension_ids)?)
+        .map_err(|e| e.to_string())
+}
+
+// Writes the launcher and a manifest for each of `browsers`, and returns where
+// the manifests went.
+pub fn install(browsers: &[Browser], extension_ids: &[String]) -> Result<Vec<PathBuf>, String> {
+    let data_dir = cli::data_dir()?;
+    let launcher = write_launcher(&data_dir)?;
+    let mut written = Vec::new();
+    for browser in browsers {
+        let manifest = browser.manifest(&launcher, extension_ids)?;
+        let path = browser.manifest_path(&data_dir)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let encoded = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+        std::fs::write(&path, encoded)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        #[cfg(windows)]
+        register(&browser.registry_key(), &path)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+fn launcher_path(data_dir: &Path) -> PathBuf {
+    let name = if cfg!(windows) {
+        "prompt-buddy-host.bat"
+    } else {
+        "prompt-buddy-host"
+    };
+    data_dir.join("native-messaging").join(name)
+}
+
+fn write_launcher(data_dir: &Path) -> Result<PathBuf, String> {
+    let exe =
+        std::env::current_exe().map_err(|e| format!("Failed to find the app binary: {}", e))?;
+    let path = launcher_path(data_dir);
+    let script = if cfg!(windows) {
+        format!(
+            "@echo off\r\n\"{}\" native-host serve %*\r\n",
+            exe.display()
+        )
+    } else {
+        let quoted = exe.to_string_lossy().replace('\'', r"'\''");
+        format!("#!/bin/sh\nexec '{}' native-host serve \"$@\"\n", quoted)
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, script)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make {} executable: {}", path.display(), e))?;
+    }
+    Ok(path)
+}
+
+#[cfg(windows)]
+fn register(key: &str, manifest: &Path) -> Result<(), String> {
+    let status = std::process::Command::new("reg")
+        .args(["add", key, "/ve", "/f", "/d"])
+        .arg(manifest)
+        .status()
+        .map_err(|e| format!("Failed to run reg: {}", e))?;
+    if !status.success() {
+        return Err(format!("reg add {} failed with {}", key, status));
+    }
+    Ok(())
+}
@@ -0,0 +1,242 @@
+// Folders and tags. A folder is the slash-separated `folder` path of the
+// prompts in it: it exists for as long as something is filed there (or below
+// it), and renaming or moving it rewrites those paths. Tags are free-form and
+// filtered with `tag_query` expressions.
+//
+// The bar can be scoped to one folder and/or a tag expression (`barFolder` and
+// `barTagFilter` in settings.json); its number keys and cmd+alt+1-9 then refer
+// to the prompts it shows. The active project's pack (see `projects`) comes
+// first and is not scoped.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::library;
+use crate::projects::{self, BarPrompt};
+use crate::prompts::{self, Prompt};
+use crate::tag_query::TagQuery;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderInfo {
+    path: String,
+    // Last segment of the path.
+    name: String,
+    parent: Option<String>,
+    // Prompts in this folder and all folders below it.
+    prompt_count: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagInfo {
+    tag: String,
+    prompt_count: usize,
+}
+
+// Every folder that has prompts in or below it, sorted by path (so parents
+// come before their children).
+fn folders(prompts: &[Prompt]) -> Vec<FolderInfo> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for folder in prompts.iter().filter_map(|p| p.folder.as_deref()) {
+        let mut path = String::new();
+        for segment in folder.split('/') {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(segment);
+            *counts.entry(path.clone()).or_default() += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .map(|(path, prompt_count)| {
+            let (parent, name) = match path.rsplit_once('/') {
+                Some((parent, name)) => (Some(parent.to_string()), name.to_string()),
+                None => (None, path.clone()),
+            };
+            FolderInfo {
+                path,
+                name,
+                parent,
+                prompt_count,
+            }
+        })
+        .collect()
+}
+
+fn filter(prompts: Vec<Prompt>, folder: Option<&str>, query: &TagQuery) -> Vec<Prompt> {
+    prompts
+        .into_iter()
+        .filter(|p| folder.is_none_or(|folder| prompts::is_in_folder(p.folder.as_deref(), folder)))
+        .filter(|p| query.matches(&p.tags))
+        .collect()
+}
+
+// Moves everything in or below folder `from` to `to`, keeping the structure
+// below it. Moving onto an existing folder merges the two.
+fn relocate(app: &AppHandle, label: &str, from: &str, to: &str) -> Result<(), String> {
+    if to == from {
+        return Ok(());
+    }
+    if prompts::is_in_folder(Some(to), from) {
+        return Err(format!("Cannot move folder '{}' into itself", from));
+    }
+    library::update_all(app, label, |prompts| {
+        let mut found = false;
+        for prompt in prompts.iter_mut() {
+            let Some(folder) = prompt.folder.as_deref() else {
+                continue;
+            };
+            if !prompts::is_in_folder(Some(folder), from) {
+                continue;
+            }
+            let rest = &folder[from.len()..];
+            prompt.folder = prompts::normalize_folder(&format!("{}{}", to, rest));
+            found = true;
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(format!("No folder '{}'", from))
+        }
+    })
+}
+
+#[tauri::command]
+pub async fn list_folders() -> Result<Vec<FolderInfo>, String> {
+    Ok(folders(&library::prompts()))
+}
+
+// Gives folder `path` a new last segment, keeping its parent.
+#[tauri::command]
+pub async fn rename_folder(app: AppHandle, path: String, name: String) -> Result<(), String> {
+    let path = prompts::normalize_folder(&path).ok_or("The top level cannot be renamed")?;
+    if name.contains('/') {
+        return Err("Folder names cannot contain '/'".to_string());
+    }
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Folder name cannot be empty".to_string());
+    }
+    let to = match path.rsplit_once('/') {
+        Some((parent, _)) => format!("{}/{}", parent, name),
+        None => name.to_string(),
+    };
+    println!("📁 Renaming folder {} to {}", path, to);
+    relocate(&app, &format!("Rename folder “{}”", path), &path, &to)
+}
+
+// Moves folder `path` into `parent` (the top level when missing).
+#[tauri::command]
+pub async fn move_folder(
+    app: AppHandle,
+    path: String,
+    parent: Option<String>,
+) -> Result<(), String> {
+    let path = prompts::normalize_folder(&path).ok_or("The top level cannot be moved")?;
+    let parent = parent.as_deref().and_then(prompts::normalize_folder);
+    let name = path.rsplit('/').next().unwrap_or(&path);
+    let to = match &parent {
+        Some(parent) => format!("{}/{}", parent, name),
+        None => name.to_string(),
+    };
+    println!("📁 Moving folder {} to {}", path, to);
+    relocate(&app, &format!("Move folder “{}”", path), &path, &to)
+}
+
+// Files prompts into `folder` (the top level when missing).
+#[tauri::command]
+pub async fn move_prompts_to_folder(
+    app: AppHandle,
+    ids: Vec<String>,
+    folder: Option<String>,
+) -> Result<(), String> {
+    let folder = folder.as_deref().and_then(prompts::normalize_folder);
+    println!("📁 Moving {} prompts to {}", ids.len(), folder.as_deref().unwrap_or("/"));
+    library::update_all(&app, "Move to folder", |prompts| {
+        for prompt in prompts.iter_mut().filter(|p| ids.contains(&p.id)) {
+            prompt.folder = folder.clone();
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn list_tags() -> Result<Vec<TagInfo>, String> {
+    let mut counts: BTreeMap<String, (String, usize)> = BTreeMap::new();
+    for tag in library::prompts().iter().flat_map(|p| p.tags.iter()) {
+        counts.entry(tag.to_lowercase()).or_insert_with(|| (tag.clone(), 0)).1 += 1;
+    }
+    Ok(counts
+        .into_values()
+        .map(|(tag, prompt_count)| TagInfo { tag, prompt_count })
+        .collect())
+}
+
+// Prompts in `folder` (or below it) whose tags match the expression `tags`.
+#[tauri::command]
+pub async fn filter_prompts(
+    tags: Option<String>,
+    folder: Option<String>,
+) -> Result<Vec<Prompt>, String> {
+    let query = TagQuery::parse(tags.as_deref().unwrap_or(""))?;
+    let folder = folder.as_deref().and_then(prompts::normalize_folder);
+    Ok(filter(library::prompts(), folder.as_deref(), &query))
+}
+
+// Adds and removes tags on several prompts at once.
+#[tauri::command]
+pub async fn apply_tags(
+    app: AppHandle,
+    ids: Vec<String>,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<(), String> {
+    let remove: Vec<String> = remove.iter().map(|tag| tag.trim().to_lowercase()).collect();
+    println!("🏷️  Tagging {} prompts", ids.len());
+    library::update_all(&app, "Edit tags", |prompts| {
+        for prompt in prompts.iter_mut().filter(|p| ids.contains(&p.id)) {
+            let mut tags: Vec<String> = prompt
+                .tags
+                .iter()
+                .filter(|tag| !remove.contains(&tag.to_lowercase()))
+                .cloned()
+                .collect();
+            tags.extend(add.iter().cloned());
+            prompt.tags = prompts::normalize_tags(&tags);
+        }
+        Ok(())
+    })
+}
+
+// What the bar shows: the active project's prompt pack, then the library
+// narrowed down by the bar scope settings. An invalid tag filter is ignored
+// rather than emptying the bar.
+#[tauri::command]
+pub async fn get_bar_prompts(app: AppHandle) -> Result<Vec<BarPrompt>, String> {
+    let store = app.store("settings.json").ok();
+    let get = |key: &str| {
+        store
+            .as_ref()
+            .and_then(|s| s.get(key))
+            .and_then(|value| value.as_str().map(|s| s.to_string()))
+    };
+
+    let folder = get("barFolder").as_deref().and_then(prompts::normalize_folder);
+    let query = match TagQuery::parse(&get("barTagFilter").unwrap_or_default()) {
+        Ok(query) => query,
+        Err(e) => {
+            println!("⚠️  Ignoring bar tag filter: {}", e);
+            TagQuery::All
+        }
+    };
+    let library = filter(library::prompts(), folder.as_deref(), &query)
+        .into_iter()
+        .map(|prompt| BarPrompt { prompt, project: None });
+    Ok(projects::bar_prompts().into_iter().chain(library).collect())
+}
@@ -0,0 +1,181 @@
+// Project prompt packs: prompts that live in a repository, as Markdown files in
+// `.promptbuddy/prompts/` (same format as the Markdown storage backend), and
+// show up in the bar while that project is being worked on.
+//
+// The active project is worked out whenever the bar captures the frontmost
+// app. If that app is an editor, a segment of its window title is matched
+// against the configured project directories (`projectDirectories` in
+// settings.json, by directory name) or taken as a path if it is a directory
+// that exists. Cursor and VS Code titles look like `main.rs — my-repo — Cursor`
+// (" - " as the separator outside macOS). Other apps leave the active project
+// as it was, so switching to a terminal and back keeps the pack in the bar.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+
+use crate::library::PromptStore;
+use crate::markdown_store::MarkdownStore;
+use crate::prompts::Prompt;
+
+const PACK_DIR: &str = ".promptbuddy/prompts";
+
+// Prefix of the ids of project prompts in the bar, so they never collide with
+// library prompts.
+const ID_PREFIX: &str = "project:";
+
+// Frontmost apps (case-insensitive substrings) whose window titles name a project.
+const EDITOR_APPS: &[&str] = &["cursor", "code", "codium", "windsurf", "zed"];
+
+static ACTIVE_PROJECT: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectInfo {
+    path: String,
+    name: String,
+    prompt_count: usize,
+}
+
+// A prompt as shown in the bar: project prompts carry their project's name.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BarPrompt {
+    #[serde(flatten)]
+    pub prompt: Prompt,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+}
+
+fn configured_projects(app: &AppHandle) -> Vec<PathBuf> {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get("projectDirectories"))
+        .and_then(|value| serde_json::from_value::<Vec<String>>(value).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|dir| !dir.trim().is_empty())
+        .map(|dir| PathBuf::from(dir.trim()))
+        .collect()
+}
+
+fn project_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+// The project a window title refers to, if any.
+fn detect(title: &str, projects: &[PathBuf]) -> Option<PathBuf> {
+    let segments: Vec<&str> = title
+        .split(" — ")
+        .flat_map(|part| part.split(" - "))
+        .map(|segment| {
+            // Unsaved-changes markers, and workspaces opened from a file.
+            segment
+                .trim()
+                .trim_start_matches('●')
+                .trim()
+                .trim_end_matches("(Workspace)")
+                .trim()
+        })
+        .collect();
+
+    for segment in &segments {
+        if let Some(project) = projects.iter().find(|p| project_name(p) == *segment) {
+            return Some(project.clone());
+        }
+    }
+    segments
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_absolute() && path.join(PACK_DIR).is_dir())
+}
+
+// Updates the active project from the frontmost app and its window title, and
+// tells the bar to reload if it changed.
+pub fn on_window_captured(app: &AppHandle, app_name: Option<&str>, title: Option<&str>) {
+    let is_editor = app_name.is_some_and(|name| {
+        let name = name.to_lowercase();
+        EDITOR_APPS.iter().any(|editor| name.contains(editor))
+    });
+    if !is_editor {
+        return;
+    }
+    let detected = title.and_then(|title| detect(title, &configured_projects(app)));
+
+    let changed = {
+        let mut active = ACTIVE_PROJECT.lock().unwrap();
+        if *active != detected {
+            *active = detected.clone();
+            true
+        } else {
+            false
+        }
+    };
+    if changed {
+        match &detected {
+            Some(path) => println!("📂 Active project: {}", path.display()),
+            None => println!("📂 No active project"),
+        }
+        let _ = app.emit("bar-scope-changed", ());
+    }
+}
+
+pub fn active_project() -> Option<PathBuf> {
+    ACTIVE_PROJECT.lock().unwrap().clone()
+}
+
+// The prompts in `project`'s pack; a project without a pack has none.
+pub fn load_pack(project: &Path) -> Result<Vec<Prompt>, String> {
+    let dir = project.join(PACK_DIR);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let prompts = MarkdownStore::open(dir)?.load()?;
+    Ok(prompts
+        .into_iter()
+        .map(|prompt| Prompt {
+            id: format!("{}{}", ID_PREFIX, prompt.id),
+            ..prompt
+        })
+        .collect())
+}
+
+// The active project's prompts, ready for the bar. They are read on every call
+// so edits to the pack show up without a watcher.
+pub fn bar_prompts() -> Vec<BarPrompt> {
+    let Some(project) = active_project() else {
+        return Vec::new();
+    };
+    let name = project_name(&project);
+    match load_pack(&project) {
+        Ok(prompts) => prompts
+            .into_iter()
+            .map(|prompt| BarPrompt {
+                prompt,
+                project: Some(name.clone()),
+            })
+            .collect(),
+        Err(e) => {
+            println!("⚠️  Failed to load prompts of {}: {}", project.display(), e);
+            Vec::new()
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_active_project() -> Result<Option<ProjectInfo>, String> {
+    let Some(project) = active_project() else {
+        return Ok(None);
+    };
+    Ok(Some(ProjectInfo {
+        path: project.display().to_string(),
+        name: project_name(&project),
+        prompt_count: load_pack(&project)?.len(),
+    }))
+}
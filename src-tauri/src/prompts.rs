@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+
+// Mirror of the `Prompt` interface used by the frontend.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Prompt {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+    pub color: String,
+    // Trigger typed anywhere (e.g. `;dbg`) that expands into `content`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abbreviation: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // Slash-separated path such as `Work/Reviews`; `None` is the top level.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+}
+
+// Gradient used when a prompt comes from somewhere that doesn't specify one.
+pub const DEFAULT_COLOR: &str = "from-blue-500 to-cyan-500";
+
+// Seeded into `prompts.json` on a fresh install.
+pub fn default_prompts() -> Vec<Prompt> {
+    [
+        (
+            "Debug Root Cause",
+            "Come up with 5-7 most likely root causes of this bug, and attempt the 1-2 most likely fixes with proper logging. Don't hold back, give it your all.",
+            "from-purple-500 to-pink-500",
+        ),
+        (
+            "Explain Code",
+            "Explain this code in detail, including its purpose, how it works, potential edge cases, and any improvements that could be made.",
+            "from-blue-500 to-cyan-500",
+        ),
+        (
+            "Refactor",
+            "Refactor this code to be more readable, maintainable, and performant. Follow best practices and explain your changes.",
+            "from-green-500 to-emerald-500",
+        ),
+        (
+            "Write Tests",
+            "Write comprehensive unit tests for this code, covering edge cases and error scenarios. Use appropriate testing patterns.",
+            "from-orange-500 to-red-500",
+        ),
+        (
+            "Optimize Performance",
+            "Analyze this code for performance bottlenecks and suggest specific optimizations with examples.",
+            "from-indigo-500 to-purple-500",
+        ),
+        (
+            "Add Error Handling",
+            "Add comprehensive error handling to this code with proper logging and user-friendly error messages.",
+            "from-teal-500 to-green-500",
+        ),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, (title, content, color))| Prompt {
+        id: (i + 1).to_string(),
+        title: title.to_string(),
+        content: content.to_string(),
+        color: color.to_string(),
+        abbreviation: None,
+        tags: Vec::new(),
+        folder: None,
+    })
+    .collect()
+}
+
+// Canonical form of a folder path: segments trimmed, empty ones dropped. The
+// top level is `None`.
+pub fn normalize_folder(path: &str) -> Option<String> {
+    let segments: Vec<&str> = path
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    if segments.is_empty() {
+        None
+    } else {
+        Some(segments.join("/"))
+    }
+}
+
+// Whether `folder` is `ancestor` itself or lies somewhere below it.
+pub fn is_in_folder(folder: Option<&str>, ancestor: &str) -> bool {
+    match folder {
+        Some(folder) => {
+            folder == ancestor
+                || folder
+                    .strip_prefix(ancestor)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        }
+        None => false,
+    }
+}
+
+// Tags with surrounding whitespace removed and without duplicates, which are
+// compared case-insensitively; the first spelling wins.
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !normalized.iter().any(|t| t.to_lowercase() == tag.to_lowercase()) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+}
@@ -0,0 +1,297 @@
+// Pre-injection safety check. Before typing anything we make sure the focused
+// control is not a password field / secure input context, and that the
+// frontmost application is not one the user marked as protected. Injection is
+// aborted with a dedicated `InjectError` variant instead of typing.
+//
+// Detection per platform:
+// - macOS: secure event input (enabled by password fields and "Secure Keyboard
+//   Entry" in terminals).
+// - Linux: role of the focused accessible via AT-SPI (`password text`). This
+//   needs the accessibility bus; without it only the protected-app list
+//   applies.
+// - Windows: `ES_PASSWORD` style on the focused edit control.
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::InjectError;
+
+// Used until the user saves their own `protectedApps` list.
+const DEFAULT_PROTECTED_APPS: &[&str] = &[
+    "1Password",
+    "Bitwarden",
+    "Dashlane",
+    "KeePassXC",
+    "Keychain Access",
+    "LastPass",
+];
+
+static PROTECTED_APPS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| {
+    Mutex::new(DEFAULT_PROTECTED_APPS.iter().map(|s| s.to_string()).collect())
+});
+
+pub fn init(app: &AppHandle) {
+    let saved = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("protectedApps"))
+        .and_then(|value| serde_json::from_value::<Vec<String>>(value).ok());
+    if let Some(apps) = saved {
+        println!("📋 Loaded {} protected apps from settings", apps.len());
+        *PROTECTED_APPS.lock().unwrap() = apps;
+    }
+
+    platform::init();
+}
+
+#[tauri::command]
+pub async fn get_protected_apps() -> Result<Vec<String>, String> {
+    Ok(PROTECTED_APPS.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn update_protected_apps(app: AppHandle, apps: Vec<String>) -> Result<(), String> {
+    let apps: Vec<String> = apps
+        .into_iter()
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect();
+    println!("🔧 Updating protected apps: {:?}", apps);
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("Failed to load settings: {}", e))?;
+    store.set("protectedApps", apps.clone());
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    *PROTECTED_APPS.lock().unwrap() = apps;
+    Ok(())
+}
+
+// Must be called once the target application has focus, right before typing.
+pub fn check_injection_target() -> Result<(), InjectError> {
+    if let Some(app_name) = crate::get_frontmost_app() {
+        let protected = PROTECTED_APPS
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(&app_name));
+        if protected {
+            return Err(InjectError::ProtectedApp(format!(
+                "{} is in the list of protected apps",
+                app_name
+            )));
+        }
+    }
+
+    if let Some(reason) = platform::secure_input_reason() {
+        return Err(InjectError::SecureInput(reason));
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        // Carbon `Boolean` is an unsigned char.
+        fn IsSecureEventInputEnabled() -> u8;
+    }
+
+    pub fn init() {}
+
+    pub fn secure_input_reason() -> Option<String> {
+        if unsafe { IsSecureEventInputEnabled() } != 0 {
+            Some("Secure input is active (a password field or secure terminal has focus)".to_string())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::sync::Mutex;
+
+    use once_cell::sync::{Lazy, OnceCell};
+    use zbus::blocking::{Connection, MessageIterator};
+    use zbus::MatchRule;
+
+    // From at-spi2-core's AtspiRole / AtspiStateType enums.
+    const ROLE_PASSWORD_TEXT: u32 = 40;
+    const STATE_FOCUSED: u32 = 12;
+
+    const ACCESSIBLE_IFACE: &str = "org.a11y.atspi.Accessible";
+
+    static A11Y_BUS: OnceCell<Connection> = OnceCell::new();
+
+    // Bus name and object path of the accessible that last gained focus.
+    static FOCUSED: Lazy<Mutex<Option<(String, String)>>> = Lazy::new(|| Mutex::new(None));
+
+    pub fn init() {
+        std::thread::spawn(|| {
+            if let Err(e) = track_focus() {
+                println!("ℹ️  AT-SPI focus tracking unavailable, password fields can't be detected: {}", e);
+            }
+        });
+    }
+
+    pub fn secure_input_reason() -> Option<String> {
+        let conn = A11Y_BUS.get()?;
+        let (name, path) = FOCUSED.lock().unwrap().clone()?;
+
+        // The cached object may have lost focus since; only trust it if it
+        // still reports the focused state.
+        if !has_state(&get_state(conn, &name, &path)?, STATE_FOCUSED) {
+            return None;
+        }
+        let role: u32 = conn
+            .call_method(Some(name.as_str()), path.as_str(), Some(ACCESSIBLE_IFACE), "GetRole", &())
+            .ok()?
+            .body()
+            .deserialize()
+            .ok()?;
+
+        if role == ROLE_PASSWORD_TEXT {
+            Some("A password field has focus".to_string())
+        } else {
+            None
+        }
+    }
+
+    fn track_focus() -> zbus::Result<()> {
+        let session = Connection::session()?;
+        let address: String = session
+            .call_method(Some("org.a11y.Bus"), "/org/a11y/bus", Some("org.a11y.Bus"), "GetAddress", &())?
+            .body()
+            .deserialize()?;
+        let conn = zbus::blocking::connection::Builder::address(address.as_str())?.build()?;
+
+        // Applications only emit the events some client registered for.
+        register_event(&conn, "object:state-changed:focused");
+
+        let rule = MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .interface("org.a11y.atspi.Event.Object")?
+            .member("StateChanged")?
+            .arg(0, "focused")?
+            .build();
+        let messages = MessageIterator::for_match_rule(rule, &conn, None)?;
+        let _ = A11Y_BUS.set(conn.clone());
+        println!("✅ Tracking focused controls via AT-SPI");
+
+        for message in messages {
+            let message = message?;
+            let header = message.header();
+            let (Some(sender), Some(path)) = (header.sender(), header.path()) else {
+                continue;
+            };
+            let (sender, path) = (sender.to_string(), path.to_string());
+
+            // The signal fires for focus loss too; ask the object directly
+            // rather than decoding the version-dependent signal body.
+            let focused = get_state(&conn, &sender, &path)
+                .map(|states| has_state(&states, STATE_FOCUSED))
+                .unwrap_or(false);
+            if focused {
+                *FOCUSED.lock().unwrap() = Some((sender, path));
+            }
+        }
+        Ok(())
+    }
+
+    fn register_event(conn: &Connection, event: &str) {
+        let registry = Some("org.a11y.atspi.Registry");
+        let path = "/org/a11y/atspi/registry";
+        let iface = Some("org.a11y.atspi.Registry");
+
+        // at-spi2-core >= 2.46 takes (event, properties, app bus name); older
+        // registries only take the event name.
+        let result = conn
+            .call_method(registry, path, iface, "RegisterEvent", &(event, Vec::<String>::new(), ""))
+            .or_else(|_| conn.call_method(registry, path, iface, "RegisterEvent", &(event,)));
+        if let Err(e) = result {
+            println!("⚠️  Failed to register AT-SPI event {}: {}", event, e);
+        }
+    }
+
+    fn get_state(conn: &Connection, name: &str, path: &str) -> Option<Vec<u32>> {
+        conn.call_method(Some(name), path, Some(ACCESSIBLE_IFACE), "GetState", &())
+            .ok()?
+            .body()
+            .deserialize()
+            .ok()
+    }
+
+    fn has_state(states: &[u32], state: u32) -> bool {
+        states
+            .get((state / 32) as usize)
+            .map(|word| word & (1 << (state % 32)) != 0)
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows_sys::Win32::Foundation::HWND;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetClassNameW, GetForegroundWindow, GetGUIThreadInfo, GetWindowLongW,
+        GetWindowThreadProcessId, GUITHREADINFO, GWL_STYLE,
+    };
+
+    // Edit control style for password entry (winuser.h). Other window classes
+    // use the same bit for something else.
+    const ES_PASSWORD: i32 = 0x0020;
+
+    pub fn init() {}
+
+    pub fn secure_input_reason() -> Option<String> {
+        unsafe {
+            let foreground = GetForegroundWindow();
+            if foreground.is_null() {
+                return None;
+            }
+            let thread_id = GetWindowThreadProcessId(foreground, std::ptr::null_mut());
+
+            let mut info: GUITHREADINFO = std::mem::zeroed();
+            info.cbSize = std::mem::size_of::<GUITHREADINFO>() as u32;
+            if GetGUIThreadInfo(thread_id, &mut info) == 0 || info.hwndFocus.is_null() {
+                return None;
+            }
+
+            let password = is_edit_control(info.hwndFocus)
+                && GetWindowLongW(info.hwndFocus, GWL_STYLE) & ES_PASSWORD != 0;
+            if password {
+                Some("A password field has focus".to_string())
+            } else {
+                None
+            }
+        }
+    }
+
+    // Plain and rich edit controls, and WinForms text boxes built on them.
+    unsafe fn is_edit_control(window: HWND) -> bool {
+        let mut class = [0u16; 256];
+        let len = GetClassNameW(window, class.as_mut_ptr(), class.len() as i32);
+        if len <= 0 {
+            return false;
+        }
+        let class = String::from_utf16_lossy(&class[..len as usize]).to_ascii_lowercase();
+        class == "edit" || class.starts_with("richedit") || class.contains(".edit.")
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+mod platform {
+    pub fn init() {}
+
+    pub fn secure_input_reason() -> Option<String> {
+        None
+    }
+}
@@ -0,0 +1,620 @@
+// Shell-command variables: `{{sh: git diff --staged}}` in a prompt is replaced
+// with the command's output when the prompt is rendered (see `template`).
+//
+// Nothing runs unless the user allowed the prompt to run exactly the commands
+// it has (`shellVariablePrompts` in settings.json maps prompt ids to the
+// approved commands). The opt-in stays on this machine, so imported or project
+// prompts never arrive with it, and a sync or import that changes a prompt's
+// commands turns them off until they are allowed again. The programs of a
+// command must also be listed in `shellAllowedCommands`, e.g. `git` or `cargo`.
+//
+// Commands aren't handed to a shell. They are words with '...' and "..."
+// quoting, joined into pipelines with `|`, and `2>&1` sends a program's errors
+// down the pipe too, which covers `{{sh: cargo test 2>&1 | tail -50}}`.
+// Redirections, `;`, `&&`, variables, wildcards and the like are refused
+// rather than passed on literally.
+//
+// They run in `shellWorkingDirectory`, or in the detected project (see
+// `projects`) when that is empty. A command that runs longer than
+// `shellTimeoutSecs`, prints more than `MAX_OUTPUT` or exits non-zero fails
+// the rendering with an error saying so.
+
+use std::collections::HashMap;
+use std::io::{PipeReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::prompts::Prompt;
+use crate::{library, projects, template};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+const MAX_TIMEOUT_SECS: u64 = 300;
+
+// Most output one command may splice into a prompt.
+const MAX_OUTPUT: u64 = 64 * 1024;
+
+static SETTINGS: Lazy<Mutex<ShellSettings>> = Lazy::new(|| Mutex::new(ShellSettings::default()));
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellSettings {
+    // The commands each prompt may run, by prompt id.
+    prompts: HashMap<String, Vec<String>>,
+    allowed_commands: Vec<String>,
+    working_directory: String,
+    timeout_secs: u64,
+}
+
+impl Default for ShellSettings {
+    fn default() -> Self {
+        ShellSettings {
+            prompts: HashMap::new(),
+            allowed_commands: Vec::new(),
+            working_directory: String::new(),
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+        }
+    }
+}
+
+fn shell_settings(get: impl Fn(&str) -> Option<serde_json::Value>) -> ShellSettings {
+    ShellSettings {
+        // Older versions kept a list of ids, approving any commands; those
+        // have to be allowed again.
+        prompts: get("shellVariablePrompts")
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default(),
+        allowed_commands: get("shellAllowedCommands")
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default(),
+        working_directory: get("shellWorkingDirectory")
+            .and_then(|value| value.as_str().map(|s| s.to_string()))
+            .unwrap_or_default(),
+        timeout_secs: get("shellTimeoutSecs")
+            .and_then(|value| value.as_u64())
+            .filter(|secs| (1..=MAX_TIMEOUT_SECS).contains(secs))
+            .unwrap_or(DEFAULT_TIMEOUT_SECS),
+    }
+}
+
+pub fn init(app: &AppHandle) {
+    let store = app.store("settings.json").ok();
+    let settings = shell_settings(|key| store.as_ref().and_then(|s| s.get(key)));
+    if !settings.prompts.is_empty() {
+        println!(
+            "🐚 {} prompts may run shell commands: {:?}",
+            settings.prompts.len(),
+            settings.allowed_commands
+        );
+    }
+    *SETTINGS.lock().unwrap() = settings;
+}
+
+// Reads the settings from `data_dir` directly, for when the app isn't running
+// (see `cli`).
+pub fn init_offline(data_dir: &Path) -> Result<(), String> {
+    let settings_path = data_dir.join("settings.json");
+    let settings: serde_json::Map<String, serde_json::Value> =
+        match std::fs::read_to_string(&settings_path) {
+            Ok(raw) => serde_json::from_str(&raw)
+                .map_err(|e| format!("{} is corrupted: {}", settings_path.display(), e))?,
+            Err(_) => serde_json::Map::new(),
+        };
+    *SETTINGS.lock().unwrap() = shell_settings(|key| settings.get(key).cloned());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_shell_settings() -> Result<ShellSettings, String> {
+    Ok(SETTINGS.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn update_shell_settings(
+    app: AppHandle,
+    allowed_commands: Vec<String>,
+    working_directory: String,
+    timeout_secs: u64,
+) -> Result<ShellSettings, String> {
+    if !(1..=MAX_TIMEOUT_SECS).contains(&timeout_secs) {
+        return Err(format!(
+            "The command timeout must be between 1 and {} seconds",
+            MAX_TIMEOUT_SECS
+        ));
+    }
+    let working_directory = working_directory.trim().to_string();
+    if !working_directory.is_empty() && !Path::new(&working_directory).is_dir() {
+        return Err(format!("{} is not a directory", working_directory));
+    }
+    let mut allowed: Vec<String> = Vec::new();
+    for command in allowed_commands.iter().map(|c| c.trim()) {
+        if !command.is_empty() && !allowed.iter().any(|c| c == command) {
+            allowed.push(command.to_string());
+        }
+    }
+    println!(
+        "🔧 Updating shell commands: {:?} in '{}'",
+        allowed, working_directory
+    );
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("Failed to load settings: {}", e))?;
+    store.set("shellAllowedCommands", allowed.clone());
+    store.set("shellWorkingDirectory", working_directory.clone());
+    store.set("shellTimeoutSecs", timeout_secs);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    let mut settings = SETTINGS.lock().unwrap();
+    settings.allowed_commands = allowed;
+    settings.working_directory = working_directory;
+    settings.timeout_secs = timeout_secs;
+    Ok(settings.clone())
+}
+
+// Whether the prompt `id` may run the commands it has now.
+#[tauri::command]
+pub async fn get_prompt_shell_commands(id: String) -> Result<bool, String> {
+    let prompt = library::current_prompt(&id)?;
+    Ok(is_allowed(&SETTINGS.lock().unwrap(), &prompt))
+}
+
+// Lets the prompt `id` run the commands it has now, or stops it.
+#[tauri::command]
+pub async fn set_prompt_shell_commands(
+    app: AppHandle,
+    id: String,
+    allowed: bool,
+) -> Result<(), String> {
+    let prompt = library::current_prompt(&id)?;
+    let mut prompts = SETTINGS.lock().unwrap().prompts.clone();
+    if allowed {
+        prompts.insert(id.clone(), template::commands(&prompt.content));
+    } else {
+        prompts.remove(&id);
+    }
+    println!("🔧 Shell commands for prompt {}: {}", id, allowed);
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("Failed to load settings: {}", e))?;
+    store.set("shellVariablePrompts", serde_json::json!(prompts));
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    SETTINGS.lock().unwrap().prompts = prompts;
+    Ok(())
+}
+
+fn is_allowed(settings: &ShellSettings, prompt: &Prompt) -> bool {
+    settings
+        .prompts
+        .get(&prompt.id)
+        .is_some_and(|approved| *approved == template::commands(&prompt.content))
+}
+
+// `prompt` with its variables filled in and its commands run.
+pub fn render(prompt: &Prompt, values: &HashMap<String, String>) -> Result<String, String> {
+    let settings = SETTINGS.lock().unwrap().clone();
+    let allowed = is_allowed(&settings, prompt);
+    template::render_with(&prompt.content, values, |command| {
+        if !allowed {
+            let reason = if settings.prompts.contains_key(&prompt.id) {
+                "its commands changed since they were allowed"
+            } else {
+                "they are off for it"
+            };
+            return Err(format!(
+                "“{}” runs shell commands, but {}; turn them on in its editor",
+                prompt.title, reason
+            ));
+        }
+        let pipeline = parse(command)?;
+        if let Some(stage) = pipeline
+            .iter()
+            .find(|stage| !settings.allowed_commands.contains(&stage.program))
+        {
+            return Err(format!(
+                "“{}” isn't in the list of allowed shell commands",
+                stage.program
+            ));
+        }
+        let directory = working_directory(&settings)?;
+        run(
+            command,
+            &pipeline,
+            &directory,
+            Duration::from_secs(settings.timeout_secs),
+        )
+    })
+}
+
+fn working_directory(settings: &ShellSettings) -> Result<PathBuf, String> {
+    if !settings.working_directory.is_empty() {
+        let directory = PathBuf::from(&settings.working_directory);
+        if !directory.is_dir() {
+            return Err(format!(
+                "The working directory for shell commands, {}, doesn't exist",
+                directory.display()
+            ));
+        }
+        return Ok(directory);
+    }
+    projects::active_project().ok_or_else(|| {
+        "Shell commands need a working directory; set one in the settings or open a project"
+            .to_string()
+    })
+}
+
+// One program of a pipeline.
+struct Stage {
+    program: String,
+    args: Vec<String>,
+    // `2>&1`: errors go down the pipe with the output.
+    merge_stderr: bool,
+}
+
+fn parse(command: &str) -> Result<Vec<Stage>, String> {
+    let mut pipeline = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut merge_stderr = false;
+    let mut word: Option<String> = None;
+    let mut chars = command.chars().peekable();
+
+    let mut end_stage = |words: &mut Vec<String>, merge_stderr: &mut bool| {
+        if words.is_empty() {
+            return Err(format!("“{}” has an empty part between pipes", command));
+        }
+        let mut args = std::mem::take(words);
+        pipeline.push(Stage {
+            program: args.remove(0),
+            args,
+            merge_stderr: std::mem::take(merge_stderr),
+        });
+        Ok(())
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '|' => {
+                if chars.peek() == Some(&'|') {
+                    return Err(unsupported("||"));
+                }
+                words.extend(word.take());
+                end_stage(&mut words, &mut merge_stderr)?;
+            }
+            '2' if word.is_none() && chars.clone().take(3).collect::<String>() == ">&1" => {
+                chars.nth(2);
+                if chars
+                    .peek()
+                    .is_some_and(|next| !next.is_whitespace() && *next != '|')
+                {
+                    return Err(unsupported("2>&1"));
+                }
+                merge_stderr = true;
+            }
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(format!("“{}” has an unclosed '", command)),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if matches!(chars.peek(), Some('"' | '\\')) => {
+                            word.extend(chars.next());
+                        }
+                        Some(c @ ('$' | '`')) => return Err(unsupported(&c.to_string())),
+                        Some(c) => word.push(c),
+                        None => return Err(format!("“{}” has an unclosed \"", command)),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => return Err(format!("“{}” ends with a lone \\", command)),
+            },
+            ';' | '&' | '<' | '>' | '(' | ')' | '$' | '`' | '*' | '?' => {
+                return Err(unsupported(&c.to_string()))
+            }
+            '~' | '#' if word.is_none() => return Err(unsupported(&c.to_string())),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word.take());
+    end_stage(&mut words, &mut merge_stderr)?;
+    Ok(pipeline)
+}
+
+fn unsupported(syntax: &str) -> String {
+    format!(
+        "“{}” isn't supported in shell variables; only programs, quoted words, | and 2>&1 are",
+        syntax
+    )
+}
+
+// Runs `pipeline` (which is `command`) in `directory` and returns its output.
+fn run(
+    command: &str,
+    pipeline: &[Stage],
+    directory: &Path,
+    timeout: Duration,
+) -> Result<String, String> {
+    println!("🐚 Running “{}” in {}", command, directory.display());
+    let started = Instant::now();
+    let mut children: Vec<Child> = Vec::new();
+    let mut errors = Vec::new();
+    let mut previous: Option<PipeReader> = None;
+    let spawned = (|| {
+        for stage in pipeline {
+            let (reader, writer) =
+                std::io::pipe().map_err(|e| format!("Failed to create a pipe: {}", e))?;
+            let mut program = Command::new(&stage.program);
+            program
+                .args(&stage.args)
+                .current_dir(directory)
+                .stdin(previous.take().map(Stdio::from).unwrap_or(Stdio::null()));
+            if stage.merge_stderr {
+                let copy = writer
+                    .try_clone()
+                    .map_err(|e| format!("Failed to create a pipe: {}", e))?;
+                program.stdout(copy).stderr(writer);
+            } else {
+                program.stdout(writer).stderr(Stdio::piped());
+            }
+            hide_console(&mut program);
+            let mut child = program
+                .spawn()
+                .map_err(|e| format!("Failed to run “{}”: {}", stage.program, e))?;
+            // Our copies of the pipe ends must close, or the reader never
+            // sees the end of the output.
+            drop(program);
+            if let Some(mut pipe) = child.stderr.take() {
+                let (sender, receiver) = mpsc::channel();
+                std::thread::spawn(move || {
+                    let mut buffer = Vec::new();
+                    let _ = pipe.read_to_end(&mut buffer);
+                    let _ = sender.send(String::from_utf8_lossy(&buffer).into_owned());
+                });
+                errors.push(receiver);
+            }
+            children.push(child);
+            previous = Some(reader);
+        }
+        Ok(())
+    })();
+    let kill_all = |children: &mut Vec<Child>| {
+        for child in children.iter_mut() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    };
+    if let Err(e) = spawned {
+        kill_all(&mut children);
+        println!("❌ {}", e);
+        return Err(e);
+    }
+
+    let (sender, output) = mpsc::channel();
+    if let Some(pipe) = previous {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let read = pipe.take(MAX_OUTPUT + 1).read_to_end(&mut buffer);
+            let _ = sender.send(read.map(|_| buffer));
+        });
+    }
+
+    let deadline = started + timeout;
+    let mut statuses = Vec::new();
+    for child in children.iter_mut() {
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break Some(status),
+                Ok(None) if Instant::now() >= deadline => break None,
+                Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+                Err(_) => break None,
+            }
+        };
+        statuses.push(status);
+    }
+    if statuses.iter().any(Option::is_none) {
+        kill_all(&mut children);
+        let error = format!("“{}” timed out after {:?}", command, timeout);
+        println!("⏱️  {}", error);
+        return Err(error);
+    }
+
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    let output = output
+        .recv_timeout(remaining)
+        .map_err(|_| format!("“{}” left its output open", command))?
+        .map_err(|e| format!("Failed to read the output of “{}”: {}", command, e))?;
+    if output.len() as u64 > MAX_OUTPUT {
+        let error = format!(
+            "“{}” printed more than {} KiB; narrow it down, e.g. with | tail -50",
+            command,
+            MAX_OUTPUT / 1024
+        );
+        println!("❌ {}", error);
+        return Err(error);
+    }
+    let output = String::from_utf8_lossy(&output).into_owned();
+
+    // Like a shell, the pipeline's status is the last program's.
+    let status = statuses.last().copied().flatten();
+    if let Some(status) = status.filter(|status| !status.success()) {
+        let errors: String = errors
+            .iter()
+            .filter_map(|receiver| receiver.recv_timeout(Duration::from_millis(100)).ok())
+            .collect();
+        // The last lines say what went wrong, wherever they went.
+        let reason = if errors.trim().is_empty() {
+            &output
+        } else {
+            &errors
+        };
+        let lines: Vec<&str> = reason.lines().filter(|l| !l.trim().is_empty()).collect();
+        let tail = lines[lines.len().saturating_sub(3)..].join("\n");
+        let error = if tail.is_empty() {
+            format!("“{}” failed with {}", command, status)
+        } else {
+            format!("“{}” failed with {}:\n{}", command, status, tail)
+        };
+        println!("❌ {}", error);
+        return Err(error);
+    }
+
+    println!("✅ “{}” finished in {:?}", command, started.elapsed());
+    Ok(output.trim_end_matches(['\n', '\r']).to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn hide_console(program: &mut Command) {
+    use std::os::windows::process::CommandExt;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    program.creation_flags(CREATE_NO_WINDOW);
+}
+
+#[cfg(not(target_os = "windows"))]
+fn hide_console(_program: &mut Command) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each stage as its words, with `2>&1` where it was.
+    fn stages(command: &str) -> Vec<Vec<String>> {
+        parse(command)
+            .unwrap()
+            .into_iter()
+            .map(|stage| {
+                let mut words = vec![stage.program];
+                words.extend(stage.args);
+                if stage.merge_stderr {
+                    words.push("2>&1".to_string());
+                }
+                words
+            })
+            .collect()
+    }
+
+    fn prompt(content: &str) -> Prompt {
+        Prompt {
+            id: "p".to_string(),
+            title: "P".to_string(),
+            content: content.to_string(),
+            color: crate::prompts::DEFAULT_COLOR.to_string(),
+            abbreviation: None,
+            tags: Vec::new(),
+            folder: None,
+        }
+    }
+
+    #[test]
+    fn splits_words_and_pipes() {
+        assert_eq!(
+            stages("git  log -3|tail -n 1"),
+            vec![vec!["git", "log", "-3"], vec!["tail", "-n", "1"]]
+        );
+    }
+
+    #[test]
+    fn quotes_and_escapes() {
+        assert_eq!(
+            stages(r#"grep -e 'a | b' "say \"hi\" \\ now" it\'s '' x"#),
+            vec![vec![
+                "grep",
+                "-e",
+                "a | b",
+                r#"say "hi" \ now"#,
+                "it's",
+                "",
+                "x"
+            ]]
+        );
+        assert!(parse("echo 'open").is_err());
+        assert!(parse("echo \"open").is_err());
+        assert!(parse("echo \\").is_err());
+    }
+
+    #[test]
+    fn merges_stderr_into_the_pipe() {
+        assert_eq!(
+            stages("cargo test 2>&1 | tail -50"),
+            vec![vec!["cargo", "test", "2>&1"], vec!["tail", "-50"]]
+        );
+        assert_eq!(
+            stages("cargo test 2>&1|tail"),
+            stages("cargo test 2>&1 | tail")
+        );
+        // Only as a word of its own.
+        assert!(parse("echo a2>&1").is_err());
+    }
+
+    #[test]
+    fn refuses_other_shell_syntax() {
+        for command in [
+            "make; rm -rf /",
+            "make && make install",
+            "make || true",
+            "make & disown",
+            "echo $HOME",
+            "echo \"$HOME\"",
+            "echo `id`",
+            "echo \"`id`\"",
+            "echo $(id)",
+            "cat < secrets",
+            "date > out",
+            "cargo test 2>&1x",
+            "cargo test 2>/dev/null",
+            "ls *.rs",
+            "ls ?",
+            "ls ~",
+            "echo # comment",
+        ] {
+            assert!(parse(command).is_err(), "{}", command);
+        }
+    }
+
+    #[test]
+    fn refuses_empty_stages() {
+        for command in ["", "   ", "| tail", "git log |", "git log | | tail"] {
+            assert!(parse(command).is_err(), "{}", command);
+        }
+    }
+
+    #[test]
+    fn allows_only_the_approved_commands() {
+        let mut settings = ShellSettings::default();
+        let prompt = prompt("{{sh: git status}}");
+        assert!(!is_allowed(&settings, &prompt));
+        settings
+            .prompts
+            .insert("p".to_string(), vec!["git status".to_string()]);
+        assert!(is_allowed(&settings, &prompt));
+        let changed = Prompt {
+            content: "{{sh: git status}} {{sh: git push}}".to_string(),
+            ..prompt
+        };
+        assert!(!is_allowed(&settings, &changed));
+    }
+}
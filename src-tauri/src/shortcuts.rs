@@ -0,0 +1,706 @@
+// Global shortcut registration plus the backend side of the shortcut settings:
+// validation/normalization, availability checks, recording of arbitrary key
+// combinations, and pausing (manually or while a blocklisted app is focused).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use tauri::{AppHandle, Emitter, Listener, Manager, WebviewWindow};
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+use tauri_plugin_store::StoreExt;
+
+use crate::CURRENT_TOGGLE_SHORTCUT;
+
+// How long `record_shortcut` waits for a key combination by default.
+const DEFAULT_RECORD_TIMEOUT_MS: u64 = 10_000;
+
+const DEFAULT_PAUSE_SHORTCUT: &str = "ctrl+alt+shift+p";
+
+// Shortcut that pauses/resumes all other global shortcuts. It stays registered
+// while paused so the user can always resume.
+static PAUSE_SHORTCUT: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+static MANUALLY_PAUSED: AtomicBool = AtomicBool::new(false);
+
+// Frontmost application from `shortcutBlocklist`, if any.
+static AUTO_PAUSE_APP: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+// True while the toggle and prompt shortcuts are unregistered.
+static GRABS_SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutValidation {
+    valid: bool,
+    // Canonical spelling (`cmd+ctrl+alt+shift+key`) to store in settings.
+    normalized: Option<String>,
+    error: Option<String>,
+    // Platform caveats that don't make the shortcut invalid but may surprise.
+    notes: Vec<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutAvailability {
+    available: bool,
+    normalized: Option<String>,
+    reason: Option<String>,
+}
+
+// Keydown forwarded by the window that started a recording. Field names follow
+// the DOM `KeyboardEvent`.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordedKey {
+    code: String,
+    #[serde(default)]
+    ctrl_key: bool,
+    #[serde(default)]
+    alt_key: bool,
+    #[serde(default)]
+    shift_key: bool,
+    #[serde(default)]
+    meta_key: bool,
+}
+
+pub fn prompt_shortcut(index: usize) -> String {
+    format!("cmd+alt+{}", index + 1)
+}
+
+// Parses `input` and renders it in the canonical form used in settings.json.
+pub fn normalize_shortcut(input: &str) -> Result<String, String> {
+    let shortcut = input
+        .trim()
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid shortcut format '{}': {}", input, e))?;
+    Ok(format_shortcut(&shortcut))
+}
+
+fn format_shortcut(shortcut: &Shortcut) -> String {
+    let mut parts = Vec::new();
+    if shortcut.mods.contains(Modifiers::SUPER) {
+        parts.push("cmd".to_string());
+    }
+    if shortcut.mods.contains(Modifiers::CONTROL) {
+        parts.push("ctrl".to_string());
+    }
+    if shortcut.mods.contains(Modifiers::ALT) {
+        parts.push("alt".to_string());
+    }
+    if shortcut.mods.contains(Modifiers::SHIFT) {
+        parts.push("shift".to_string());
+    }
+    parts.push(format_key(shortcut.key));
+    parts.join("+")
+}
+
+fn format_key(key: Code) -> String {
+    let symbol = match key {
+        Code::Backquote => "`",
+        Code::Minus => "-",
+        Code::Equal => "=",
+        Code::BracketLeft => "[",
+        Code::BracketRight => "]",
+        Code::Backslash => "\\",
+        Code::Semicolon => ";",
+        Code::Quote => "'",
+        Code::Comma => ",",
+        Code::Period => ".",
+        Code::Slash => "/",
+        _ => "",
+    };
+    if !symbol.is_empty() {
+        return symbol.to_string();
+    }
+
+    let name = key.to_string();
+    let name = name
+        .strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name);
+    name.to_lowercase()
+}
+
+fn platform_notes(input: &str, shortcut: &Shortcut) -> Vec<String> {
+    let mut notes = Vec::new();
+    let mods = shortcut.mods;
+
+    if mods.is_empty() {
+        notes.push("Shortcuts without modifiers grab that key in every application.".to_string());
+    } else if mods == Modifiers::SHIFT {
+        notes.push("Shift-only shortcuts intercept normal typing of that character.".to_string());
+    }
+
+    if input.to_lowercase().contains("cmdorctrl") || input.to_lowercase().contains("commandorcontrol") {
+        let resolved = if cfg!(target_os = "macos") { "cmd" } else { "ctrl" };
+        notes.push(format!("`cmdorctrl` resolves to `{}` on this platform.", resolved));
+    }
+
+    if mods.contains(Modifiers::SUPER) {
+        if cfg!(target_os = "linux") {
+            notes.push("`cmd` is not available on Linux; it is mapped to the Super key, which most desktop environments reserve for their own shortcuts.".to_string());
+        } else if cfg!(target_os = "windows") {
+            notes.push("`cmd` is mapped to the Windows key; most Win+key combinations are reserved by Windows and cannot be registered.".to_string());
+        }
+    }
+
+    if cfg!(target_os = "macos") && shortcut.key == Code::Space {
+        if mods == Modifiers::CONTROL {
+            notes.push("macOS uses ctrl+space to switch input sources by default.".to_string());
+        } else if mods == Modifiers::SUPER {
+            notes.push("macOS uses cmd+space for Spotlight by default.".to_string());
+        }
+    }
+
+    let is_prompt_shortcut = (0..9).any(|i| {
+        prompt_shortcut(i)
+            .parse::<Shortcut>()
+            .map(|s| s.id() == shortcut.id())
+            .unwrap_or(false)
+    });
+    if is_prompt_shortcut {
+        notes.push("This combination is already used by a prompt injection shortcut.".to_string());
+    }
+
+    notes
+}
+
+fn validate(input: &str) -> ShortcutValidation {
+    match input.trim().parse::<Shortcut>() {
+        Ok(shortcut) => ShortcutValidation {
+            valid: true,
+            normalized: Some(format_shortcut(&shortcut)),
+            error: None,
+            notes: platform_notes(input, &shortcut),
+        },
+        Err(e) => ShortcutValidation {
+            valid: false,
+            normalized: None,
+            error: Some(format!("Invalid shortcut format '{}': {}", input, e)),
+            notes: Vec::new(),
+        },
+    }
+}
+
+#[tauri::command]
+pub async fn validate_shortcut(shortcut: String) -> Result<ShortcutValidation, String> {
+    Ok(validate(&shortcut))
+}
+
+// Checks whether the OS lets us grab `shortcut` right now, without keeping the
+// registration.
+#[tauri::command]
+pub async fn test_register_shortcut(app: AppHandle, shortcut: String) -> Result<ShortcutAvailability, String> {
+    println!("🧪 Testing shortcut availability: {}", shortcut);
+
+    let parsed = match shortcut.trim().parse::<Shortcut>() {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            return Ok(ShortcutAvailability {
+                available: false,
+                normalized: None,
+                reason: Some(format!("Invalid shortcut format '{}': {}", shortcut, e)),
+            })
+        }
+    };
+    let normalized = Some(format_shortcut(&parsed));
+
+    if app.global_shortcut().is_registered(parsed) {
+        let is_toggle = CURRENT_TOGGLE_SHORTCUT
+            .lock()
+            .unwrap()
+            .as_deref()
+            .and_then(|current| current.parse::<Shortcut>().ok())
+            .map(|current| current.id() == parsed.id())
+            .unwrap_or(false);
+        // Re-using the current toggle shortcut is fine; anything else we own
+        // is a prompt injection or the pause shortcut.
+        let reason = if is_toggle {
+            "Already registered as the toggle shortcut"
+        } else {
+            "Already used by another Prompt Buddy shortcut"
+        };
+        return Ok(ShortcutAvailability {
+            available: is_toggle,
+            normalized,
+            reason: Some(reason.to_string()),
+        });
+    }
+
+    match app.global_shortcut().register(parsed) {
+        Ok(_) => {
+            if let Err(e) = app.global_shortcut().unregister(parsed) {
+                println!("⚠️  Failed to release test registration: {}", e);
+            }
+            Ok(ShortcutAvailability {
+                available: true,
+                normalized,
+                reason: None,
+            })
+        }
+        Err(e) => Ok(ShortcutAvailability {
+            available: false,
+            normalized,
+            reason: Some(format!("Could not register shortcut (probably used by another app): {}", e)),
+        }),
+    }
+}
+
+// Captures the next key combination pressed in the calling window. Our own
+// global shortcuts are released while recording so they can be captured too.
+// Pressing Escape without modifiers cancels.
+#[tauri::command]
+pub async fn record_shortcut(
+    app: AppHandle,
+    window: WebviewWindow,
+    timeout_ms: Option<u64>,
+) -> Result<ShortcutValidation, String> {
+    println!("⏺️  Recording shortcut...");
+
+    suspend_all(&app);
+
+    let (tx, rx) = mpsc::channel::<RecordedKey>();
+    let listener = app.listen("shortcut-recorder-key", move |event| {
+        match serde_json::from_str::<RecordedKey>(event.payload()) {
+            Ok(key) => {
+                let _ = tx.send(key);
+            }
+            Err(e) => println!("⚠️  Ignoring malformed recorder key event: {}", e),
+        }
+    });
+
+    if let Err(e) = window.emit("shortcut-recording-started", ()) {
+        println!("⚠️  Failed to emit shortcut-recording-started: {}", e);
+    }
+
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_RECORD_TIMEOUT_MS));
+    let result = tauri::async_runtime::spawn_blocking(move || wait_for_combo(&rx, timeout))
+        .await
+        .map_err(|e| format!("Shortcut recording failed: {}", e))
+        .and_then(|result| result);
+
+    app.unlisten(listener);
+    restore_all(&app);
+    let _ = window.emit("shortcut-recording-stopped", ());
+
+    let combo = result?;
+    println!("⏺️  Recorded shortcut: {}", combo);
+    Ok(validate(&combo))
+}
+
+fn wait_for_combo(rx: &mpsc::Receiver<RecordedKey>, timeout: Duration) -> Result<String, String> {
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        let key = rx
+            .recv_timeout(remaining)
+            .map_err(|_| "Timed out waiting for a key combination".to_string())?;
+
+        // Modifier keydowns arrive first; keep waiting for the actual key.
+        let is_modifier = ["Control", "Alt", "Shift", "Meta", "OS"]
+            .iter()
+            .any(|m| key.code.starts_with(m));
+        if is_modifier {
+            continue;
+        }
+
+        let has_modifiers = key.ctrl_key || key.alt_key || key.shift_key || key.meta_key;
+        if key.code == "Escape" && !has_modifiers {
+            return Err("Shortcut recording cancelled".to_string());
+        }
+
+        let mut parts = Vec::new();
+        if key.meta_key {
+            parts.push("cmd");
+        }
+        if key.ctrl_key {
+            parts.push("ctrl");
+        }
+        if key.alt_key {
+            parts.push("alt");
+        }
+        if key.shift_key {
+            parts.push("shift");
+        }
+        parts.push(&key.code);
+        return Ok(parts.join("+"));
+    }
+}
+
+pub fn register_toggle_shortcut(app: &AppHandle, shortcut_str: &str) -> Result<(), String> {
+    let shortcut = shortcut_str
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid shortcut format '{}': {}", shortcut_str, e))?;
+
+    let shortcut_for_log = shortcut_str.to_string();
+    app.global_shortcut()
+        .on_shortcut(shortcut, move |app, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                println!("🎯 Toggle shortcut ({}) pressed!", shortcut_for_log);
+            }
+            crate::handle_toggle_shortcut(app, event.state());
+        })
+        .map_err(|e| format!("Failed to register shortcut '{}': {}", shortcut_str, e))?;
+
+    *CURRENT_TOGGLE_SHORTCUT.lock().unwrap() = Some(shortcut_str.to_string());
+    Ok(())
+}
+
+// Registers Cmd+Alt+1-9 and returns how many could be grabbed.
+pub fn register_prompt_shortcuts(app: &AppHandle) -> usize {
+    let mut successful_shortcuts = 0;
+    for prompt_index in 0..9 {
+        let shortcut_str = prompt_shortcut(prompt_index);
+
+        match shortcut_str.parse::<Shortcut>() {
+            Ok(shortcut) => {
+                let shortcut_for_log = shortcut_str.clone();
+                match app.global_shortcut().on_shortcut(shortcut, move |app, _shortcut, event| {
+                    if event.state() == ShortcutState::Pressed {
+                        println!("🚀 Prompt shortcut triggered: {}", shortcut_for_log);
+                        // Emit event to frontend to trigger injection
+                        if let Some(window) = app.get_webview_window("main") {
+                            if let Err(e) = window.emit("inject-prompt", prompt_index) {
+                                println!("❌ Failed to emit inject-prompt event: {}", e);
+                            } else {
+                                println!("✅ Emitted inject-prompt event for index: {}", prompt_index);
+                            }
+                        }
+                    }
+                }) {
+                    Ok(_) => {
+                        println!("✅ Registered: {}", shortcut_str);
+                        successful_shortcuts += 1;
+                    }
+                    Err(e) => {
+                        println!("❌ Failed to register {}: {} (probably conflicts with another app)", shortcut_str, e);
+                    }
+                }
+            }
+            Err(e) => {
+                println!("❌ Failed to parse shortcut {}: {}", shortcut_str, e);
+            }
+        }
+    }
+    successful_shortcuts
+}
+
+// Releases every global shortcut we own; `restore_all` grabs them again.
+fn suspend_all(app: &AppHandle) {
+    if let Err(e) = app.global_shortcut().unregister_all() {
+        println!("⚠️  Failed to unregister shortcuts: {}", e);
+    }
+}
+
+fn restore_all(app: &AppHandle) {
+    // While paused only the pause shortcut itself stays grabbed.
+    if !GRABS_SUSPENDED.load(Ordering::SeqCst) {
+        register_grabs(app);
+    }
+    let pause = PAUSE_SHORTCUT.lock().unwrap().clone();
+    if let Some(pause) = pause {
+        if let Err(e) = register_pause_shortcut(app, &pause) {
+            println!("❌ {}", e);
+        }
+    }
+}
+
+// The toggle shortcut and Cmd+Alt+1-9 ("grabs") are what collide with games,
+// remote desktops and other apps, so those are what pausing releases.
+fn register_grabs(app: &AppHandle) {
+    let toggle = CURRENT_TOGGLE_SHORTCUT.lock().unwrap().clone();
+    if let Some(toggle) = toggle {
+        if let Err(e) = register_toggle_shortcut(app, &toggle) {
+            println!("❌ {}", e);
+        }
+    }
+    register_prompt_shortcuts(app);
+}
+
+fn unregister_grabs(app: &AppHandle) {
+    let toggle = CURRENT_TOGGLE_SHORTCUT.lock().unwrap().clone();
+    let shortcuts = toggle
+        .into_iter()
+        .chain((0..9).map(prompt_shortcut))
+        .filter_map(|s| s.parse::<Shortcut>().ok());
+    for shortcut in shortcuts {
+        if app.global_shortcut().is_registered(shortcut) {
+            if let Err(e) = app.global_shortcut().unregister(shortcut) {
+                println!("⚠️  Failed to unregister {}: {}", format_shortcut(&shortcut), e);
+            }
+        }
+    }
+}
+
+// Whether the toggle and prompt shortcuts are currently released because of a
+// manual pause or a blocklisted frontmost app.
+pub fn grabs_suspended() -> bool {
+    GRABS_SUSPENDED.load(Ordering::SeqCst)
+}
+
+pub fn register_pause_shortcut(app: &AppHandle, shortcut_str: &str) -> Result<(), String> {
+    let shortcut = shortcut_str
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid shortcut format '{}': {}", shortcut_str, e))?;
+
+    app.global_shortcut()
+        .on_shortcut(shortcut, |app, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                let paused = !MANUALLY_PAUSED.load(Ordering::SeqCst);
+                println!("⏯️  Pause shortcut pressed, paused = {}", paused);
+                set_manual_pause(app, paused);
+            }
+        })
+        .map_err(|e| format!("Failed to register shortcut '{}': {}", shortcut_str, e))?;
+
+    *PAUSE_SHORTCUT.lock().unwrap() = Some(shortcut_str.to_string());
+    Ok(())
+}
+
+// 从设置中读取暂停快捷键配置
+pub fn load_pause_shortcut_from_settings(app: &AppHandle) -> String {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get("pauseShortcut"))
+        .and_then(|value| value.as_str().map(|s| s.to_string()))
+        .unwrap_or_else(|| DEFAULT_PAUSE_SHORTCUT.to_string())
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PauseStatus {
+    paused: bool,
+    manually_paused: bool,
+    // Frontmost blocklisted application that caused an automatic pause.
+    blocked_app: Option<String>,
+}
+
+fn pause_status() -> PauseStatus {
+    PauseStatus {
+        paused: GRABS_SUSPENDED.load(Ordering::SeqCst),
+        manually_paused: MANUALLY_PAUSED.load(Ordering::SeqCst),
+        blocked_app: AUTO_PAUSE_APP.lock().unwrap().clone(),
+    }
+}
+
+fn set_manual_pause(app: &AppHandle, paused: bool) {
+    MANUALLY_PAUSED.store(paused, Ordering::SeqCst);
+    sync_pause_state(app);
+}
+
+// Brings the actual registrations in line with the manual/automatic pause
+// flags and tells the windows about it.
+fn sync_pause_state(app: &AppHandle) {
+    let should_pause =
+        MANUALLY_PAUSED.load(Ordering::SeqCst) || AUTO_PAUSE_APP.lock().unwrap().is_some();
+
+    if GRABS_SUSPENDED.swap(should_pause, Ordering::SeqCst) != should_pause {
+        if should_pause {
+            println!("⏸️  Pausing global shortcuts");
+            unregister_grabs(app);
+        } else {
+            println!("▶️  Resuming global shortcuts");
+            register_grabs(app);
+        }
+    }
+
+    if let Err(e) = app.emit("shortcuts-paused", pause_status()) {
+        println!("⚠️  Failed to emit shortcuts-paused event: {}", e);
+    }
+}
+
+// What is currently grabbed, for clients of `ipc`.
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutStatus {
+    toggle: Option<String>,
+    toggle_registered: bool,
+    pause: Option<String>,
+    // Prompt shortcuts that are registered right now.
+    prompt_shortcuts: Vec<String>,
+    #[serde(flatten)]
+    pause_status: PauseStatus,
+}
+
+pub fn status(app: &AppHandle) -> ShortcutStatus {
+    let is_registered = |shortcut: &str| {
+        shortcut
+            .parse::<Shortcut>()
+            .is_ok_and(|shortcut| app.global_shortcut().is_registered(shortcut))
+    };
+    let toggle = CURRENT_TOGGLE_SHORTCUT.lock().unwrap().clone();
+    ShortcutStatus {
+        toggle_registered: toggle.as_deref().is_some_and(is_registered),
+        toggle,
+        pause: PAUSE_SHORTCUT.lock().unwrap().clone(),
+        prompt_shortcuts: (0..9)
+            .map(prompt_shortcut)
+            .filter(|shortcut| is_registered(shortcut))
+            .collect(),
+        pause_status: pause_status(),
+    }
+}
+
+#[tauri::command]
+pub async fn set_shortcuts_paused(app: AppHandle, paused: bool) -> Result<PauseStatus, String> {
+    println!("🔧 Setting shortcuts paused: {}", paused);
+    set_manual_pause(&app, paused);
+    Ok(pause_status())
+}
+
+#[tauri::command]
+pub async fn get_shortcuts_pause_status() -> Result<PauseStatus, String> {
+    Ok(pause_status())
+}
+
+#[tauri::command]
+pub async fn update_pause_shortcut(app: AppHandle, new_shortcut: String) -> Result<String, String> {
+    println!("🔧 Updating pause shortcut to: {}", new_shortcut);
+    let normalized = normalize_shortcut(&new_shortcut)?;
+
+    let previous = PAUSE_SHORTCUT.lock().unwrap().clone();
+    if let Some(shortcut) = previous.as_deref().and_then(|s| s.parse::<Shortcut>().ok()) {
+        if let Err(e) = app.global_shortcut().unregister(shortcut) {
+            println!("⚠️  Failed to unregister previous pause shortcut: {}", e);
+        }
+    }
+
+    match register_pause_shortcut(&app, &normalized) {
+        Ok(_) => Ok(format!("Pause shortcut updated to: {}", normalized)),
+        Err(e) => {
+            if let Some(previous) = previous {
+                let _ = register_pause_shortcut(&app, &previous);
+            }
+            Err(e)
+        }
+    }
+}
+
+pub fn has_blocklist(app: &AppHandle) -> bool {
+    !load_blocklist(app).is_empty()
+}
+
+fn load_blocklist(app: &AppHandle) -> Vec<String> {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get("shortcutBlocklist"))
+        .and_then(|value| serde_json::from_value::<Vec<String>>(value).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+// Called by the focus tracker whenever the frontmost application changes.
+pub fn on_focus_changed(app: &AppHandle, frontmost: Option<&str>) {
+    let blocked = frontmost.filter(|name| {
+        load_blocklist(app)
+            .iter()
+            .any(|entry| entry.eq_ignore_ascii_case(name))
+    });
+
+    let changed = {
+        let mut auto_pause_app = AUTO_PAUSE_APP.lock().unwrap();
+        let blocked = blocked.map(|name| name.to_string());
+        if *auto_pause_app != blocked {
+            *auto_pause_app = blocked;
+            true
+        } else {
+            false
+        }
+    };
+
+    if changed {
+        match blocked {
+            Some(name) => println!("⛔ {} is blocklisted, suspending global shortcuts", name),
+            None => println!("✅ Left blocklisted app, restoring global shortcuts"),
+        }
+        sync_pause_state(app);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_spelling_and_modifier_order() {
+        for input in [
+            "cmd+alt+1",
+            "Command+Option+Digit1",
+            "  super+ALT+1 ",
+            "alt+cmd+1",
+        ] {
+            assert_eq!(normalize_shortcut(input).unwrap(), "cmd+alt+1", "{}", input);
+        }
+        assert_eq!(
+            normalize_shortcut("Shift+Control+Alt+Cmd+KeyP").unwrap(),
+            "cmd+ctrl+alt+shift+p"
+        );
+    }
+
+    #[test]
+    fn normalizes_keys() {
+        assert_eq!(normalize_shortcut("ctrl+Slash").unwrap(), "ctrl+/");
+        assert_eq!(normalize_shortcut("ctrl+`").unwrap(), "ctrl+`");
+        assert_eq!(normalize_shortcut("alt+Space").unwrap(), "alt+space");
+        assert_eq!(normalize_shortcut("ctrl+F12").unwrap(), "ctrl+f12");
+    }
+
+    #[test]
+    fn resolves_cmd_or_ctrl_for_this_platform() {
+        let expected = if cfg!(target_os = "macos") {
+            "cmd+shift+p"
+        } else {
+            "ctrl+shift+p"
+        };
+        assert_eq!(normalize_shortcut("CmdOrCtrl+Shift+P").unwrap(), expected);
+        assert_eq!(
+            normalize_shortcut("CommandOrControl+Shift+P").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn normalized_shortcuts_parse_to_the_same_shortcut() {
+        for input in ["ctrl+alt+shift+p", "cmd+;", "alt+=", "ctrl+shift+["] {
+            let normalized = normalize_shortcut(input).unwrap();
+            assert_eq!(normalize_shortcut(&normalized).unwrap(), normalized);
+            assert_eq!(
+                normalized.parse::<Shortcut>().unwrap().id(),
+                input.parse::<Shortcut>().unwrap().id()
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_shortcuts() {
+        for input in ["", "ctrl+", "ctrl+alt", "ctrl+a+b", "ctrl+nope", "hyper+a"] {
+            let err = normalize_shortcut(input).unwrap_err();
+            assert!(
+                err.starts_with("Invalid shortcut format"),
+                "{}: {}",
+                input,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn validation_notes() {
+        let validation = validate("cmd+alt+3");
+        assert!(validation.valid);
+        assert!(validation
+            .notes
+            .iter()
+            .any(|note| note.contains("prompt injection shortcut")));
+        assert!(validate("f").notes[0].contains("without modifiers"));
+        assert!(validate("shift+f").notes[0].contains("Shift-only"));
+        let invalid = validate("ctrl+");
+        assert!(!invalid.valid);
+        assert!(invalid.normalized.is_none() && invalid.error.is_some());
+    }
+}
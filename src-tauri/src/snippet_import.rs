@@ -0,0 +1,872 @@
+// Importers for the snippet collections of other tools:
+// - Espanso match files (YAML with `matches:`);
+// - Alfred snippet collections (`.alfredsnippets`, a zip of JSON files, or a
+//   directory of the extracted files);
+// - Raycast snippet exports (JSON array);
+// - VS Code snippet files (`.code-snippets`, JSON with comments).
+//
+// Triggers become abbreviations and placeholders become prompt variables,
+// `{{name}}` or `{{name|default}}`. The clipboard is `{{clipboard}}`, which the
+// bar and text expansion fill in with the clipboard text; the CLI, the API and
+// links have to pass it like any other variable (`--var clipboard=...`).
+// Text expansion can't ask for other values, so a snippet with a variable
+// without a default is imported without its trigger. Whatever has no
+// equivalent (cursor markers, dates, regex triggers, shell commands...) is
+// dropped or kept as plain text, and reported as an `ImportIssue`.
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::prompts::{self, Prompt};
+use crate::template;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportIssue {
+    // Title (or trigger) of the snippet concerned.
+    pub item: String,
+    pub message: String,
+}
+
+#[derive(Default)]
+pub struct Converted {
+    pub prompts: Vec<Prompt>,
+    pub issues: Vec<ImportIssue>,
+}
+
+impl Converted {
+    pub fn into_parts(self) -> (Vec<Prompt>, Vec<ImportIssue>) {
+        (self.prompts, self.issues)
+    }
+
+    fn issue(&mut self, item: &str, message: impl Into<String>) {
+        self.issues.push(ImportIssue {
+            item: item.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn push(&mut self, title: String, content: String, abbreviation: Option<String>, tags: Vec<String>) {
+        let mut abbreviation = abbreviation.filter(|a| !a.trim().is_empty());
+        let required: Vec<String> = template::variables(&content)
+            .into_iter()
+            .filter(|v| v.default.is_none() && v.name != "clipboard")
+            .map(|v| v.name)
+            .collect();
+        if let Some(trigger) = abbreviation.take_if(|_| !required.is_empty()) {
+            self.issue(
+                &title,
+                format!(
+                    "Trigger '{}' dropped: text expansion can't ask for {}; use the bar instead",
+                    trigger,
+                    required.join(", ")
+                ),
+            );
+        }
+        self.prompts.push(Prompt {
+            id: String::new(),
+            title,
+            content,
+            color: prompts::DEFAULT_COLOR.to_string(),
+            abbreviation,
+            tags,
+            folder: None,
+        });
+    }
+}
+
+// First line of a snippet, shortened, for snippets without a name.
+fn title_from(content: &str, fallback: &str) -> String {
+    let line = content.lines().map(str::trim).find(|line| !line.is_empty());
+    match line {
+        Some(line) if line.chars().count() > 40 => {
+            format!("{}…", line.chars().take(40).collect::<String>())
+        }
+        Some(line) => line.to_string(),
+        None => fallback.to_string(),
+    }
+}
+
+// --- Espanso ---
+
+#[derive(Deserialize)]
+struct EspansoFile {
+    #[serde(default)]
+    matches: Vec<EspansoMatch>,
+    #[serde(default)]
+    global_vars: Vec<serde_yaml::Value>,
+    #[serde(default)]
+    imports: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct EspansoMatch {
+    trigger: Option<String>,
+    #[serde(default)]
+    triggers: Vec<String>,
+    regex: Option<String>,
+    replace: Option<String>,
+    form: Option<String>,
+    label: Option<String>,
+    #[serde(default)]
+    vars: Vec<EspansoVar>,
+    image_path: Option<String>,
+    html: Option<String>,
+    markdown: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EspansoVar {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    params: serde_yaml::Value,
+}
+
+pub fn espanso(path: &Path) -> Result<Converted, String> {
+    let raw = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let file: EspansoFile = serde_yaml::from_str(&raw)
+        .map_err(|e| format!("{} is not an Espanso match file: {}", path.display(), e))?;
+
+    let mut converted = Converted::default();
+    if !file.imports.is_empty() {
+        converted.issue("imports", "Imported match files are not followed; import them separately");
+    }
+    if !file.global_vars.is_empty() {
+        converted.issue("global_vars", "Global variables are not supported and were ignored");
+    }
+
+    for (i, m) in file.matches.into_iter().enumerate() {
+        let mut triggers: Vec<String> = m.trigger.into_iter().chain(m.triggers).collect();
+        let item = m
+            .label
+            .clone()
+            .or_else(|| triggers.first().cloned())
+            .unwrap_or_else(|| format!("match {}", i + 1));
+
+        if let Some(regex) = &m.regex {
+            if triggers.is_empty() {
+                converted.issue(&item, format!("Regex trigger '{}' is not supported; skipped", regex));
+                continue;
+            }
+        }
+        let (text, is_form) = match (m.replace.or(m.markdown).or(m.html), m.form) {
+            (Some(text), _) => (text, false),
+            (None, Some(form)) => (form, true),
+            (None, None) => {
+                let what = if m.image_path.is_some() { "Image" } else { "Empty" };
+                converted.issue(&item, format!("{} matches are not supported; skipped", what));
+                continue;
+            }
+        };
+
+        let mut content = if is_form {
+            // Form fields are `[[name]]`.
+            replace_delimited(&text, "[[", "]]", |name| Some(format!("{{{{{}}}}}", name.trim())))
+        } else {
+            text
+        };
+        if content.contains("$|$") {
+            content = content.replace("$|$", "");
+            converted.issue(&item, "Cursor position marker $|$ removed");
+        }
+        for var in &m.vars {
+            match var.kind.as_str() {
+                "echo" => {
+                    let echo = var.params.get("echo").and_then(|v| v.as_str()).unwrap_or("");
+                    content = content.replace(
+                        &format!("{{{{{}}}}}", var.name),
+                        &format!("{{{{{}|{}}}}}", var.name, echo),
+                    );
+                }
+                "clipboard" => {
+                    content = content.replace(&format!("{{{{{}}}}}", var.name), "{{clipboard}}");
+                }
+                "form" => {}
+                kind => converted.issue(
+                    &item,
+                    format!("Variable '{}' of type '{}' is not supported; it becomes a plain variable", var.name, kind),
+                ),
+            }
+        }
+
+        let abbreviation = if triggers.is_empty() { None } else { Some(triggers.remove(0)) };
+        if !triggers.is_empty() {
+            converted.issue(&item, format!("Only the first trigger is kept; dropped {}", triggers.join(", ")));
+        }
+        let title = m.label.unwrap_or_else(|| title_from(&content, &item));
+        converted.push(title, content, abbreviation, Vec::new());
+    }
+    Ok(converted)
+}
+
+// --- Alfred ---
+
+#[derive(Deserialize)]
+struct AlfredFile {
+    alfredsnippet: AlfredSnippet,
+}
+
+#[derive(Deserialize)]
+struct AlfredSnippet {
+    snippet: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    keyword: String,
+}
+
+pub fn alfred(path: &Path) -> Result<Converted, String> {
+    // (file name, contents) of every file in the collection.
+    let mut files: Vec<(String, String)> = Vec::new();
+    if path.is_dir() {
+        let entries = fs::read_dir(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Ok(contents) = fs::read_to_string(entry.path()) {
+                files.push((name, contents));
+            }
+        }
+    } else {
+        let file = fs::File::open(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut archive = zip::ZipArchive::new(file)
+            .map_err(|e| format!("{} is not an Alfred snippet collection: {}", path.display(), e))?;
+        for i in 0..archive.len() {
+            let mut entry = archive
+                .by_index(i)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let mut contents = String::new();
+            if entry.is_file() && entry.read_to_string(&mut contents).is_ok() {
+                files.push((entry.name().to_string(), contents));
+            }
+        }
+    }
+    files.sort();
+
+    // The collection's keyword prefix and suffix live in info.plist.
+    let plist = files
+        .iter()
+        .find(|(name, _)| name.ends_with("info.plist"))
+        .map(|(_, contents)| contents.as_str())
+        .unwrap_or("");
+    let prefix = plist_string(plist, "snippetkeywordprefix").unwrap_or_default();
+    let suffix = plist_string(plist, "snippetkeywordsuffix").unwrap_or_default();
+
+    let mut converted = Converted::default();
+    for (name, contents) in files.iter().filter(|(name, _)| name.ends_with(".json")) {
+        let snippet = match serde_json::from_str::<AlfredFile>(contents) {
+            Ok(file) => file.alfredsnippet,
+            Err(e) => {
+                converted.issue(name, format!("Not an Alfred snippet: {}", e));
+                continue;
+            }
+        };
+        let item = if snippet.name.is_empty() { name.clone() } else { snippet.name.clone() };
+        let content = convert_braces(&snippet.snippet, &item, &mut converted.issues);
+        let abbreviation = (!snippet.keyword.is_empty())
+            .then(|| format!("{}{}{}", prefix, snippet.keyword, suffix));
+        let title = if snippet.name.is_empty() { title_from(&content, name) } else { snippet.name };
+        converted.push(title, content, abbreviation, Vec::new());
+    }
+    if converted.prompts.is_empty() && converted.issues.is_empty() {
+        return Err(format!("{} contains no Alfred snippets", path.display()));
+    }
+    Ok(converted)
+}
+
+// Value of `<key>key</key><string>value</string>` in an XML plist.
+fn plist_string(plist: &str, key: &str) -> Option<String> {
+    let start = plist.find(&format!("<key>{}</key>", key))?;
+    let rest = &plist[start..];
+    let open = rest.find("<string>")? + "<string>".len();
+    let close = rest[open..].find("</string>")?;
+    Some(rest[open..open + close].to_string())
+}
+
+// --- Raycast ---
+
+#[derive(Deserialize)]
+struct RaycastSnippet {
+    #[serde(default)]
+    name: String,
+    text: String,
+    #[serde(default)]
+    keyword: Option<String>,
+}
+
+pub fn raycast(path: &Path) -> Result<Converted, String> {
+    let raw = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let snippets: Vec<RaycastSnippet> = serde_json::from_str(&raw)
+        .map_err(|e| format!("{} is not a Raycast snippet export: {}", path.display(), e))?;
+
+    let mut converted = Converted::default();
+    for (i, snippet) in snippets.into_iter().enumerate() {
+        let item = if snippet.name.is_empty() { format!("snippet {}", i + 1) } else { snippet.name.clone() };
+        let content = convert_braces(&snippet.text, &item, &mut converted.issues);
+        let title = if snippet.name.is_empty() { title_from(&content, &item) } else { snippet.name };
+        converted.push(title, content, snippet.keyword, Vec::new());
+    }
+    Ok(converted)
+}
+
+// Alfred and Raycast share most of their `{placeholder}` syntax. Raycast's
+// `{argument name="x" default="y"}` becomes `{{x|y}}`.
+fn convert_braces(text: &str, item: &str, issues: &mut Vec<ImportIssue>) -> String {
+    let mut unsupported: Vec<String> = Vec::new();
+    let mut dropped_cursor = false;
+    let content = replace_delimited(text, "{", "}", |inner| {
+        let inner = inner.trim();
+        let keyword = inner.split([' ', ':']).next().unwrap_or("");
+        match keyword {
+            "clipboard" if inner == "clipboard" => Some("{{clipboard}}".to_string()),
+            "cursor" => {
+                dropped_cursor = true;
+                Some(String::new())
+            }
+            "argument" => {
+                let name = attribute(inner, "name").unwrap_or_else(|| "argument".to_string());
+                Some(match attribute(inner, "default") {
+                    Some(default) => format!("{{{{{}|{}}}}}", name, default),
+                    None => format!("{{{{{}}}}}", name),
+                })
+            }
+            "clipboard" | "date" | "time" | "datetime" | "day" | "uuid" | "selection"
+            | "snippet" | "random" | "browser-tab" => {
+                unsupported.push(format!("{{{}}}", inner));
+                None
+            }
+            // Anything else is just text in braces.
+            _ => None,
+        }
+    });
+    if dropped_cursor {
+        issues.push(ImportIssue {
+            item: item.to_string(),
+            message: "Cursor placeholder removed".to_string(),
+        });
+    }
+    for placeholder in unsupported {
+        issues.push(ImportIssue {
+            item: item.to_string(),
+            message: format!("Placeholder {} is not supported; kept as text", placeholder),
+        });
+    }
+    content
+}
+
+// Value of `key="value"` inside a Raycast placeholder.
+fn attribute(placeholder: &str, key: &str) -> Option<String> {
+    let start = placeholder.find(&format!("{}=\"", key))? + key.len() + 2;
+    let end = placeholder[start..].find('"')?;
+    Some(placeholder[start..start + end].to_string())
+}
+
+// --- VS Code ---
+
+pub fn vscode(path: &Path) -> Result<Converted, String> {
+    let raw = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let value: Value = serde_json::from_str(&strip_jsonc(&raw))
+        .map_err(|e| format!("{} is not a VS Code snippet file: {}", path.display(), e))?;
+    let Value::Object(snippets) = value else {
+        return Err(format!("{} is not a VS Code snippet file", path.display()));
+    };
+
+    let mut converted = Converted::default();
+    for (name, snippet) in snippets {
+        let body = match snippet.get("body") {
+            Some(Value::String(body)) => body.clone(),
+            Some(Value::Array(lines)) => lines
+                .iter()
+                .map(|line| line.as_str().unwrap_or(""))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => {
+                converted.issue(&name, "Snippet has no body; skipped");
+                continue;
+            }
+        };
+        let mut prefixes: Vec<String> = match snippet.get("prefix") {
+            Some(Value::String(prefix)) => vec![prefix.clone()],
+            Some(Value::Array(prefixes)) => prefixes
+                .iter()
+                .filter_map(|p| p.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let abbreviation = if prefixes.is_empty() { None } else { Some(prefixes.remove(0)) };
+        if !prefixes.is_empty() {
+            converted.issue(&name, format!("Only the first prefix is kept; dropped {}", prefixes.join(", ")));
+        }
+        // Language scopes make good tags.
+        let tags: Vec<String> = snippet
+            .get("scope")
+            .and_then(|scope| scope.as_str())
+            .map(|scope| scope.split(',').map(|s| s.trim().to_string()).collect())
+            .unwrap_or_default();
+
+        let content = convert_tabstops(&body, &name, &mut converted.issues);
+        converted.push(name, content, abbreviation, prompts::normalize_tags(&tags));
+    }
+    Ok(converted)
+}
+
+// Converts TextMate snippet syntax: `$1`/`${1}` become `{{arg1}}`, `${1:text}`
+// `{{arg1|text}}`, choices `${1|a,b|}` keep their first option, `$0` is
+// dropped, and `$CLIPBOARD` becomes `{{clipboard}}`. Other variables become
+// plain variables named after them.
+fn convert_tabstops(body: &str, item: &str, issues: &mut Vec<ImportIssue>) -> String {
+    let mut out = String::new();
+    let chars: Vec<char> = body.chars().collect();
+    let mut i = 0;
+    let mut report = |message: String| {
+        if !issues.iter().any(|issue| issue.item == item && issue.message == message) {
+            issues.push(ImportIssue {
+                item: item.to_string(),
+                message,
+            });
+        }
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\\' && matches!(chars.get(i + 1), Some('$' | '}' | '\\')) {
+            out.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+        if c != '$' {
+            out.push(c);
+            i += 1;
+            continue;
+        }
+
+        // `${...}`, with nesting.
+        if chars.get(i + 1) == Some(&'{') {
+            let mut depth = 0;
+            let mut end = None;
+            for (j, &ch) in chars.iter().enumerate().skip(i + 1) {
+                match ch {
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = Some(j);
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let Some(end) = end else {
+                out.push(c);
+                i += 1;
+                continue;
+            };
+            let inner: String = chars[i + 2..end].iter().collect();
+            out.push_str(&convert_placeholder(&inner, &mut report));
+            i = end + 1;
+            continue;
+        }
+
+        // `$1` or `$NAME`.
+        let word: String = chars[i + 1..]
+            .iter()
+            .take_while(|ch| ch.is_ascii_alphanumeric() || **ch == '_')
+            .collect();
+        if word.is_empty() {
+            out.push(c);
+            i += 1;
+            continue;
+        }
+        out.push_str(&convert_placeholder(&word, &mut report));
+        i += 1 + word.chars().count();
+    }
+    out
+}
+
+fn convert_placeholder(inner: &str, report: &mut impl FnMut(String)) -> String {
+    let name_len = inner
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(inner.len());
+    let (name, rest) = inner.split_at(name_len);
+
+    let variable = if name.chars().all(|c| c.is_ascii_digit()) {
+        if name == "0" {
+            report("Final cursor position $0 removed".to_string());
+            return String::new();
+        }
+        format!("arg{}", name)
+    } else if name == "CLIPBOARD" {
+        "clipboard".to_string()
+    } else {
+        report(format!("Variable ${} has no equivalent; it becomes a plain variable", name));
+        name.to_lowercase()
+    };
+
+    if let Some(default) = rest.strip_prefix(':') {
+        let default = placeholder_text(default);
+        format!("{{{{{}|{}}}}}", variable, default)
+    } else if let Some(choices) = rest.strip_prefix('|').and_then(|r| r.strip_suffix('|')) {
+        report(format!("Choice ${{{}}} reduced to its first option", inner));
+        let first = choices.split(',').next().unwrap_or("");
+        format!("{{{{{}|{}}}}}", variable, first)
+    } else if rest.starts_with('/') {
+        report(format!("Transform in ${{{}}} is not supported", inner));
+        format!("{{{{{}}}}}", variable)
+    } else {
+        format!("{{{{{}}}}}", variable)
+    }
+}
+
+// A default that contains further placeholders keeps only their own defaults:
+// `outer ${2:inner} $3` is `outer inner `.
+fn placeholder_text(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(inner) = after.strip_prefix('{') {
+            let mut depth = 1;
+            let end = inner.char_indices().find_map(|(j, c)| {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                (depth == 0).then_some(j)
+            });
+            let Some(end) = end else {
+                out.push_str(&rest[start..]);
+                return out;
+            };
+            if let Some((_, default)) = inner[..end].split_once(':') {
+                out.push_str(&placeholder_text(default));
+            }
+            rest = &inner[end + 1..];
+        } else {
+            let len = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            if len == 0 {
+                out.push('$');
+            }
+            rest = &after[len..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// VS Code snippet files may contain comments and trailing commas.
+fn strip_jsonc(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let chars: Vec<char> = raw.chars().collect();
+    let mut i = 0;
+    let mut in_string = false;
+    // Where in `out` the last comma went, until something other than
+    // whitespace or a comment follows it.
+    let mut comma: Option<usize> = None;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            out.push(c);
+            if c == '\\' {
+                if let Some(&next) = chars.get(i + 1) {
+                    out.push(next);
+                    i += 1;
+                }
+            } else if c == '"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+        match (c, chars.get(i + 1)) {
+            ('"', _) => {
+                in_string = true;
+                comma = None;
+                out.push(c);
+                i += 1;
+            }
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            ('/', Some('*')) => {
+                i += 2;
+                while i + 1 < chars.len() && !(chars[i] == '*' && chars[i + 1] == '/') {
+                    i += 1;
+                }
+                i += 2;
+            }
+            (',', _) => {
+                comma = Some(out.len());
+                out.push(c);
+                i += 1;
+            }
+            ('}' | ']', _) => {
+                if let Some(trailing) = comma.take() {
+                    out.remove(trailing);
+                }
+                out.push(c);
+                i += 1;
+            }
+            _ => {
+                if !c.is_whitespace() {
+                    comma = None;
+                }
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+// Replaces every `open ... close` span for which `replace` returns something;
+// the others are kept as they are.
+fn replace_delimited(
+    text: &str,
+    open: &str,
+    close: &str,
+    mut replace: impl FnMut(&str) -> Option<String>,
+) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(open) {
+        let after = &rest[start + open.len()..];
+        let Some(end) = after.find(close) else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        match replace(&after[..end]) {
+            Some(replacement) => out.push_str(&replacement),
+            None => out.push_str(&rest[start..start + open.len() + end + close.len()]),
+        }
+        rest = &after[end + close.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // A fresh directory under the system temp dir.
+    fn temp_dir() -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("prompt-buddy-import-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn convert(
+        converter: fn(&Path) -> Result<Converted, String>,
+        file_name: &str,
+        contents: &str,
+    ) -> Converted {
+        let dir = temp_dir();
+        let path = dir.join(file_name);
+        fs::write(&path, contents).unwrap();
+        let converted = converter(&path);
+        fs::remove_dir_all(&dir).unwrap();
+        converted.unwrap()
+    }
+
+    fn messages(issues: &[ImportIssue]) -> Vec<String> {
+        issues
+            .iter()
+            .map(|issue| format!("{}: {}", issue.item, issue.message))
+            .collect()
+    }
+
+    #[test]
+    fn converts_espanso_matches() {
+        let converted = convert(
+            espanso,
+            "base.yml",
+            r#"
+matches:
+  - trigger: ":sig"
+    replace: "Best,\n{{name}}$|$"
+    vars:
+      - name: name
+        type: echo
+        params:
+          echo: Alice
+  - triggers: [":a", ":b"]
+    label: Multi
+    replace: "{{clip}}"
+    vars:
+      - name: clip
+        type: clipboard
+  - regex: "x\\d"
+    replace: "y"
+  - trigger: ":form"
+    form: "Hi [[ who ]]"
+  - trigger: ":img"
+    image_path: "/tmp/x.png"
+"#,
+        );
+        let prompts = &converted.prompts;
+        assert_eq!(prompts.len(), 3);
+        assert_eq!(prompts[0].title, "Best,");
+        assert_eq!(prompts[0].content, "Best,\n{{name|Alice}}");
+        assert_eq!(prompts[0].abbreviation.as_deref(), Some(":sig"));
+        assert_eq!(prompts[1].title, "Multi");
+        assert_eq!(prompts[1].content, "{{clipboard}}");
+        assert_eq!(prompts[1].abbreviation.as_deref(), Some(":a"));
+        assert_eq!(prompts[2].content, "Hi {{who}}");
+        assert_eq!(prompts[2].abbreviation, None);
+        assert_eq!(
+            messages(&converted.issues),
+            [
+                ":sig: Cursor position marker $|$ removed",
+                "Multi: Only the first trigger is kept; dropped :b",
+                "match 3: Regex trigger 'x\\d' is not supported; skipped",
+                "Hi {{who}}: Trigger ':form' dropped: text expansion can't ask for who; use the bar instead",
+                ":img: Image matches are not supported; skipped",
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_raycast_snippets() {
+        let converted = convert(
+            raycast,
+            "snippets.json",
+            r#"[
+                {"name": "Greet", "text": "Hi {argument name=\"who\" default=\"you\"}{cursor}, {clipboard} {date} {not a placeholder}", "keyword": "gr"},
+                {"text": "Plain\nsecond line"}
+            ]"#,
+        );
+        let prompts = &converted.prompts;
+        assert_eq!(
+            prompts[0].content,
+            "Hi {{who|you}}, {{clipboard}} {date} {not a placeholder}"
+        );
+        assert_eq!(prompts[0].abbreviation.as_deref(), Some("gr"));
+        assert_eq!(prompts[1].title, "Plain");
+        assert_eq!(prompts[1].abbreviation, None);
+        assert_eq!(
+            messages(&converted.issues),
+            [
+                "Greet: Cursor placeholder removed",
+                "Greet: Placeholder {date} is not supported; kept as text",
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_an_extracted_alfred_collection() {
+        let dir = temp_dir();
+        fs::write(
+            dir.join("info.plist"),
+            "<dict><key>snippetkeywordprefix</key><string>;</string></dict>",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("thanks.json"),
+            r#"{"alfredsnippet": {"snippet": "Thanks, {clipboard}!", "name": "Thx", "keyword": "ty"}}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("broken.json"), "{}").unwrap();
+        let converted = alfred(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        let converted = converted.unwrap();
+
+        assert_eq!(converted.prompts.len(), 1);
+        assert_eq!(converted.prompts[0].title, "Thx");
+        assert_eq!(converted.prompts[0].content, "Thanks, {{clipboard}}!");
+        assert_eq!(converted.prompts[0].abbreviation.as_deref(), Some(";ty"));
+        assert_eq!(converted.issues.len(), 1);
+        assert_eq!(converted.issues[0].item, "broken.json");
+    }
+
+    #[test]
+    fn converts_vscode_snippets() {
+        let converted = convert(
+            vscode,
+            "js.code-snippets",
+            r#"{
+                // A comment
+                "Log": {
+                    "prefix": ["log", "cl"],
+                    "body": ["console.log('${1:msg}', $2);", "$0"],
+                    "scope": "javascript, typescript",
+                },
+                /* Another comment */
+                "No body": { "prefix": "x" },
+            }"#,
+        );
+        assert_eq!(converted.prompts.len(), 1);
+        let prompt = &converted.prompts[0];
+        assert_eq!(prompt.title, "Log");
+        assert_eq!(prompt.content, "console.log('{{arg1|msg}}', {{arg2}});\n");
+        assert_eq!(prompt.abbreviation, None);
+        assert_eq!(prompt.tags, ["javascript", "typescript"]);
+        assert_eq!(
+            messages(&converted.issues),
+            [
+                "Log: Only the first prefix is kept; dropped cl",
+                "Log: Final cursor position $0 removed",
+                "Log: Trigger 'log' dropped: text expansion can't ask for arg2; use the bar instead",
+                "No body: Snippet has no body; skipped",
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_tabstops() {
+        let mut issues = Vec::new();
+        let mut tabstops = |body: &str| convert_tabstops(body, "s", &mut issues);
+        assert_eq!(tabstops("$1 and ${2}"), "{{arg1}} and {{arg2}}");
+        assert_eq!(tabstops("${1|a,b,c|}"), "{{arg1|a}}");
+        assert_eq!(
+            tabstops("${2:outer ${3:inner} $4}"),
+            "{{arg2|outer inner }}"
+        );
+        assert_eq!(tabstops("${1/(.*)/${1:/upcase}/}"), "{{arg1}}");
+        assert_eq!(tabstops("$CLIPBOARD"), "{{clipboard}}");
+        assert_eq!(tabstops("$TM_FILENAME"), "{{tm_filename}}");
+        assert_eq!(tabstops("\\$1 costs $ or ${"), "$1 costs $ or ${");
+        assert_eq!(issues.len(), 3);
+    }
+
+    #[test]
+    fn strips_jsonc() {
+        let value: Value = serde_json::from_str(&strip_jsonc(
+            r#"{"a": "x // not a comment, }", /* c */ "b": [1, 2,], // end
+            }"#,
+        ))
+        .unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"a": "x // not a comment, }", "b": [1, 2]})
+        );
+    }
+
+    #[test]
+    fn titles_from_the_first_line() {
+        assert_eq!(title_from("\n  First line \nsecond", "x"), "First line");
+        assert_eq!(title_from("   ", "fallback"), "fallback");
+        assert_eq!(
+            title_from(&"é".repeat(50), "x"),
+            format!("{}…", "é".repeat(40))
+        );
+    }
+}
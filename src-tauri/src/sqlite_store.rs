@@ -0,0 +1,443 @@
+// SQLite storage backend. Prompts are rows in `prompts.db` (app data dir), so
+// an edit only touches the rows that changed, and an FTS5 index over title,
+// content and tags backs `search_prompts`.
+//
+// The index is an external-content FTS5 table kept in sync by triggers, so
+// nothing outside this file needs to know it exists. The schema version lives in
+// `PRAGMA user_version`; see `MIGRATIONS`.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::library::{PromptStore, SearchHit};
+use crate::prompts::Prompt;
+
+// Tags are stored one per line: they may contain spaces but never newlines.
+const TAG_SEPARATOR: &str = "\n";
+
+const SEARCH_LIMIT: usize = 50;
+
+// `MIGRATIONS[n]` upgrades schema version n to n + 1. Databases created before
+// versioning was introduced are at version 0 but already have the v1 tables,
+// hence the `IF NOT EXISTS`.
+const MIGRATIONS: &[&str] = &[SCHEMA_V1, SCHEMA_V2];
+
+const SCHEMA_V1: &str = "
+CREATE TABLE IF NOT EXISTS prompts (
+    row INTEGER PRIMARY KEY,
+    id TEXT NOT NULL UNIQUE,
+    position INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    color TEXT NOT NULL,
+    abbreviation TEXT,
+    tags TEXT NOT NULL DEFAULT ''
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS prompts_fts USING fts5(
+    title, content, tags,
+    content = 'prompts', content_rowid = 'row',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER IF NOT EXISTS prompts_fts_insert AFTER INSERT ON prompts BEGIN
+    INSERT INTO prompts_fts (rowid, title, content, tags)
+    VALUES (new.row, new.title, new.content, new.tags);
+END;
+
+CREATE TRIGGER IF NOT EXISTS prompts_fts_delete AFTER DELETE ON prompts BEGIN
+    INSERT INTO prompts_fts (prompts_fts, rowid, title, content, tags)
+    VALUES ('delete', old.row, old.title, old.content, old.tags);
+END;
+
+CREATE TRIGGER IF NOT EXISTS prompts_fts_update AFTER UPDATE ON prompts BEGIN
+    INSERT INTO prompts_fts (prompts_fts, rowid, title, content, tags)
+    VALUES ('delete', old.row, old.title, old.content, old.tags);
+    INSERT INTO prompts_fts (rowid, title, content, tags)
+    VALUES (new.row, new.title, new.content, new.tags);
+END;
+
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+";
+
+// v2: folders.
+const SCHEMA_V2: &str = "
+ALTER TABLE prompts ADD COLUMN folder TEXT;
+";
+
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let conn = Connection::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        apply_migrations(&conn, path)?;
+
+        let check: String = conn
+            .query_row("PRAGMA quick_check", [], |row| row.get(0))
+            .map_err(|e| format!("Failed to check {}: {}", path.display(), e))?;
+        if check != "ok" {
+            return Err(format!("{} is corrupted: {}", path.display(), check));
+        }
+        Ok(Self { conn })
+    }
+
+    // Whether the one-time import from `prompts.json` already happened.
+    pub fn json_imported(&self) -> Result<bool, String> {
+        self.conn
+            .query_row("SELECT 1 FROM meta WHERE key = 'json_imported'", [], |_| Ok(()))
+            .optional()
+            .map(|row| row.is_some())
+            .map_err(|e| format!("Failed to read database metadata: {}", e))
+    }
+
+    pub fn mark_json_imported(&self) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES ('json_imported', datetime('now'))",
+                [],
+            )
+            .map(|_| ())
+            .map_err(|e| format!("Failed to write database metadata: {}", e))
+    }
+}
+
+impl PromptStore for SqliteStore {
+    fn load(&self) -> Result<Vec<Prompt>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, title, content, color, abbreviation, tags, folder
+                 FROM prompts ORDER BY position",
+            )
+            .map_err(|e| format!("Failed to query prompts: {}", e))?;
+        let rows = stmt
+            .query_map([], prompt_from_row)
+            .map_err(|e| format!("Failed to query prompts: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read prompts: {}", e))
+    }
+
+    fn save(&mut self, prompts: &[Prompt]) -> Result<(), String> {
+        let existing: HashMap<String, (usize, Prompt)> = self
+            .load()?
+            .into_iter()
+            .enumerate()
+            .map(|(position, prompt)| (prompt.id.clone(), (position, prompt)))
+            .collect();
+
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        for id in existing.keys() {
+            if !prompts.iter().any(|p| &p.id == id) {
+                tx.execute("DELETE FROM prompts WHERE id = ?1", params![id])
+                    .map_err(|e| format!("Failed to delete prompt {}: {}", id, e))?;
+            }
+        }
+        for (position, prompt) in prompts.iter().enumerate() {
+            let unchanged = existing
+                .get(&prompt.id)
+                .map(|(old_position, old)| *old_position == position && old == prompt)
+                .unwrap_or(false);
+            if !unchanged {
+                upsert(&tx, prompt, position)?;
+            }
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to save prompts: {}", e))
+    }
+
+    fn save_prompt(&mut self, prompts: &[Prompt], index: usize) -> Result<(), String> {
+        let prompt = prompts
+            .get(index)
+            .ok_or_else(|| format!("No prompt at position {}", index))?;
+        upsert(&self.conn, prompt, index)
+    }
+
+    fn search(&self, query: &str) -> Result<Vec<SearchHit>, String> {
+        let Some(fts_query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+
+        // Matches in the title weigh most, then tags, then content.
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT p.id, p.title, p.content, p.color, p.abbreviation, p.tags, p.folder,
+                        p.position,
+                        snippet(prompts_fts, -1, '<mark>', '</mark>', '…', 16),
+                        bm25(prompts_fts, 10.0, 1.0, 5.0) AS score
+                 FROM prompts_fts JOIN prompts p ON p.row = prompts_fts.rowid
+                 WHERE prompts_fts MATCH ?1
+                 ORDER BY score
+                 LIMIT ?2",
+            )
+            .map_err(|e| format!("Failed to search prompts: {}", e))?;
+        let rows = stmt
+            .query_map(params![fts_query, SEARCH_LIMIT as i64], |row| {
+                let position: i64 = row.get(7)?;
+                let score: f64 = row.get(9)?;
+                Ok(SearchHit {
+                    prompt: prompt_from_row(row)?,
+                    index: position as usize,
+                    snippet: row.get(8)?,
+                    // bm25() is lower-is-better; expose higher-is-better.
+                    score: -score,
+                })
+            })
+            .map_err(|e| format!("Failed to search prompts: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read search results: {}", e))
+    }
+}
+
+// Brings an existing database up to the current schema, backing it up first.
+// Missing databases are left alone; `SqliteStore::open` creates them, and
+// unreadable ones are recovered by the library when it opens them.
+pub fn migrate(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let conn = Connection::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let Ok(version) = schema_version(&conn) else {
+        println!("⚠️  {} is unreadable, not migrating it", path.display());
+        return Ok(());
+    };
+    if version < MIGRATIONS.len() {
+        let backup = crate::migrations::backup(path, version as u64)?;
+        println!(
+            "🔄 Migrating prompts.db v{} → v{} (backup: {})",
+            version,
+            MIGRATIONS.len(),
+            backup.display()
+        );
+    }
+    apply_migrations(&conn, path)
+}
+
+fn schema_version(conn: &Connection) -> Result<usize, String> {
+    conn.pragma_query_value(None, "user_version", |row| row.get::<_, i64>(0))
+        .map(|version| version as usize)
+        .map_err(|e| format!("Failed to read database schema version: {}", e))
+}
+
+fn apply_migrations(conn: &Connection, path: &Path) -> Result<(), String> {
+    let version = schema_version(conn)?;
+    if version > MIGRATIONS.len() {
+        return Err(format!(
+            "{} uses schema v{}, but this version of Prompt Buddy only understands up to v{}. \
+             Update Prompt Buddy, or restore a backup of the file.",
+            path.display(),
+            version,
+            MIGRATIONS.len()
+        ));
+    }
+
+    for (from, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        conn.execute_batch(&format!(
+            "BEGIN; {} PRAGMA user_version = {}; COMMIT;",
+            sql,
+            from + 1
+        ))
+        .map_err(|e| format!("Failed to migrate prompt database to v{}: {}", from + 1, e))?;
+    }
+    Ok(())
+}
+
+fn prompt_from_row(row: &Row) -> rusqlite::Result<Prompt> {
+    let tags: String = row.get(5)?;
+    Ok(Prompt {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        content: row.get(2)?,
+        color: row.get(3)?,
+        abbreviation: row.get(4)?,
+        tags: tags
+            .split(TAG_SEPARATOR)
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| tag.to_string())
+            .collect(),
+        folder: row.get(6)?,
+    })
+}
+
+fn upsert(conn: &Connection, prompt: &Prompt, position: usize) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO prompts (id, position, title, content, color, abbreviation, tags, folder)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT (id) DO UPDATE SET
+             position = excluded.position,
+             title = excluded.title,
+             content = excluded.content,
+             color = excluded.color,
+             abbreviation = excluded.abbreviation,
+             tags = excluded.tags,
+             folder = excluded.folder",
+        params![
+            prompt.id,
+            position as i64,
+            prompt.title,
+            prompt.content,
+            prompt.color,
+            prompt.abbreviation,
+            prompt.tags.join(TAG_SEPARATOR),
+            prompt.folder,
+        ],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to save prompt {}: {}", prompt.id, e))
+}
+
+// Turns free text into an FTS5 query: every word must match, as a prefix, so
+// results narrow down while typing. Words are quoted so user input can never
+// be parsed as FTS5 syntax.
+fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_memory() -> SqliteStore {
+        let conn = Connection::open_in_memory().unwrap();
+        apply_migrations(&conn, Path::new(":memory:")).unwrap();
+        SqliteStore { conn }
+    }
+
+    fn prompt(id: &str, title: &str, content: &str, tags: &[&str]) -> Prompt {
+        Prompt {
+            id: id.to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            color: crate::prompts::DEFAULT_COLOR.to_string(),
+            abbreviation: None,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            folder: None,
+        }
+    }
+
+    fn library() -> SqliteStore {
+        let mut store = in_memory();
+        store
+            .save(&[
+                prompt("tests", "Write tests", "Cover the code paths.", &[]),
+                prompt(
+                    "bugs",
+                    "Find bugs",
+                    "Look near the edge cases of this code.",
+                    &["code"],
+                ),
+                prompt("review", "Code review", "Review the diff.", &[]),
+                prompt("quote", "Say \"hi\"", "Greet them -- politely.", &["a-b"]),
+            ])
+            .unwrap();
+        store
+    }
+
+    fn ids(hits: &[SearchHit]) -> Vec<&str> {
+        hits.iter().map(|hit| hit.prompt.id.as_str()).collect()
+    }
+
+    #[test]
+    fn quotes_every_word_as_a_prefix() {
+        assert_eq!(fts_query("code rev").as_deref(), Some("\"code\"* \"rev\"*"));
+        assert_eq!(
+            fts_query("say \"hi\"").as_deref(),
+            Some("\"say\"* \"\"\"hi\"\"\"*")
+        );
+        assert_eq!(fts_query("a* -b").as_deref(), Some("\"a*\"* \"-b\"*"));
+        assert_eq!(
+            fts_query("NEAR(a b) OR c").as_deref(),
+            Some("\"NEAR(a\"* \"b)\"* \"OR\"* \"c\"*")
+        );
+        assert_eq!(fts_query(""), None);
+        assert_eq!(fts_query(" \t\n"), None);
+    }
+
+    #[test]
+    fn fts_syntax_is_searched_for_literally() {
+        let store = library();
+        for query in [
+            "\"",
+            "\"hi",
+            "say \"hi\"",
+            "*",
+            "code*",
+            "-",
+            "-code",
+            "a-b",
+            "--",
+            "NEAR",
+            "NEAR(code tests)",
+            "code OR tests",
+            "code AND",
+            "NOT code",
+            "title:code",
+            "^code",
+            "(code)",
+            "{title}: code",
+            "",
+            "   ",
+        ] {
+            if let Err(e) = store.search(query) {
+                panic!("searching for {:?} failed: {}", query, e);
+            }
+        }
+
+        assert!(store.search("").unwrap().is_empty());
+        // Operators are words that must appear, not operators.
+        assert!(store.search("code OR tests").unwrap().is_empty());
+        assert!(store.search("NOT code").unwrap().is_empty());
+        assert_eq!(ids(&store.search("near").unwrap()), ["bugs"]);
+        assert_eq!(
+            ids(&store.search("NEAR(code tests)").unwrap()),
+            Vec::<&str>::new()
+        );
+        assert_eq!(ids(&store.search("say \"hi\"").unwrap()), ["quote"]);
+        assert_eq!(ids(&store.search("-- politely").unwrap()), ["quote"]);
+    }
+
+    #[test]
+    fn ranks_title_matches_first_and_highlights_them() {
+        let store = library();
+        let hits = store.search("code").unwrap();
+        assert_eq!(ids(&hits), ["review", "bugs", "tests"]);
+        assert!(hits.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        assert_eq!(hits[0].index, 2);
+        assert_eq!(hits[0].snippet, "<mark>Code</mark> review");
+        assert!(
+            hits[2].snippet.contains("<mark>code</mark>"),
+            "{}",
+            hits[2].snippet
+        );
+
+        // Every word must match, each as a prefix.
+        assert_eq!(ids(&store.search("rev co").unwrap()), ["review"]);
+        assert!(store.search("code xyz").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+// Tag expressions used to filter prompts, e.g. `review`, `rust go` (both),
+// `rust or go`, `!draft` and `(rust or go) and not draft`.
+//
+//     expr  := and ("or" | "||" and)*
+//     and   := unary (["and" | "&&"] unary)*
+//     unary := ("not" | "!" | "-") unary | "(" expr ")" | tag
+//
+// Keywords and tags are case-insensitive, a leading `#` on a tag is ignored,
+// and an empty expression matches every prompt.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TagQuery {
+    All,
+    Tag(String),
+    Not(Box<TagQuery>),
+    And(Box<TagQuery>, Box<TagQuery>),
+    Or(Box<TagQuery>, Box<TagQuery>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Tag(String),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl TagQuery {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let tokens = tokenize(expression);
+        if tokens.is_empty() {
+            return Ok(TagQuery::All);
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(query),
+            Some(token) => Err(format!(
+                "Invalid tag filter '{}': unexpected {}",
+                expression,
+                describe(token)
+            )),
+        }
+    }
+
+    pub fn matches(&self, tags: &[String]) -> bool {
+        match self {
+            TagQuery::All => true,
+            TagQuery::Tag(tag) => tags.iter().any(|t| t.to_lowercase() == *tag),
+            TagQuery::Not(inner) => !inner.matches(tags),
+            TagQuery::And(a, b) => a.matches(tags) && b.matches(tags),
+            TagQuery::Or(a, b) => a.matches(tags) || b.matches(tags),
+        }
+    }
+}
+
+fn tokenize(expression: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let flush = |word: &mut String, tokens: &mut Vec<Token>| {
+        if word.is_empty() {
+            return;
+        }
+        let token = match word.to_lowercase().as_str() {
+            "and" => Token::And,
+            "or" => Token::Or,
+            "not" => Token::Not,
+            lower => Token::Tag(lower.trim_start_matches('#').to_string()),
+        };
+        tokens.push(token);
+        word.clear();
+    };
+
+    let mut chars = expression.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => flush(&mut word, &mut tokens),
+            '(' | ')' => {
+                flush(&mut word, &mut tokens);
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '!' | '-' if word.is_empty() => tokens.push(Token::Not),
+            '&' | '|' if word.is_empty() => {
+                if chars.peek() == Some(&c) {
+                    chars.next();
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+            }
+            c => word.push(c),
+        }
+    }
+    flush(&mut word, &mut tokens);
+    tokens
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Tag(tag) => format!("'{}'", tag),
+        Token::And => "'and'".to_string(),
+        Token::Or => "'or'".to_string(),
+        Token::Not => "'not'".to_string(),
+        Token::Open => "'('".to_string(),
+        Token::Close => "')'".to_string(),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn or(&mut self) -> Result<TagQuery, String> {
+        let mut query = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            query = TagQuery::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<TagQuery, String> {
+        let mut query = self.unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.pos += 1,
+                // Juxtaposition means "and".
+                Some(Token::Tag(_) | Token::Not | Token::Open) => {}
+                _ => return Ok(query),
+            }
+            query = TagQuery::And(Box::new(query), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<TagQuery, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| "Invalid tag filter: incomplete expression".to_string())?;
+        self.pos += 1;
+        match token {
+            Token::Not => Ok(TagQuery::Not(Box::new(self.unary()?))),
+            Token::Open => {
+                let query = self.or()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err("Invalid tag filter: missing ')'".to_string());
+                }
+                self.pos += 1;
+                Ok(query)
+            }
+            Token::Tag(tag) => Ok(TagQuery::Tag(tag)),
+            other => Err(format!("Invalid tag filter: unexpected {}", describe(&other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(name: &str) -> Box<TagQuery> {
+        Box::new(TagQuery::Tag(name.to_string()))
+    }
+
+    fn tags(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn empty_expression_matches_everything() {
+        assert_eq!(TagQuery::parse("").unwrap(), TagQuery::All);
+        assert_eq!(TagQuery::parse("   ").unwrap(), TagQuery::All);
+        assert!(TagQuery::All.matches(&[]));
+    }
+
+    #[test]
+    fn tags_are_lowercased_without_hash() {
+        assert_eq!(TagQuery::parse("#Rust").unwrap(), *tag("rust"));
+        assert_eq!(TagQuery::parse("code-review").unwrap(), *tag("code-review"));
+        // Operators only count at the start of a word.
+        assert_eq!(TagQuery::parse("r&d").unwrap(), *tag("r&d"));
+    }
+
+    #[test]
+    fn juxtaposition_and_keywords_mean_and() {
+        let expected = TagQuery::And(tag("rust"), tag("go"));
+        for expression in ["rust go", "rust and go", "rust AND go", "rust && go"] {
+            assert_eq!(
+                TagQuery::parse(expression).unwrap(),
+                expected,
+                "{}",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn or_binds_looser_than_and() {
+        assert_eq!(
+            TagQuery::parse("a b or c").unwrap(),
+            TagQuery::Or(Box::new(TagQuery::And(tag("a"), tag("b"))), tag("c"))
+        );
+        assert_eq!(
+            TagQuery::parse("a || b").unwrap(),
+            TagQuery::Or(tag("a"), tag("b"))
+        );
+    }
+
+    #[test]
+    fn negation_and_parentheses() {
+        let expected = TagQuery::And(
+            Box::new(TagQuery::Or(tag("rust"), tag("go"))),
+            Box::new(TagQuery::Not(tag("draft"))),
+        );
+        for expression in [
+            "(rust or go) and not draft",
+            "(rust or go) !draft",
+            "(rust || go) -draft",
+        ] {
+            assert_eq!(
+                TagQuery::parse(expression).unwrap(),
+                expected,
+                "{}",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        for expression in ["(rust", "rust)", "rust or", "and", "not", "()"] {
+            assert!(TagQuery::parse(expression).is_err(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn matches_case_insensitively() {
+        let query = TagQuery::parse("(rust or go) and not draft").unwrap();
+        assert!(query.matches(&tags(&["Rust"])));
+        assert!(query.matches(&tags(&["go", "review"])));
+        assert!(!query.matches(&tags(&["rust", "Draft"])));
+        assert!(!query.matches(&tags(&["python"])));
+        assert!(!query.matches(&[]));
+    }
+}
@@ -0,0 +1,272 @@
+// Prompt variables: `{{name}}` is replaced with a value supplied at injection
+// time, `{{name|default}}` falls back to `default` when none is given. Names
+// are letters, digits, `_`, `-` and `.`; spaces around the name and the default
+// are ignored. `{{sh: command}}` is replaced with the output of `command` (see
+// `shell_vars`). Anything else in double braces is left as it is.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Variable {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+// A `{{...}}` placeholder found at `start..end` of the content.
+struct Placeholder {
+    start: usize,
+    end: usize,
+    kind: Kind,
+}
+
+enum Kind {
+    Variable(Variable),
+    Command(String),
+}
+
+fn placeholders(content: &str) -> Vec<Placeholder> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(open) = content[offset..].find("{{") {
+        let start = offset + open;
+        let Some(close) = content[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + close + 2;
+        let inner = &content[start + 2..end - 2];
+        // Commands have pipes of their own, so they never have a default.
+        if let Some(command) = inner.trim_start().strip_prefix("sh:") {
+            if !command.trim().is_empty() {
+                found.push(Placeholder {
+                    start,
+                    end,
+                    kind: Kind::Command(command.trim().to_string()),
+                });
+                offset = end;
+                continue;
+            }
+        }
+        let (name, default) = match inner.split_once('|') {
+            Some((name, default)) => (name.trim(), Some(default.trim().to_string())),
+            None => (inner.trim(), None),
+        };
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if valid {
+            found.push(Placeholder {
+                start,
+                end,
+                kind: Kind::Variable(Variable {
+                    name: name.to_string(),
+                    default,
+                }),
+            });
+            offset = end;
+        } else {
+            offset = start + 2;
+        }
+    }
+    found
+}
+
+// The variables used in `content`, each once, in order of appearance. The
+// first default given for a name wins.
+pub fn variables(content: &str) -> Vec<Variable> {
+    let mut variables: Vec<Variable> = Vec::new();
+    for placeholder in placeholders(content) {
+        let Kind::Variable(variable) = placeholder.kind else {
+            continue;
+        };
+        match variables.iter_mut().find(|v| v.name == variable.name) {
+            Some(existing) => {
+                if existing.default.is_none() {
+                    existing.default = variable.default;
+                }
+            }
+            None => variables.push(variable),
+        }
+    }
+    variables
+}
+
+// The `{{sh: ...}}` commands in `content`, in order of appearance.
+pub fn commands(content: &str) -> Vec<String> {
+    placeholders(content)
+        .into_iter()
+        .filter_map(|placeholder| match placeholder.kind {
+            Kind::Command(command) => Some(command),
+            Kind::Variable(_) => None,
+        })
+        .collect()
+}
+
+// Fills in every variable of `content`. Fails, naming them, if variables
+// without a default have no value, and on any command.
+pub fn render(content: &str, values: &HashMap<String, String>) -> Result<String, String> {
+    render_with(content, values, |command| {
+        Err(format!("Can't run “{}” here", command))
+    })
+}
+
+// Like `render`, with commands replaced by what `run` makes of them. Commands
+// only run once every variable has a value.
+pub fn render_with(
+    content: &str,
+    values: &HashMap<String, String>,
+    mut run: impl FnMut(&str) -> Result<String, String>,
+) -> Result<String, String> {
+    let variables = variables(content);
+    let missing: Vec<&str> = variables
+        .iter()
+        .filter(|v| v.default.is_none() && !values.contains_key(&v.name))
+        .map(|v| v.name.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing value for {}", missing.join(", ")));
+    }
+
+    let mut rendered = String::with_capacity(content.len());
+    let mut last = 0;
+    for placeholder in placeholders(content) {
+        rendered.push_str(&content[last..placeholder.start]);
+        match &placeholder.kind {
+            Kind::Variable(variable) => {
+                let default = variables
+                    .iter()
+                    .find(|v| v.name == variable.name)
+                    .and_then(|v| v.default.as_ref());
+                let value = values
+                    .get(&variable.name)
+                    .or(default)
+                    .map(String::as_str)
+                    .unwrap_or_default();
+                rendered.push_str(value);
+            }
+            Kind::Command(command) => rendered.push_str(&run(command)?),
+        }
+        last = placeholder.end;
+    }
+    rendered.push_str(&content[last..]);
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn variable(name: &str, default: Option<&str>) -> Variable {
+        Variable {
+            name: name.to_string(),
+            default: default.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn finds_variables_once_in_order() {
+        assert_eq!(
+            variables("{{ lang | Rust }} {{focus}} {{lang}} {{user.name}}"),
+            vec![
+                variable("lang", Some("Rust")),
+                variable("focus", None),
+                variable("user.name", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn first_default_wins() {
+        assert_eq!(
+            variables("{{lang}} {{lang|Go}} {{lang|Rust}}"),
+            vec![variable("lang", Some("Go"))]
+        );
+        assert_eq!(
+            render("{{lang}} {{lang|Go}} {{lang|Rust}}", &values(&[])).unwrap(),
+            "Go Go Go"
+        );
+    }
+
+    #[test]
+    fn leaves_other_braces_alone() {
+        let content = "{{}} {{ }} {{two words}} {{a,b}} {{unclosed";
+        assert!(placeholders(content).is_empty());
+        assert_eq!(render(content, &values(&[])).unwrap(), content);
+    }
+
+    #[test]
+    fn renders_values_and_defaults() {
+        assert_eq!(
+            render(
+                "Review this {{lang|Rust}} code for {{focus}}.",
+                &values(&[("focus", "safety")])
+            )
+            .unwrap(),
+            "Review this Rust code for safety."
+        );
+        assert_eq!(
+            render("{{lang|Rust}}", &values(&[("lang", "Go")])).unwrap(),
+            "Go"
+        );
+        assert_eq!(render("{{note|}}!", &values(&[])).unwrap(), "!");
+    }
+
+    #[test]
+    fn names_missing_variables() {
+        let err = render("{{a}} {{b|x}} {{c}}", &values(&[])).unwrap_err();
+        assert_eq!(err, "Missing value for a, c");
+    }
+
+    #[test]
+    fn commands_are_separate_from_variables() {
+        let content = "Branch: {{sh: git branch --show-current}} {{ sh: echo a | tr a b }}";
+        assert_eq!(
+            commands(content),
+            vec!["git branch --show-current", "echo a | tr a b"]
+        );
+        assert!(variables(content).is_empty());
+        // Without a command it's just an odd name.
+        assert!(commands("{{sh:}}").is_empty());
+    }
+
+    #[test]
+    fn render_refuses_commands() {
+        assert!(render("{{sh: date}}", &values(&[])).is_err());
+    }
+
+    #[test]
+    fn render_with_replaces_commands() {
+        let mut ran = Vec::new();
+        let rendered = render_with(
+            "{{who}} on {{sh: hostname}}",
+            &values(&[("who", "me")]),
+            |command| {
+                ran.push(command.to_string());
+                Ok("box".to_string())
+            },
+        )
+        .unwrap();
+        assert_eq!(rendered, "me on box");
+        assert_eq!(ran, vec!["hostname"]);
+    }
+
+    #[test]
+    fn render_with_runs_nothing_while_variables_are_missing() {
+        let mut ran = false;
+        let result = render_with("{{who}} {{sh: hostname}}", &values(&[]), |_| {
+            ran = true;
+            Ok(String::new())
+        });
+        assert!(result.is_err());
+        assert!(!ran);
+    }
+}
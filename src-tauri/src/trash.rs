@@ -0,0 +1,90 @@
+// Deleted prompts. Whenever a prompt disappears from the library, however that
+// happened, it lands here (`trash.json` in the app data dir) with its former
+// position, and leaves again when it comes back. Items older than
+// `trashRetentionDays` (settings.json, default 30) are purged for good.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backups;
+use crate::prompts::Prompt;
+
+pub const DEFAULT_RETENTION_DAYS: u64 = 30;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedPrompt {
+    pub prompt: Prompt,
+    // Unix seconds.
+    pub deleted_at: u64,
+    // Position in the library when it was deleted.
+    pub index: usize,
+}
+
+pub fn load(path: &Path) -> Result<Vec<TrashedPrompt>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("{} is corrupted: {}", path.display(), e))
+}
+
+fn save(path: &Path, items: &[TrashedPrompt]) -> Result<(), String> {
+    let contents = serde_json::to_vec_pretty(items)
+        .map_err(|e| format!("Failed to serialize trash: {}", e))?;
+    backups::write_atomic(path, &contents)
+}
+
+// Moves prompts that are in `before` but not in `after` into the trash, and
+// takes those in `after` out of it.
+pub fn sync(path: &Path, before: &[Prompt], after: &[Prompt]) -> Result<(), String> {
+    let mut items = load(path)?;
+    let len = items.len();
+    items.retain(|item| !after.iter().any(|p| p.id == item.prompt.id));
+    let mut changed = items.len() != len;
+
+    let now = backups::unix_now();
+    for (index, prompt) in before.iter().enumerate() {
+        if !after.iter().any(|p| p.id == prompt.id) {
+            items.retain(|item| item.prompt.id != prompt.id);
+            items.push(TrashedPrompt {
+                prompt: prompt.clone(),
+                deleted_at: now,
+                index,
+            });
+            changed = true;
+        }
+    }
+
+    if changed {
+        save(path, &items)?;
+    }
+    Ok(())
+}
+
+// Permanently deletes one item, or everything when `id` is `None`.
+pub fn purge(path: &Path, id: Option<&str>) -> Result<(), String> {
+    let mut items = load(path)?;
+    match id {
+        Some(id) => items.retain(|item| item.prompt.id != id),
+        None => items.clear(),
+    }
+    save(path, &items)
+}
+
+pub fn purge_expired(path: &Path, retention_days: u64) -> Result<(), String> {
+    let mut items = load(path)?;
+    let cutoff = backups::unix_now().saturating_sub(retention_days * SECONDS_PER_DAY);
+    let len = items.len();
+    items.retain(|item| item.deleted_at >= cutoff);
+    if items.len() != len {
+        println!("🗑️  Purged {} prompts from the trash", len - items.len());
+        save(path, &items)?;
+    }
+    Ok(())
+}
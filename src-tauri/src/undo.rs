@@ -0,0 +1,85 @@
+// Library-level undo/redo. Every change made through `library` is recorded as
+// the whole library before and after it, which keeps undo independent of what
+// kind of change it was (create, edit, delete, reorder, ...). The stacks live
+// in memory only and are capped at `MAX_STEPS`.
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::prompts::Prompt;
+
+const MAX_STEPS: usize = 50;
+
+#[derive(Clone, Debug)]
+pub struct Mutation {
+    // Shown to the user, e.g. `Delete “Refactor”`.
+    pub label: String,
+    pub before: Vec<Prompt>,
+    pub after: Vec<Prompt>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoState {
+    can_undo: bool,
+    can_redo: bool,
+    undo_label: Option<String>,
+    redo_label: Option<String>,
+}
+
+#[derive(Default)]
+struct Stacks {
+    undo: Vec<Mutation>,
+    redo: Vec<Mutation>,
+}
+
+static STACKS: Lazy<Mutex<Stacks>> = Lazy::new(|| Mutex::new(Stacks::default()));
+
+// Records a new change; anything that could have been redone is gone.
+pub fn record(mutation: Mutation) {
+    let mut stacks = STACKS.lock().unwrap();
+    stacks.redo.clear();
+    push(&mut stacks.undo, mutation);
+}
+
+pub fn pop_undo() -> Option<Mutation> {
+    STACKS.lock().unwrap().undo.pop()
+}
+
+pub fn pop_redo() -> Option<Mutation> {
+    STACKS.lock().unwrap().redo.pop()
+}
+
+pub fn push_undo(mutation: Mutation) {
+    push(&mut STACKS.lock().unwrap().undo, mutation);
+}
+
+pub fn push_redo(mutation: Mutation) {
+    push(&mut STACKS.lock().unwrap().redo, mutation);
+}
+
+// Forgets everything, e.g. when the library was replaced by something the
+// stacks know nothing about.
+pub fn clear() {
+    let mut stacks = STACKS.lock().unwrap();
+    stacks.undo.clear();
+    stacks.redo.clear();
+}
+
+pub fn state() -> UndoState {
+    let stacks = STACKS.lock().unwrap();
+    UndoState {
+        can_undo: !stacks.undo.is_empty(),
+        can_redo: !stacks.redo.is_empty(),
+        undo_label: stacks.undo.last().map(|m| m.label.clone()),
+        redo_label: stacks.redo.last().map(|m| m.label.clone()),
+    }
+}
+
+fn push(stack: &mut Vec<Mutation>, mutation: Mutation) {
+    stack.push(mutation);
+    let excess = stack.len().saturating_sub(MAX_STEPS);
+    stack.drain(..excess);
+}
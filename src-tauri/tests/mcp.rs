@@ -0,0 +1,266 @@
+// Drives `prompt-buddy mcp` (see `mcp`) as an MCP client would, with its
+// data dir in a fresh temporary directory so neither the real library nor a
+// running app is touched: the handshake, then the prompts and tools.
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use serde_json::{json, Value};
+
+// A temporary data dir, removed again when the test ends, pass or fail.
+struct ScratchHome(PathBuf);
+
+impl Drop for ScratchHome {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+struct Server {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl Server {
+    fn start(home: &Path) -> Result<Self, String> {
+        std::fs::create_dir_all(home)
+            .map_err(|e| format!("Failed to create {}: {}", home.display(), e))?;
+        // Every platform's data dir ends up below `home`.
+        let mut child = Command::new(env!("CARGO_BIN_EXE_prompt-buddy"))
+            .arg("mcp")
+            .env("HOME", home)
+            .env("XDG_DATA_HOME", home)
+            .env("APPDATA", home)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start prompt-buddy: {}", e))?;
+        let stdin = child.stdin.take().ok_or("No stdin")?;
+        let stdout = BufReader::new(child.stdout.take().ok_or("No stdout")?);
+        Ok(Server {
+            child,
+            stdin,
+            stdout,
+            next_id: 1,
+        })
+    }
+
+    fn send(&mut self, message: &Value) -> Result<(), String> {
+        writeln!(self.stdin, "{}", message).map_err(|e| format!("Failed to send: {}", e))
+    }
+
+    // The result of `method`, or the error code and message.
+    fn call(
+        &mut self,
+        method: &str,
+        params: Value,
+    ) -> Result<Result<Value, (i64, String)>, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+        let mut line = String::new();
+        match self.stdout.read_line(&mut line) {
+            Ok(0) => return Err("The server exited".to_string()),
+            Ok(_) => {}
+            Err(e) => return Err(format!("No answer: {}", e)),
+        }
+        let response: Value =
+            serde_json::from_str(&line).map_err(|e| format!("Invalid JSON: {}", e))?;
+        if response["id"] != json!(id) {
+            return Err(format!("Answer to {} has id {}", method, response["id"]));
+        }
+        Ok(match response.get("error") {
+            Some(error) => Err((
+                error["code"].as_i64().unwrap_or_default(),
+                error["message"].as_str().unwrap_or_default().to_string(),
+            )),
+            None => Ok(response.get("result").cloned().unwrap_or(Value::Null)),
+        })
+    }
+
+    fn tool(&mut self, name: &str, arguments: Value) -> Result<(bool, String), String> {
+        let result = self
+            .call(
+                "tools/call",
+                json!({ "name": name, "arguments": arguments }),
+            )?
+            .map_err(|(_, message)| message)?;
+        let text = result["content"][0]["text"].as_str().unwrap_or_default();
+        Ok((result["isError"] != json!(true), text.to_string()))
+    }
+}
+
+#[test]
+fn mcp_session() {
+    let home = ScratchHome(
+        std::env::temp_dir().join(format!("prompt-buddy-mcp-{}", uuid::Uuid::new_v4())),
+    );
+    let mut server = Server::start(&home.0).unwrap();
+
+    let result = server
+        .call(
+            "initialize",
+            json!({
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": { "name": "mcp-test", "version": "0" },
+            }),
+        )
+        .unwrap();
+    check(
+        "initialize",
+        result.as_ref().is_ok_and(|r| {
+            r["protocolVersion"] == "2025-06-18"
+                && r["capabilities"]["prompts"].is_object()
+                && r["capabilities"]["tools"].is_object()
+        }),
+        &result,
+    );
+    server
+        .send(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+        .unwrap();
+
+    let result = server.call("tools/list", json!({})).unwrap();
+    check(
+        "tools/list",
+        result.as_ref().is_ok_and(|r| {
+            let names: Vec<&str> = r["tools"]
+                .as_array()
+                .map(|tools| tools.iter().filter_map(|t| t["name"].as_str()).collect())
+                .unwrap_or_default();
+            names == ["search_prompts", "add_prompt"]
+        }),
+        &result,
+    );
+
+    let added = server
+        .tool(
+            "add_prompt",
+            json!({
+                "title": "Review Code!",
+                "content": "Review this {{lang|Rust}} code for {{focus}}.",
+                "tags": ["mcp-test"],
+            }),
+        )
+        .unwrap();
+    check(
+        "add_prompt",
+        added.0 && added.1.contains("review-code"),
+        &added,
+    );
+    let added = server
+        .tool(
+            "add_prompt",
+            json!({ "title": "Review code", "content": "Second prompt with a clashing name" }),
+        )
+        .unwrap();
+    check("add_prompt with a clashing name", added.0, &added);
+    let added = server
+        .tool("add_prompt", json!({ "content": "No title" }))
+        .unwrap();
+    check("add_prompt without a title", !added.0, &added);
+
+    let result = server.call("prompts/list", json!({})).unwrap();
+    check(
+        "prompts/list",
+        result.as_ref().is_ok_and(|r| {
+            let prompts = r["prompts"].as_array().cloned().unwrap_or_default();
+            prompts.len() == 2
+                && prompts[0]["name"] == "review-code"
+                && prompts[0]["title"] == "Review Code!"
+                && prompts[0]["arguments"]
+                    == json!([
+                        { "name": "lang", "required": false, "description": "Defaults to “Rust”" },
+                        { "name": "focus", "required": true },
+                    ])
+                && prompts[1]["name"] == "review-code-2"
+        }),
+        &result,
+    );
+
+    let result = server
+        .call(
+            "prompts/get",
+            json!({ "name": "review-code", "arguments": { "focus": "safety" } }),
+        )
+        .unwrap();
+    check(
+        "prompts/get",
+        result.as_ref().is_ok_and(|r| {
+            r["messages"]
+                == json!([{
+                    "role": "user",
+                    "content": { "type": "text", "text": "Review this Rust code for safety." },
+                }])
+        }),
+        &result,
+    );
+    let result = server
+        .call(
+            "prompts/get",
+            json!({ "name": "Review Code!", "arguments": { "focus": "style", "lang": "Go" } }),
+        )
+        .unwrap();
+    check(
+        "prompts/get by title",
+        result
+            .as_ref()
+            .is_ok_and(|r| r["messages"][0]["content"]["text"] == "Review this Go code for style."),
+        &result,
+    );
+    let result = server
+        .call("prompts/get", json!({ "name": "review-code" }))
+        .unwrap();
+    check(
+        "prompts/get without a required argument",
+        result
+            .as_ref()
+            .is_err_and(|(code, message)| *code == -32602 && message.contains("focus")),
+        &result,
+    );
+    let result = server
+        .call("prompts/get", json!({ "name": "nope" }))
+        .unwrap();
+    check("prompts/get of an unknown prompt", result.is_err(), &result);
+
+    let found = server
+        .tool("search_prompts", json!({ "query": "clashing" }))
+        .unwrap();
+    check(
+        "search_prompts",
+        found.0 && found.1.contains("review-code-2") && !found.1.contains("\"review-code\""),
+        &found,
+    );
+    let found = server
+        .tool("search_prompts", json!({ "query": "zebra" }))
+        .unwrap();
+    check("search_prompts without hits", found.0, &found);
+
+    let result = server
+        .call("tools/call", json!({ "name": "nope", "arguments": {} }))
+        .unwrap();
+    check(
+        "unknown tool",
+        result.as_ref().is_err_and(|(code, _)| *code == -32602),
+        &result,
+    );
+    let result = server.call("resources/list", json!({})).unwrap();
+    check(
+        "unknown method",
+        result.as_ref().is_err_and(|(code, _)| *code == -32601),
+        &result,
+    );
+    let result = server.call("ping", json!({})).unwrap();
+    check("ping", result.is_ok(), &result);
+
+    drop(server.stdin);
+    let status = server.child.wait().unwrap();
+    check("exits when stdin closes", status.success(), status);
+}
+
+fn check(name: &str, passed: bool, detail: impl std::fmt::Debug) {
+    assert!(passed, "{}: {:?}", name, detail);
+}
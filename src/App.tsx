@@ -1,10 +1,10 @@
 import { useState, useEffect, useCallback, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Store } from "@tauri-apps/plugin-store";
 import { listen } from "@tauri-apps/api/event";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { WebviewWindow } from "@tauri-apps/api/webviewWindow";
-import { Settings, X, Pencil } from "lucide-react";
+import { readText } from "@tauri-apps/plugin-clipboard-manager";
+import { Settings, X, Pencil, Check } from "lucide-react";
 import "./App.css";
 import { PhysicalPosition } from "@tauri-apps/api/window";
 
@@ -13,78 +13,67 @@ interface Prompt {
   title: string;
   content: string;
   color: string;
+  abbreviation?: string;
+  tags?: string[];
+  folder?: string;
+  // Name of the project whose prompt pack this comes from; such prompts live
+  // in the repository and are not part of the library.
+  project?: string;
 }
 
-const DEFAULT_PROMPTS: Prompt[] = [
-  {
-    id: "1",
-    title: "Debug Root Cause",
-    content:
-      "Come up with 5-7 most likely root causes of this bug, and attempt the 1-2 most likely fixes with proper logging. Don't hold back, give it your all.",
-    color: "from-purple-500 to-pink-500",
-  },
-  {
-    id: "2",
-    title: "Explain Code",
-    content:
-      "Explain this code in detail, including its purpose, how it works, potential edge cases, and any improvements that could be made.",
-    color: "from-blue-500 to-cyan-500",
-  },
-  {
-    id: "3",
-    title: "Refactor",
-    content:
-      "Refactor this code to be more readable, maintainable, and performant. Follow best practices and explain your changes.",
-    color: "from-green-500 to-emerald-500",
-  },
-  {
-    id: "4",
-    title: "Write Tests",
-    content:
-      "Write comprehensive unit tests for this code, covering edge cases and error scenarios. Use appropriate testing patterns.",
-    color: "from-orange-500 to-red-500",
-  },
-  {
-    id: "5",
-    title: "Optimize Performance",
-    content:
-      "Analyze this code for performance bottlenecks and suggest specific optimizations with examples.",
-    color: "from-indigo-500 to-purple-500",
-  },
-  {
-    id: "6",
-    title: "Add Error Handling",
-    content:
-      "Add comprehensive error handling to this code with proper logging and user-friendly error messages.",
-    color: "from-teal-500 to-green-500",
-  },
-];
+interface Variable {
+  name: string;
+  default: string | null;
+}
+
+// A prompt waiting for the values of its variables before it is typed.
+interface VariablesRequest {
+  prompt: Prompt;
+  shortcut: number;
+  variables: Variable[];
+  values: Record<string, string>;
+}
+
+interface UndoState {
+  canUndo: boolean;
+  canRedo: boolean;
+  undoLabel: string | null;
+  redoLabel: string | null;
+}
 
 function App() {
-  const [prompts, setPrompts] = useState<Prompt[]>(DEFAULT_PROMPTS);
+  const [prompts, setPrompts] = useState<Prompt[]>([]);
   const [expandedIndex, setExpandedIndex] = useState<number | null>(null);
   const [injectedId, setInjectedId] = useState<string | null>(null);
   const [errorMessage, setErrorMessage] = useState<string>("");
+  const [notice, setNotice] = useState<string>("");
+  const [libraryError, setLibraryError] = useState<string | null>(null);
+  const [variablesRequest, setVariablesRequest] = useState<VariablesRequest | null>(null);
   const pillRefs = useRef<(HTMLDivElement | null)[]>([]);
 
   /* --------------------------------------------------
    * Load & persist prompts
    * -------------------------------------------------- */
   const loadPrompts = useCallback(async () => {
-    const store = await Store.load("prompts.json");
-    let saved = await store.get<Prompt[]>("prompts");
-    if (!saved || saved.length === 0) {
-      saved = DEFAULT_PROMPTS;
-      await store.set("prompts", saved);
-      await store.save();
-    }
-    setPrompts(saved);
+    // The bar may be scoped to a folder or tags (see Settings); indexes and
+    // shortcuts below refer to what it shows.
+    setPrompts(await invoke<Prompt[]>("get_bar_prompts"));
+    setLibraryError(await invoke<string | null>("get_library_error"));
   }, []);
 
   useEffect(() => {
     loadPrompts();
   }, [loadPrompts]);
 
+  useEffect(() => {
+    const unlistenPromise = listen<string>("library-error", ({ payload }) =>
+      setLibraryError(payload)
+    );
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
   useEffect(() => {
     pillRefs.current = pillRefs.current.slice(0, prompts.length);
   }, [prompts]);
@@ -107,21 +96,138 @@ function App() {
   }, [prompts]);
 
   useEffect(() => {
-    const unlistenPromise = listen("prompts-updated", loadPrompts);
+    const unlistenPromises = [
+      listen("prompts-updated", loadPrompts),
+      listen("bar-scope-changed", loadPrompts),
+    ];
     return () => {
-      unlistenPromise.then((unlisten) => unlisten());
+      unlistenPromises.forEach((p) => p.then((unlisten) => unlisten()));
     };
   }, [loadPrompts]);
 
+  /* --------------------------------------------------
+   * Hover handlers
+   * -------------------------------------------------- */
+  const highlightPrompt = useCallback((index: number | null) => {
+    setExpandedIndex(index);
+    // Hold-to-peek mode injects the highlighted prompt when the toggle
+    // shortcut is released, so the backend needs to know about it.
+    invoke("set_highlighted_prompt", { index }).catch((err) =>
+      console.warn("set_highlighted_prompt failed", err)
+    );
+  }, []);
+
+  const handleMouseEnter = useCallback((index: number) => {
+    highlightPrompt(index);
+  }, [highlightPrompt]);
+
+  const handleMouseLeave = useCallback(() => {
+    highlightPrompt(null);
+  }, [highlightPrompt]);
+
+  /* --------------------------------------------------
+   * Library edits – delete, reorder, undo/redo
+   * -------------------------------------------------- */
+  const showNotice = useCallback((message: string) => {
+    setNotice(message);
+    setTimeout(() => setNotice(""), 3000);
+  }, []);
+
+  const deletePrompt = useCallback(async (prompt: Prompt) => {
+    try {
+      await invoke("delete_prompt", { id: prompt.id });
+      highlightPrompt(null);
+      showNotice(`Moved “${prompt.title}” to the trash – ⌘Z / Ctrl+Z to undo`);
+    } catch (err) {
+      console.error("delete_prompt failed", err);
+      setErrorMessage(`Failed to delete prompt: ${err}`);
+      setTimeout(() => setErrorMessage(""), 3000);
+    }
+  }, [highlightPrompt, showNotice]);
+
+  // Moves `prompt` to where `neighbour` is. The bar may only show part of the
+  // library, so the position is looked up in the whole of it.
+  const movePrompt = useCallback(async (prompt: Prompt, neighbour: Prompt, barIndex: number) => {
+    try {
+      const all = await invoke<Prompt[]>("get_prompts");
+      const toIndex = all.findIndex((p) => p.id === neighbour.id);
+      if (toIndex < 0) return;
+      await invoke("move_prompt", { id: prompt.id, toIndex });
+      highlightPrompt(barIndex);
+    } catch (err) {
+      console.error("move_prompt failed", err);
+    }
+  }, [highlightPrompt]);
+
+  const undoRedo = useCallback(async (redo: boolean) => {
+    try {
+      const before = await invoke<UndoState>("get_undo_state");
+      const label = redo ? before.redoLabel : before.undoLabel;
+      if (!label) return;
+      await invoke(redo ? "redo" : "undo");
+      showNotice(`${redo ? "Redone" : "Undone"}: ${label}`);
+    } catch (err) {
+      console.error("undo/redo failed", err);
+      setErrorMessage(`${err}`);
+      setTimeout(() => setErrorMessage(""), 3000);
+    }
+  }, [showNotice]);
+
   /* --------------------------------------------------
    * Keyboard listener – press 1-9 to inject prompts
    * -------------------------------------------------- */
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
+      // Keys are typed into the variables form while it is open.
+      if (variablesRequest) return;
+
+      // Cmd/Ctrl+Z undoes the last library change, Cmd/Ctrl+Shift+Z or
+      // Ctrl+Y redoes it.
+      const key = e.key.toLowerCase();
+      if ((e.metaKey || e.ctrlKey) && (key === "z" || key === "y")) {
+        e.preventDefault();
+        undoRedo(key === "y" || e.shiftKey);
+        return;
+      }
+
+      const highlighted = expandedIndex !== null ? prompts[expandedIndex] : undefined;
+
+      // Delete / Backspace moves the highlighted prompt to the trash, once
+      // confirmed: hovering is enough to highlight, so a stray key would do.
+      if ((e.key === "Delete" || e.key === "Backspace") && highlighted && !highlighted.project) {
+        e.preventDefault();
+        if (confirm(`Move “${highlighted.title}” to the trash?`)) {
+          deletePrompt(highlighted);
+        }
+        return;
+      }
+
+      // Alt+Left / Alt+Right moves the highlighted prompt along the bar.
+      if (e.altKey && (e.key === "ArrowLeft" || e.key === "ArrowRight") && highlighted) {
+        e.preventDefault();
+        if (highlighted.project) return;
+        const target = expandedIndex! + (e.key === "ArrowLeft" ? -1 : 1);
+        if (target >= 0 && target < Math.min(prompts.length, 9) && !prompts[target].project) {
+          movePrompt(highlighted, prompts[target], target);
+        }
+        return;
+      }
+
+      // Tab / arrow keys move the highlight, even while the toggle shortcut's
+      // modifiers are still held down in hold-to-peek mode.
+      if (e.key === "Tab" || e.key === "ArrowRight" || e.key === "ArrowLeft") {
+        const count = Math.min(prompts.length, 9);
+        if (count === 0) return;
+        e.preventDefault();
+        const step = e.key === "ArrowLeft" || (e.key === "Tab" && e.shiftKey) ? -1 : 1;
+        const current = expandedIndex ?? (step > 0 ? -1 : 0);
+        highlightPrompt((current + step + count) % count);
+        return;
+      }
+
       // Only act on plain number keys (no modifiers) while the bar has focus
       if (e.altKey || e.metaKey || e.ctrlKey || e.shiftKey) return;
 
-      const key = e.key;
       if (key >= "1" && key <= "9") {
         const index = parseInt(key, 10) - 1;
         if (index < prompts.length) {
@@ -133,25 +239,40 @@ function App() {
 
     window.addEventListener("keydown", handleKeyDown);
     return () => window.removeEventListener("keydown", handleKeyDown);
-  }, [prompts]);
-
-  /* --------------------------------------------------
-   * Hover handlers
-   * -------------------------------------------------- */
-  const handleMouseEnter = useCallback((index: number) => {
-    setExpandedIndex(index);
-  }, []);
-
-  const handleMouseLeave = useCallback(() => {
-    setExpandedIndex(null);
-  }, []);
+  }, [prompts, expandedIndex, highlightPrompt, deletePrompt, movePrompt, undoRedo, variablesRequest]);
 
   /* --------------------------------------------------
    * Inject text helper
    * -------------------------------------------------- */
-  const injectTextViaShortcut = async (prompt: Prompt, shortcut: number) => {
+  const injectTextViaShortcut = async (
+    prompt: Prompt,
+    shortcut: number,
+    vars?: Record<string, string>
+  ) => {
     setInjectedId(null);
     setErrorMessage("");
+
+    // `{{clipboard}}` (e.g. from imported snippets) is the clipboard text;
+    // other variables without a default are asked for first.
+    if (vars === undefined) {
+      try {
+        const variables = await invoke<Variable[]>("get_prompt_variables", {
+          content: prompt.content,
+        });
+        const values: Record<string, string> = {};
+        if (variables.some((v) => v.name === "clipboard" && v.default === null)) {
+          values.clipboard = await readText().catch(() => "");
+        }
+        if (variables.some((v) => v.default === null && !(v.name in values))) {
+          await askForVariables(prompt, shortcut, variables, values);
+          return;
+        }
+        vars = values;
+      } catch (err) {
+        console.warn("get_prompt_variables failed", err);
+      }
+    }
+
     try {
       // If the prompt bar is hidden (e.g. the user used a global shortcut
       // without opening the UI), we first capture whichever application is
@@ -179,16 +300,69 @@ function App() {
       // Give macOS a moment to actually switch focus.
       await new Promise((r) => setTimeout(r, 300));
 
-      await invoke<string>("inject_text", { text: prompt.content });
+      await invoke<string>("inject_text", {
+        text: prompt.content,
+        promptId: prompt.id,
+        vars: vars ?? null,
+      });
       setInjectedId(prompt.id);
       setTimeout(() => setInjectedId(null), 2000);
     } catch (e) {
       console.error(e);
-      setErrorMessage(`Failed to inject prompt ${shortcut}`);
+      // inject_text refuses password fields, protected apps and text the
+      // pre-injection hook vetoed, and reports prompts it could not fill in
+      // (e.g. a failing shell variable), with a dedicated error kind; say so
+      // instead of reporting a failure.
+      const kind = (e as { kind?: string } | null)?.kind;
+      if (
+        kind === "secureInput" ||
+        kind === "protectedApp" ||
+        kind === "vetoed" ||
+        kind === "renderFailed"
+      ) {
+        setErrorMessage(`Prompt ${shortcut} not injected: ${(e as { message: string }).message}`);
+      } else {
+        setErrorMessage(`Failed to inject prompt ${shortcut}`);
+      }
       setTimeout(() => setErrorMessage(""), 3000);
     }
   };
 
+  // Shows the variables form, bringing up the bar when a global shortcut
+  // fired while it was hidden.
+  const askForVariables = async (
+    prompt: Prompt,
+    shortcut: number,
+    variables: Variable[],
+    known: Record<string, string>
+  ) => {
+    try {
+      const win = getCurrentWindow();
+      if (!(await win.isVisible())) {
+        await invoke("capture_frontmost_app");
+        await win.show();
+      }
+      await win.setFocus();
+    } catch (err) {
+      console.warn("Failed to show the bar", err);
+    }
+    setVariablesRequest({
+      prompt,
+      shortcut,
+      variables,
+      values: Object.fromEntries(
+        variables.map((v) => [v.name, known[v.name] ?? v.default ?? ""])
+      ),
+    });
+  };
+
+  const submitVariables = () => {
+    if (!variablesRequest) return;
+    const { prompt, shortcut, values } = variablesRequest;
+    setVariablesRequest(null);
+    injectTextViaShortcut(prompt, shortcut, values);
+  };
+
   /* --------------------------------------------------
    * Close window helper
    * -------------------------------------------------- */
@@ -212,7 +386,7 @@ function App() {
     new WebviewWindow("settings", {
       url: "index.html?settings",
       width: 450,
-      height: 260,
+      height: 680,
       resizable: false,
       title: "Prompt Picker Settings",
       decorations: true,
@@ -223,6 +397,9 @@ function App() {
    * Open Edit Window
    * -------------------------------------------------- */
   const openEditWindow = async (index: number, element?: HTMLElement | null) => {
+    const prompt = prompts[index];
+    // Project prompts are edited in their repository.
+    if (!prompt || prompt.project) return;
     console.log(`openEditWindow called for index ${index}`);
     try {
       const label = `edit-${index}`;
@@ -234,7 +411,7 @@ function App() {
       }
 
       const EDIT_WIDTH = 400;
-      const EDIT_HEIGHT = 420;
+      const EDIT_HEIGHT = 580;
 
       let newLeft: number | undefined;
       let newTop: number | undefined;
@@ -259,7 +436,7 @@ function App() {
       }
 
       const newWin = new WebviewWindow(label, {
-        url: `index.html?edit=${index}`,
+        url: `index.html?edit=${index}&id=${encodeURIComponent(prompt.id)}`,
         title: `Edit Prompt ${index + 1}`,
         width: EDIT_WIDTH,
         height: EDIT_HEIGHT,
@@ -294,7 +471,8 @@ function App() {
               ref={(el) => (pillRefs.current[i] = el)}
               className={`prompt-pill ${injectedId === p.id ? "injected" : ""} ${
                 expandedIndex === i ? "expanded" : ""
-              }`}
+              } ${p.project ? "project-scoped" : ""}`}
+              title={p.project ? `From project ${p.project}` : undefined}
               onMouseEnter={() => handleMouseEnter(i)}
               onMouseLeave={handleMouseLeave}
               onClick={() => injectTextViaShortcut(p, i + 1)}
@@ -311,18 +489,20 @@ function App() {
               {/* Number + Edit stacked */}
               <div className="prompt-badge" data-tauri-drag-region="false">
                 <div className="prompt-number">{i + 1}</div>
-                <button
-                  className="edit-btn"
-                  onClick={(e) => {
-                    e.stopPropagation();
-                    console.log(`Edit button clicked for prompt ${i + 1}`);
-                    const pill = pillRefs.current[i];
-                    openEditWindow(i, pill);
-                  }}
-                  data-tauri-drag-region="false"
-                >
-                  <Pencil size={12} />
-                </button>
+                {!p.project && (
+                  <button
+                    className="edit-btn"
+                    onClick={(e) => {
+                      e.stopPropagation();
+                      console.log(`Edit button clicked for prompt ${i + 1}`);
+                      const pill = pillRefs.current[i];
+                      openEditWindow(i, pill);
+                    }}
+                    data-tauri-drag-region="false"
+                  >
+                    <Pencil size={12} />
+                  </button>
+                )}
               </div>
 
               {expandedIndex === i ? (
@@ -336,7 +516,9 @@ function App() {
               ) : (
                 <div className="prompt-info">
                   <div className="prompt-title">{p.title}</div>
-                  <div className="prompt-shortcut">⌘⌥{i + 1}</div>
+                  <div className="prompt-shortcut">
+                    {p.project ? `${p.project} · ` : ""}⌘⌥{i + 1}
+                  </div>
                 </div>
               )}
 
@@ -366,8 +548,61 @@ function App() {
         </div>
       </div>
 
+      {/* Values for the variables of the prompt about to be typed */}
+      {variablesRequest && (
+        <form
+          className="variables-form"
+          onSubmit={(e) => {
+            e.preventDefault();
+            submitVariables();
+          }}
+          onKeyDown={(e) => {
+            if (e.key === "Escape") setVariablesRequest(null);
+          }}
+          data-tauri-drag-region="false"
+        >
+          <span className="variables-title">{variablesRequest.prompt.title}</span>
+          {variablesRequest.variables.map((v, i) => (
+            <input
+              key={v.name}
+              className="variables-input"
+              placeholder={v.name}
+              title={v.name}
+              value={variablesRequest.values[v.name]}
+              onChange={(e) =>
+                setVariablesRequest({
+                  ...variablesRequest,
+                  values: { ...variablesRequest.values, [v.name]: e.target.value },
+                })
+              }
+              autoFocus={i === 0}
+              data-tauri-drag-region="false"
+            />
+          ))}
+          <button type="submit" className="control-btn" title="Type" data-tauri-drag-region="false">
+            <Check size={16} />
+          </button>
+          <button
+            type="button"
+            className="control-btn close-btn"
+            onClick={() => setVariablesRequest(null)}
+            data-tauri-drag-region="false"
+          >
+            <X size={16} />
+          </button>
+        </form>
+      )}
+
       {/* Error toast */}
       {errorMessage && <div className="error-toast">{errorMessage}</div>}
+      {notice && !errorMessage && (
+        <div className="error-toast notice-toast">{notice}</div>
+      )}
+      {libraryError && !errorMessage && !notice && (
+        <div className="error-toast" title={libraryError}>
+          Prompt library could not be loaded. Restore a backup in Settings.
+        </div>
+      )}
 
       {/* Settings overlay moved to dedicated window */}
     </div>
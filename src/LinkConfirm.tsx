@@ -0,0 +1,98 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import "./App.css";
+
+interface LinkRequest {
+  action: "inject" | "add";
+  title: string;
+  text: string;
+  commands: string[];
+  source: string | null;
+}
+
+// Asks before a promptbuddy:// link types or adds a prompt.
+function LinkConfirm() {
+  const [request, setRequest] = useState<LinkRequest | null>(null);
+  const [loaded, setLoaded] = useState(false);
+
+  useEffect(() => {
+    invoke<LinkRequest | null>("get_pending_link").then((r) => {
+      setRequest(r);
+      setLoaded(true);
+    });
+  }, []);
+
+  const resolve = async (approved: boolean) => {
+    try {
+      await invoke("resolve_pending_link", { approved });
+    } catch (err) {
+      // The window is closed by then; failed injections are reported as usual.
+      console.warn("Link failed", err);
+    }
+  };
+
+  useEffect(() => {
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape") resolve(false);
+    };
+    window.addEventListener("keydown", handleKeyDown);
+    return () => window.removeEventListener("keydown", handleKeyDown);
+  }, []);
+
+  if (!loaded) {
+    return <div className="prompt-editor">Loading...</div>;
+  }
+
+  if (request === null) {
+    return (
+      <div className="prompt-editor">
+        <p>No link is waiting for confirmation.</p>
+        <div className="editor-buttons">
+          <button onClick={() => getCurrentWindow().close()} className="cancel-btn">
+            Close
+          </button>
+        </div>
+      </div>
+    );
+  }
+
+  const from = request.source ? ` from ${request.source}` : "";
+  return (
+    <div className="prompt-editor">
+      <h2>
+        {request.action === "inject"
+          ? `Type “${request.title}”${from}?`
+          : `Add “${request.title}”${from}?`}
+      </h2>
+      <label>
+        {request.action === "inject"
+          ? "This text will be typed into the app you came from:"
+          : "This prompt will be added to your library:"}
+        <textarea value={request.text} readOnly className="editor-textarea" rows={6} />
+      </label>
+      {request.commands.length > 0 && (
+        <label>
+          These shell commands run first, and their output replaces them:
+          <ul>
+            {request.commands.map((command, i) => (
+              <li key={i}>
+                <code>{command}</code>
+              </li>
+            ))}
+          </ul>
+        </label>
+      )}
+      <div className="editor-buttons">
+        <button onClick={() => resolve(true)} className="save-btn" autoFocus>
+          {request.action === "inject" ? "Type" : "Add"}
+        </button>
+        <button onClick={() => resolve(false)} className="cancel-btn">
+          Cancel
+        </button>
+      </div>
+    </div>
+  );
+}
+
+export default LinkConfirm;
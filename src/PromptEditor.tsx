@@ -1,6 +1,5 @@
-import { useState, useEffect } from "react";
-import { Store } from "@tauri-apps/plugin-store";
-import { emit } from "@tauri-apps/api/event";
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import "./App.css";
 
@@ -9,91 +8,154 @@ interface Prompt {
   title: string;
   content: string;
   color: string;
+  abbreviation?: string;
+  tags?: string[];
+  folder?: string;
 }
 
-const DEFAULT_PROMPTS: Prompt[] = [
-  {
-    id: "1",
-    title: "Debug Root Cause",
-    content:
-      "Come up with 5-7 most likely root causes of this bug, and attempt the 1-2 most likely fixes with proper logging. Don't hold back, give it your all.",
-    color: "from-purple-500 to-pink-500",
-  },
-  {
-    id: "2",
-    title: "Explain Code",
-    content:
-      "Explain this code in detail, including its purpose, how it works, potential edge cases, and any improvements that could be made.",
-    color: "from-blue-500 to-cyan-500",
-  },
-  {
-    id: "3",
-    title: "Refactor",
-    content:
-      "Refactor this code to be more readable, maintainable, and performant. Follow best practices and explain your changes.",
-    color: "from-green-500 to-emerald-500",
-  },
-  {
-    id: "4",
-    title: "Write Tests",
-    content:
-      "Write comprehensive unit tests for this code, covering edge cases and error scenarios. Use appropriate testing patterns.",
-    color: "from-orange-500 to-red-500",
-  },
-  {
-    id: "5",
-    title: "Optimize Performance",
-    content:
-      "Analyze this code for performance bottlenecks and suggest specific optimizations with examples.",
-    color: "from-indigo-500 to-purple-500",
-  },
-  {
-    id: "6",
-    title: "Add Error Handling",
-    content:
-      "Add comprehensive error handling to this code with proper logging and user-friendly error messages.",
-    color: "from-teal-500 to-green-500",
-  },
-];
+interface Revision {
+  revision: number;
+  savedAt: number | null;
+  author: string | null;
+  revertedFrom?: number;
+  prompt: Prompt;
+}
+
+interface DiffLine {
+  kind: "equal" | "insert" | "delete";
+  text: string;
+}
+
+interface PromptDiff {
+  fromTitle: string;
+  toTitle: string;
+  lines: DiffLine[];
+}
 
 function PromptEditor() {
   const urlParams = new URLSearchParams(window.location.search);
   const index = parseInt(urlParams.get("edit") || "-1", 10);
+  // The bar can be scoped, so its index isn't necessarily the library index.
+  const id = urlParams.get("id");
 
   const [prompt, setPrompt] = useState<Prompt | null>(null);
   const [title, setTitle] = useState("");
   const [content, setContent] = useState("");
+  const [abbreviation, setAbbreviation] = useState("");
+  const [folder, setFolder] = useState("");
+  const [tags, setTags] = useState("");
+  const [shellAllowed, setShellAllowed] = useState(false);
+  const [shellWasAllowed, setShellWasAllowed] = useState(false);
   const [loaded, setLoaded] = useState(false);
+  const [history, setHistory] = useState<Revision[]>([]);
+  const [selectedRevision, setSelectedRevision] = useState<number | null>(null);
+  const [diff, setDiff] = useState<PromptDiff | null>(null);
+
+  const load = useCallback(async () => {
+    const saved = await invoke<Prompt[]>("get_prompts");
+    const p = id !== null ? saved.find((p) => p.id === id) : saved[index];
+    if (p) {
+      setPrompt(p);
+      setTitle(p.title);
+      setContent(p.content);
+      setAbbreviation(p.abbreviation ?? "");
+      setFolder(p.folder ?? "");
+      setTags((p.tags ?? []).join(", "));
+      const shellAllowed = await invoke<boolean>("get_prompt_shell_commands", { id: p.id });
+      setShellAllowed(shellAllowed);
+      setShellWasAllowed(shellAllowed);
+    } else {
+      setPrompt(null);
+    }
+    setLoaded(true);
+  }, [id, index]);
+
+  useEffect(() => {
+    load();
+  }, [load]);
 
+  // Cmd/Ctrl+Z and Cmd/Ctrl+Shift+Z (or Ctrl+Y) undo and redo library
+  // changes, unless a text field has focus and should get its own undo.
   useEffect(() => {
-    const load = async () => {
-      const store = await Store.load("prompts.json");
-      let saved = (await store.get<Prompt[]>("prompts")) || [];
-      if (saved.length === 0) saved = DEFAULT_PROMPTS;
-      if (index >= 0 && index < saved.length) {
-        const p = saved[index];
-        setPrompt(p);
-        setTitle(p.title);
-        setContent(p.content);
+    const handleKeyDown = async (e: KeyboardEvent) => {
+      const target = e.target as HTMLElement | null;
+      if (target?.tagName === "INPUT" || target?.tagName === "TEXTAREA") return;
+      const key = e.key.toLowerCase();
+      if (!(e.metaKey || e.ctrlKey) || (key !== "z" && key !== "y")) return;
+      e.preventDefault();
+      const redo = key === "y" || e.shiftKey;
+      try {
+        await invoke(redo ? "redo" : "undo");
+        await load();
+      } catch (err) {
+        console.warn(`${redo ? "redo" : "undo"} failed`, err);
       }
-      setLoaded(true);
     };
-    load();
-  }, [index]);
+    window.addEventListener("keydown", handleKeyDown);
+    return () => window.removeEventListener("keydown", handleKeyDown);
+  }, [load]);
 
   const save = async () => {
     if (!prompt) return;
-    const store = await Store.load("prompts.json");
-    const saved = (await store.get<Prompt[]>("prompts")) || DEFAULT_PROMPTS;
-    if (index >= 0 && index < saved.length) {
-      saved[index] = { ...prompt, title, content };
-      await store.set("prompts", saved);
-      await emit("prompts-updated");
-      const win = getCurrentWindow();
-      await win.close();
+    const trigger = abbreviation.trim();
+    await invoke("update_prompt", {
+      prompt: {
+        ...prompt,
+        title,
+        content,
+        abbreviation: trigger === "" ? undefined : trigger,
+        folder: folder.trim() === "" ? undefined : folder,
+        tags: tags.split(",").map((tag) => tag.trim()).filter((tag) => tag !== ""),
+      },
+    });
+    // Allowing applies to the commands as saved, so it is renewed on every
+    // save while the box is ticked.
+    if (shellAllowed || shellWasAllowed) {
+      await invoke("set_prompt_shell_commands", { id: prompt.id, allowed: shellAllowed });
     }
+    const win = getCurrentWindow();
+    await win.close();
+  };
+
+  const loadHistory = async () => {
+    if (!prompt) return;
+    const revisions = await invoke<Revision[]>("prompt_history", { id: prompt.id });
+    setHistory(revisions);
+    // Default to the revision before the current one.
+    const previous = revisions[revisions.length - 2];
+    if (previous) showDiff(previous.revision, revisions);
   };
 
+  const showDiff = async (revision: number, revisions = history) => {
+    if (!prompt) return;
+    const current = revisions[revisions.length - 1];
+    setSelectedRevision(revision);
+    setDiff(
+      await invoke<PromptDiff>("diff_prompt_revisions", {
+        id: prompt.id,
+        from: revision,
+        to: current.revision,
+      })
+    );
+  };
+
+  const revert = async () => {
+    if (!prompt || selectedRevision === null) return;
+    await invoke("revert_prompt", { id: prompt.id, revision: selectedRevision });
+    const win = getCurrentWindow();
+    await win.close();
+  };
+
+  const describeRevision = (r: Revision) =>
+    [
+      `#${r.revision}`,
+      r.savedAt ? new Date(r.savedAt * 1000).toLocaleString() : "unknown date",
+      r.author ?? "",
+      r.revertedFrom ? `(revert of #${r.revertedFrom})` : "",
+    ]
+      .filter(Boolean)
+      .join(" · ");
+
   const cancel = async () => {
     const win = getCurrentWindow();
     await win.close();
@@ -127,6 +189,84 @@ function PromptEditor() {
           rows={5}
         />
       </label>
+      <label>
+        Abbreviation:
+        <input
+          value={abbreviation}
+          onChange={(e) => setAbbreviation(e.target.value)}
+          placeholder="e.g. ;dbg"
+          className="editor-input"
+        />
+      </label>
+      <label>
+        Folder:
+        <input
+          value={folder}
+          onChange={(e) => setFolder(e.target.value)}
+          placeholder="e.g. Work/Reviews"
+          className="editor-input"
+        />
+      </label>
+      <label>
+        Tags:
+        <input
+          value={tags}
+          onChange={(e) => setTags(e.target.value)}
+          placeholder="comma-separated, e.g. rust, review"
+          className="editor-input"
+        />
+      </label>
+      {(shellAllowed || /\{\{\s*sh:/.test(content)) && (
+        <label>
+          <input
+            type="checkbox"
+            checked={shellAllowed}
+            onChange={(e) => setShellAllowed(e.target.checked)}
+          />
+          Run the {"{{sh: …}}"} commands of this prompt (only allowed programs, see Settings)
+        </label>
+      )}
+      <details
+        className="editor-history"
+        onToggle={(e) => (e.target as HTMLDetailsElement).open && loadHistory()}
+      >
+        <summary>History</summary>
+        {history.length < 2 ? (
+          <p>No earlier revisions.</p>
+        ) : (
+          <>
+            <select
+              value={selectedRevision ?? ""}
+              onChange={(e) => showDiff(Number(e.target.value))}
+              className="editor-input"
+            >
+              {history.slice(0, -1).reverse().map((r) => (
+                <option key={r.revision} value={r.revision}>
+                  {describeRevision(r)}
+                </option>
+              ))}
+            </select>
+            {diff && (
+              <pre className="editor-diff">
+                {diff.fromTitle !== diff.toTitle && (
+                  <div className="diff-title">
+                    Title: {diff.fromTitle} → {diff.toTitle}
+                  </div>
+                )}
+                {diff.lines.map((line, i) => (
+                  <div key={i} className={`diff-${line.kind}`}>
+                    {line.kind === "insert" ? "+ " : line.kind === "delete" ? "- " : "  "}
+                    {line.text}
+                  </div>
+                ))}
+              </pre>
+            )}
+            <button onClick={revert} className="cancel-btn">
+              Revert to this revision
+            </button>
+          </>
+        )}
+      </details>
       <div className="editor-buttons">
         <button onClick={save} className="save-btn">Save</button>
         <button onClick={cancel} className="cancel-btn">Cancel</button>
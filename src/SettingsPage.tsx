@@ -2,10 +2,123 @@ import { useState, useEffect } from "react";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { Store } from "@tauri-apps/plugin-store";
 import { invoke } from "@tauri-apps/api/core";
+import { emit } from "@tauri-apps/api/event";
 import "./App.css";
 
+interface StorageSettings {
+  backend: "sqlite" | "json" | "markdown";
+  markdownDirectory: string;
+}
+
+interface BackupInfo {
+  name: string;
+  createdAt: number;
+  promptCount: number;
+}
+
+interface TrashedPrompt {
+  prompt: { id: string; title: string };
+  deletedAt: number;
+  index: number;
+}
+
+interface FolderInfo {
+  path: string;
+  promptCount: number;
+}
+
+interface ProjectInfo {
+  path: string;
+  name: string;
+  promptCount: number;
+}
+
+interface ImportPreview {
+  added: number;
+  overwritten: number;
+  skipped: number;
+  keptBoth: number;
+  changes: { action: string; title: string; existingTitle: string | null }[];
+  issues: { item: string; message: string }[];
+}
+
+type BundleFormat = "json" | "markdown" | "csv" | "espanso" | "alfred" | "raycast" | "vscode";
+
+// 其他工具的片段格式只能导入
+const IMPORT_ONLY_FORMATS: BundleFormat[] = ["espanso", "alfred", "raycast", "vscode"];
+
+interface HttpApiSettings {
+  enabled: boolean;
+  port: number;
+  token: string | null;
+  allowedOrigins: string[];
+  url: string | null;
+}
+
+interface InjectHooks {
+  preInject: string;
+  postInject: string;
+  timeoutSecs: number;
+}
+
+interface ShellSettings {
+  prompts: Record<string, string[]>;
+  allowedCommands: string[];
+  workingDirectory: string;
+  timeoutSecs: number;
+}
+
+interface ShortcutValidation {
+  valid: boolean;
+  normalized: string | null;
+  error: string | null;
+  notes: string[];
+}
+
 function SettingsPage() {
   const [toggleShortcut, setToggleShortcut] = useState("ctrl+space");
+  const [toggleMode, setToggleMode] = useState("toggle");
+  const [pauseShortcut, setPauseShortcut] = useState("ctrl+alt+shift+p");
+  const [shortcutsPaused, setShortcutsPaused] = useState(false);
+  const [shortcutBlocklist, setShortcutBlocklist] = useState("");
+  const [protectedApps, setProtectedApps] = useState("");
+  const [linkTrustedApps, setLinkTrustedApps] = useState("");
+  const [preInjectHook, setPreInjectHook] = useState("");
+  const [postInjectHook, setPostInjectHook] = useState("");
+  const [injectHookTimeout, setInjectHookTimeout] = useState(5);
+  const [shellCommands, setShellCommands] = useState("");
+  const [shellDirectory, setShellDirectory] = useState("");
+  const [shellTimeout, setShellTimeout] = useState(10);
+  const [expansionEnabled, setExpansionEnabled] = useState(false);
+  const [expansionBlocklist, setExpansionBlocklist] = useState("");
+  const [storageBackend, setStorageBackend] = useState<StorageSettings["backend"]>("sqlite");
+  const [markdownDirectory, setMarkdownDirectory] = useState("");
+  const [backups, setBackups] = useState<BackupInfo[]>([]);
+  const [selectedBackup, setSelectedBackup] = useState("");
+  const [libraryError, setLibraryError] = useState<string | null>(null);
+  const [trash, setTrash] = useState<TrashedPrompt[]>([]);
+  const [folders, setFolders] = useState<FolderInfo[]>([]);
+  const [barFolder, setBarFolder] = useState("");
+  const [barTagFilter, setBarTagFilter] = useState("");
+  const [projectDirectories, setProjectDirectories] = useState("");
+  const [activeProject, setActiveProject] = useState<ProjectInfo | null>(null);
+  const [bundleFormat, setBundleFormat] = useState<BundleFormat>("json");
+  const [bundlePath, setBundlePath] = useState("");
+  const [mergeStrategy, setMergeStrategy] = useState<"skip" | "overwrite" | "keepBoth">("skip");
+  const [importPreview, setImportPreview] = useState<ImportPreview | null>(null);
+  const [rulePrompts, setRulePrompts] = useState<{ id: string; title: string }[]>([]);
+  const [ruleSelection, setRuleSelection] = useState<string[]>([]);
+  const [ruleTarget, setRuleTarget] = useState<"cursor" | "agents" | "copilot">("cursor");
+  const [ruleProject, setRuleProject] = useState("");
+  const [ruleGlobs, setRuleGlobs] = useState("");
+  const [ruleAlwaysApply, setRuleAlwaysApply] = useState(false);
+  const [trashRetentionDays, setTrashRetentionDays] = useState(30);
+  const [httpApi, setHttpApi] = useState<HttpApiSettings | null>(null);
+  const [httpApiEnabled, setHttpApiEnabled] = useState(false);
+  const [httpApiPort, setHttpApiPort] = useState(27182);
+  const [httpApiOrigins, setHttpApiOrigins] = useState("");
+  const [shortcutCheck, setShortcutCheck] = useState<ShortcutValidation | null>(null);
+  const [recording, setRecording] = useState(false);
   const [loading, setLoading] = useState(true);
   const [saving, setSaving] = useState(false);
 
@@ -18,6 +131,68 @@ function SettingsPage() {
         if (savedShortcut) {
           setToggleShortcut(savedShortcut);
         }
+        const savedMode = await store.get<string>("toggleMode");
+        if (savedMode) {
+          setToggleMode(savedMode);
+        }
+        const savedPauseShortcut = await store.get<string>("pauseShortcut");
+        if (savedPauseShortcut) {
+          setPauseShortcut(savedPauseShortcut);
+        }
+        const savedShortcutBlocklist = await store.get<string[]>("shortcutBlocklist");
+        if (savedShortcutBlocklist) {
+          setShortcutBlocklist(savedShortcutBlocklist.join(", "));
+        }
+        const pauseStatus = await invoke<{ manuallyPaused: boolean }>(
+          "get_shortcuts_pause_status"
+        );
+        setShortcutsPaused(pauseStatus.manuallyPaused);
+        setProtectedApps(
+          (await invoke<string[]>("get_protected_apps")).join(", ")
+        );
+        setLinkTrustedApps(
+          (await invoke<string[]>("get_deep_link_trusted_apps")).join(", ")
+        );
+        const hooks = await invoke<InjectHooks>("get_inject_hooks");
+        setPreInjectHook(hooks.preInject);
+        setPostInjectHook(hooks.postInject);
+        setInjectHookTimeout(hooks.timeoutSecs);
+        const shell = await invoke<ShellSettings>("get_shell_settings");
+        setShellCommands(shell.allowedCommands.join(", "));
+        setShellDirectory(shell.workingDirectory);
+        setShellTimeout(shell.timeoutSecs);
+        setExpansionEnabled(
+          (await store.get<boolean>("textExpansionEnabled")) ?? false
+        );
+        const savedBlocklist = await store.get<string[]>("textExpansionBlocklist");
+        if (savedBlocklist) {
+          setExpansionBlocklist(savedBlocklist.join(", "));
+        }
+        const storage = await invoke<StorageSettings>("get_storage_settings");
+        setStorageBackend(storage.backend);
+        setMarkdownDirectory(storage.markdownDirectory);
+        const savedBackups = await invoke<BackupInfo[]>("list_backups");
+        setBackups(savedBackups);
+        setSelectedBackup(savedBackups[0]?.name ?? "");
+        setLibraryError(await invoke<string | null>("get_library_error"));
+        setTrashRetentionDays(
+          (await store.get<number>("trashRetentionDays")) ?? 30
+        );
+        setTrash(await invoke<TrashedPrompt[]>("list_trash"));
+        setFolders(await invoke<FolderInfo[]>("list_folders"));
+        setBarFolder((await store.get<string>("barFolder")) ?? "");
+        setBarTagFilter((await store.get<string>("barTagFilter")) ?? "");
+        const savedProjects = await store.get<string[]>("projectDirectories");
+        if (savedProjects) {
+          setProjectDirectories(savedProjects.join(", "));
+        }
+        setActiveProject(await invoke<ProjectInfo | null>("get_active_project"));
+        setRulePrompts(await invoke<{ id: string; title: string }[]>("get_prompts"));
+        const savedHttpApi = await invoke<HttpApiSettings>("get_http_api_settings");
+        setHttpApi(savedHttpApi);
+        setHttpApiEnabled(savedHttpApi.enabled);
+        setHttpApiPort(savedHttpApi.port);
+        setHttpApiOrigins(savedHttpApi.allowedOrigins.join(", "));
       } catch (err) {
         console.warn("Failed to load settings:", err);
       } finally {
@@ -27,16 +202,98 @@ function SettingsPage() {
     loadSettings();
   }, []);
 
+  // 校验快捷键并显示平台提示
+  useEffect(() => {
+    invoke<ShortcutValidation>("validate_shortcut", { shortcut: toggleShortcut })
+      .then(setShortcutCheck)
+      .catch((err) => console.warn("validate_shortcut failed", err));
+  }, [toggleShortcut]);
+
+  // 录制下一个按下的组合键
+  const recordShortcut = async () => {
+    const forwardKey = (e: KeyboardEvent) => {
+      e.preventDefault();
+      emit("shortcut-recorder-key", {
+        code: e.code,
+        ctrlKey: e.ctrlKey,
+        altKey: e.altKey,
+        shiftKey: e.shiftKey,
+        metaKey: e.metaKey,
+      });
+    };
+
+    setRecording(true);
+    window.addEventListener("keydown", forwardKey);
+    try {
+      const result = await invoke<ShortcutValidation>("record_shortcut");
+      if (result.normalized) {
+        setToggleShortcut(result.normalized);
+      }
+      setShortcutCheck(result);
+    } catch (err) {
+      console.warn("record_shortcut failed", err);
+    } finally {
+      window.removeEventListener("keydown", forwardKey);
+      setRecording(false);
+    }
+  };
+
   // 保存设置
   const saveSettings = async () => {
     setSaving(true);
     try {
       const store = await Store.load("settings.json");
-      await store.set("toggleShortcut", toggleShortcut);
+      const shortcut = shortcutCheck?.normalized ?? toggleShortcut;
+      await store.set("toggleShortcut", shortcut);
+      await store.set("toggleMode", toggleMode);
+      await store.set("pauseShortcut", pauseShortcut);
+      await store.set("shortcutBlocklist", splitList(shortcutBlocklist));
+      await store.set(
+        "textExpansionBlocklist",
+        splitList(expansionBlocklist)
+      );
+      await store.set("trashRetentionDays", trashRetentionDays);
+      if (barTagFilter.trim() !== "") {
+        // 校验标签表达式
+        await invoke("filter_prompts", { tags: barTagFilter });
+      }
+      await store.set("barFolder", barFolder);
+      await store.set("barTagFilter", barTagFilter);
+      await store.set("projectDirectories", splitList(projectDirectories));
       await store.save();
+      await emit("bar-scope-changed");
       
       // 通知后端更新快捷键
-      await invoke("update_toggle_shortcut", { newShortcut: toggleShortcut });
+      await invoke("update_toggle_shortcut", { newShortcut: shortcut });
+      await invoke("update_toggle_mode", { mode: toggleMode });
+      await invoke("set_text_expansion_enabled", { enabled: expansionEnabled });
+      await invoke("update_pause_shortcut", { newShortcut: pauseShortcut });
+      await invoke("set_shortcuts_paused", { paused: shortcutsPaused });
+      await invoke("update_protected_apps", { apps: splitList(protectedApps) });
+      await invoke("update_deep_link_trusted_apps", {
+        apps: splitList(linkTrustedApps),
+      });
+      await invoke("update_inject_hooks", {
+        preInject: preInjectHook,
+        postInject: postInjectHook,
+        timeoutSecs: injectHookTimeout,
+      });
+      await invoke("update_shell_settings", {
+        allowedCommands: splitList(shellCommands),
+        workingDirectory: shellDirectory,
+        timeoutSecs: shellTimeout,
+      });
+      await invoke("set_storage_backend", {
+        backend: storageBackend,
+        markdownDirectory,
+      });
+      setHttpApi(
+        await invoke<HttpApiSettings>("update_http_api_settings", {
+          enabled: httpApiEnabled,
+          port: httpApiPort,
+          allowedOrigins: splitList(httpApiOrigins),
+        })
+      );
       
       console.log("Settings saved successfully");
     } catch (err) {
@@ -47,6 +304,127 @@ function SettingsPage() {
     }
   };
 
+  // 恢复备份（立即生效，当前提示词会先被备份）
+  const restoreBackup = async () => {
+    if (!selectedBackup || !confirm("用所选备份替换当前所有提示词？")) return;
+    try {
+      await invoke("restore_backup", { name: selectedBackup });
+      setLibraryError(null);
+      setBackups(await invoke<BackupInfo[]>("list_backups"));
+    } catch (err) {
+      console.error("Failed to restore backup:", err);
+      alert(`恢复备份失败: ${err}`);
+    }
+  };
+
+  // 从回收站恢复 / 永久删除
+  const restoreFromTrash = async (id: string) => {
+    try {
+      await invoke("restore_from_trash", { id });
+      setTrash(await invoke<TrashedPrompt[]>("list_trash"));
+    } catch (err) {
+      console.error("Failed to restore prompt:", err);
+      alert(`恢复提示词失败: ${err}`);
+    }
+  };
+
+  const purgeTrash = async (id?: string) => {
+    const message = id ? "永久删除此提示词？" : "清空回收站？此操作无法撤销";
+    if (!confirm(message)) return;
+    try {
+      await invoke("purge_trash", { id });
+      setTrash(await invoke<TrashedPrompt[]>("list_trash"));
+    } catch (err) {
+      console.error("Failed to purge trash:", err);
+      alert(`清理回收站失败: ${err}`);
+    }
+  };
+
+  // 导入 / 导出提示词包
+  const exportLibrary = async () => {
+    try {
+      const count = await invoke<number>("export_library", {
+        format: bundleFormat,
+        path: bundlePath,
+      });
+      alert(`已导出 ${count} 个提示词`);
+    } catch (err) {
+      console.error("Failed to export library:", err);
+      alert(`导出失败: ${err}`);
+    }
+  };
+
+  const previewImport = async () => {
+    try {
+      setImportPreview(
+        await invoke<ImportPreview>("preview_import", {
+          format: bundleFormat,
+          path: bundlePath,
+          strategy: mergeStrategy,
+        })
+      );
+    } catch (err) {
+      console.error("Failed to preview import:", err);
+      alert(`无法读取导入文件: ${err}`);
+    }
+  };
+
+  // 生成新的 HTTP API 令牌，旧令牌立即失效
+  const regenerateHttpApiToken = async () => {
+    if (!confirm("生成新令牌？使用旧令牌的工具将无法再访问")) return;
+    try {
+      setHttpApi(await invoke<HttpApiSettings>("regenerate_http_api_token"));
+    } catch (err) {
+      console.error("Failed to regenerate token:", err);
+      alert(`生成令牌失败: ${err}`);
+    }
+  };
+
+  // 发布为 Cursor 规则 / AGENTS.md / Copilot 指令
+  const exportAgentRules = async () => {
+    try {
+      const written = await invoke<string[]>("export_agent_rules", {
+        target: ruleTarget,
+        project: ruleProject,
+        ids: ruleSelection,
+        globs: ruleGlobs,
+        alwaysApply: ruleAlwaysApply,
+      });
+      alert(`已写入:\n${written.join("\n")}`);
+    } catch (err) {
+      console.error("Failed to export agent rules:", err);
+      alert(`发布失败: ${err}`);
+    }
+  };
+
+  const importLibrary = async () => {
+    try {
+      await invoke("import_library", {
+        format: bundleFormat,
+        path: bundlePath,
+        strategy: mergeStrategy,
+      });
+      setImportPreview(null);
+      setFolders(await invoke<FolderInfo[]>("list_folders"));
+    } catch (err) {
+      console.error("Failed to import library:", err);
+      alert(`导入失败: ${err}`);
+    }
+  };
+
+  const importActionLabels: Record<string, string> = {
+    add: "新增",
+    overwrite: "覆盖",
+    skip: "跳过",
+    keepBoth: "保留两者",
+  };
+
+  const splitList = (value: string) =>
+    value
+      .split(",")
+      .map((app) => app.trim())
+      .filter((app) => app !== "");
+
   const closeWindow = async () => {
     const win = getCurrentWindow();
     await win.close();
@@ -82,21 +460,634 @@ function SettingsPage() {
         <div className="settings-section">
           <label className="settings-label">
             显示/隐藏快捷键:
-            <select 
+            <input
               value={toggleShortcut}
               onChange={(e) => setToggleShortcut(e.target.value)}
+              list="shortcut-options"
               className="settings-select"
               data-tauri-drag-region="false"
-            >
+            />
+            <datalist id="shortcut-options">
               {shortcutOptions.map((option) => (
                 <option key={option.value} value={option.value}>
                   {option.label}
                 </option>
               ))}
+            </datalist>
+          </label>
+          <button
+            className="settings-close"
+            onClick={recordShortcut}
+            disabled={recording}
+            data-tauri-drag-region="false"
+          >
+            {recording ? "请按下组合键（Esc 取消）..." : "录制快捷键"}
+          </button>
+          {shortcutCheck?.error && (
+            <p className="settings-hint-small">{shortcutCheck.error}</p>
+          )}
+          {shortcutCheck?.notes.map((note) => (
+            <p key={note} className="settings-hint-small">
+              {note}
+            </p>
+          ))}
+          <p className="settings-hint-small">
+            选择、输入或录制用于显示/隐藏应用窗口的快捷键组合
+          </p>
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            快捷键模式:
+            <select
+              value={toggleMode}
+              onChange={(e) => setToggleMode(e.target.value)}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            >
+              <option value="toggle">按下切换</option>
+              <option value="hold">按住显示，松开隐藏</option>
             </select>
           </label>
           <p className="settings-hint-small">
-            选择用于显示/隐藏应用窗口的快捷键组合
+            按住模式下，松开快捷键时会注入当前高亮的提示词
+          </p>
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            <input
+              type="checkbox"
+              checked={shortcutsPaused}
+              onChange={(e) => setShortcutsPaused(e.target.checked)}
+              data-tauri-drag-region="false"
+            />
+            暂停所有全局快捷键
+          </label>
+          <label className="settings-label">
+            暂停/恢复快捷键:
+            <input
+              value={pauseShortcut}
+              onChange={(e) => setPauseShortcut(e.target.value)}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          <label className="settings-label">
+            在这些应用中自动暂停（逗号分隔）:
+            <input
+              value={shortcutBlocklist}
+              onChange={(e) => setShortcutBlocklist(e.target.value)}
+              className="settings-select"
+              placeholder="Steam, Microsoft Remote Desktop"
+              data-tauri-drag-region="false"
+            />
+          </label>
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            <input
+              type="checkbox"
+              checked={expansionEnabled}
+              onChange={(e) => setExpansionEnabled(e.target.checked)}
+              data-tauri-drag-region="false"
+            />
+            启用缩写展开（在任意应用中输入缩写，如 ;dbg）
+          </label>
+          <label className="settings-label">
+            不展开的应用（逗号分隔）:
+            <input
+              value={expansionBlocklist}
+              onChange={(e) => setExpansionBlocklist(e.target.value)}
+              className="settings-select"
+              placeholder="KeePassXC, Terminal"
+              data-tauri-drag-region="false"
+            />
+          </label>
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            禁止注入的应用（逗号分隔）:
+            <input
+              value={protectedApps}
+              onChange={(e) => setProtectedApps(e.target.value)}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          <p className="settings-hint-small">
+            密码输入框和安全输入模式下始终不会注入
+          </p>
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            无需确认的链接来源应用（逗号分隔）:
+            <input
+              value={linkTrustedApps}
+              onChange={(e) => setLinkTrustedApps(e.target.value)}
+              className="settings-select"
+              placeholder="Obsidian, Slack"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          <p className="settings-hint-small">
+            来自其他应用的 promptbuddy:// 链接在注入或添加提示词前需要确认
+          </p>
+          <p className="settings-hint-small">
+            浏览器不能设为可信来源：任何网页都能发出链接
+          </p>
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            注入前脚本:
+            <input
+              value={preInjectHook}
+              onChange={(e) => setPreInjectHook(e.target.value)}
+              className="settings-select"
+              placeholder="例如 ~/bin/redact.sh"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          <label className="settings-label">
+            注入后脚本:
+            <input
+              value={postInjectHook}
+              onChange={(e) => setPostInjectHook(e.target.value)}
+              className="settings-select"
+              placeholder="例如 ~/bin/audit.sh"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          <label className="settings-label">
+            脚本超时（秒）:
+            <input
+              type="number"
+              min={1}
+              max={300}
+              value={injectHookTimeout}
+              onChange={(e) => setInjectHookTimeout(Number(e.target.value))}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          <p className="settings-hint-small">
+            注入前脚本从标准输入读取文本，输出替换后的文本；以非零状态退出、超时或无法运行时取消注入
+          </p>
+          <p className="settings-hint-small">
+            注入后脚本从标准输入读取 JSON（提示词、目标应用、结果），在后台运行
+          </p>
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            允许的命令（逗号分隔）:
+            <input
+              value={shellCommands}
+              onChange={(e) => setShellCommands(e.target.value)}
+              className="settings-select"
+              placeholder="git, cargo, tail"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          <label className="settings-label">
+            命令工作目录:
+            <input
+              value={shellDirectory}
+              onChange={(e) => setShellDirectory(e.target.value)}
+              className="settings-select"
+              placeholder="留空则使用当前项目"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          <label className="settings-label">
+            命令超时（秒）:
+            <input
+              type="number"
+              min={1}
+              max={300}
+              value={shellTimeout}
+              onChange={(e) => setShellTimeout(Number(e.target.value))}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          <p className="settings-hint-small">
+            提示词中的 {"{{sh: git diff --staged}}"} 会替换为命令输出；需在提示词编辑器中为该提示词单独开启，且只能运行上面列出的程序
+          </p>
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            提示词存储:
+            <select
+              value={storageBackend}
+              onChange={(e) =>
+                setStorageBackend(e.target.value as StorageSettings["backend"])
+              }
+              className="settings-select"
+              data-tauri-drag-region="false"
+            >
+              <option value="sqlite">SQLite 数据库（支持全文搜索）</option>
+              <option value="json">JSON 文件（prompts.json）</option>
+              <option value="markdown">Markdown 文件目录</option>
+            </select>
+          </label>
+          {storageBackend === "markdown" && (
+            <label className="settings-label">
+              目录:
+              <input
+                value={markdownDirectory}
+                onChange={(e) => setMarkdownDirectory(e.target.value)}
+                className="settings-select"
+                data-tauri-drag-region="false"
+              />
+            </label>
+          )}
+          {storageBackend === "markdown" && (
+            <p className="settings-hint-small">
+              每个提示词保存为一个带 YAML 头信息的 .md 文件，目录中的修改会自动同步
+            </p>
+          )}
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            提示栏显示的文件夹:
+            <select
+              value={barFolder}
+              onChange={(e) => setBarFolder(e.target.value)}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            >
+              <option value="">全部</option>
+              {folders.map((folder) => (
+                <option key={folder.path} value={folder.path}>
+                  {folder.path}（{folder.promptCount} 个提示词）
+                </option>
+              ))}
+            </select>
+          </label>
+          <label className="settings-label">
+            提示栏标签筛选:
+            <input
+              value={barTagFilter}
+              onChange={(e) => setBarTagFilter(e.target.value)}
+              placeholder="例如 rust or go, !draft"
+              className="settings-select"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          <p className="settings-hint-small">
+            支持 and / or / not（或 &amp;&amp; / || / !）和括号；留空显示全部提示词
+          </p>
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            项目目录:
+            <input
+              value={projectDirectories}
+              onChange={(e) => setProjectDirectories(e.target.value)}
+              placeholder="例如 /Users/me/code/my-app, /Users/me/code/api"
+              className="settings-select"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          <p className="settings-hint-small">
+            {activeProject
+              ? `当前项目: ${activeProject.name}（${activeProject.promptCount} 个项目提示词）`
+              : "当前没有识别到项目"}
+          </p>
+          <p className="settings-hint-small">
+            在 Cursor / VS Code 中打开这些项目时，其 .promptbuddy/prompts/*.md 中的提示词会显示在提示栏最前面
+          </p>
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            <input
+              type="checkbox"
+              checked={httpApiEnabled}
+              onChange={(e) => setHttpApiEnabled(e.target.checked)}
+              data-tauri-drag-region="false"
+            />
+            启用本地 HTTP API（仅 127.0.0.1）
+          </label>
+          <label className="settings-label">
+            端口:
+            <input
+              type="number"
+              min={1}
+              max={65535}
+              value={httpApiPort}
+              onChange={(e) => setHttpApiPort(Number(e.target.value))}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          <label className="settings-label">
+            允许的网页来源（逗号分隔）:
+            <input
+              value={httpApiOrigins}
+              onChange={(e) => setHttpApiOrigins(e.target.value)}
+              placeholder="例如 https://wiki.example.com"
+              className="settings-select"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          {httpApi?.token && (
+            <label className="settings-label">
+              访问令牌:
+              <input
+                value={httpApi.token}
+                readOnly
+                className="settings-select"
+                data-tauri-drag-region="false"
+              />
+              <button
+                onClick={regenerateHttpApiToken}
+                className="settings-close"
+                data-tauri-drag-region="false"
+              >
+                重新生成
+              </button>
+            </label>
+          )}
+          <p className="settings-hint-small">
+            {httpApi?.url
+              ? `运行中: ${httpApi.url}（接口说明见 ${httpApi.url}/openapi.json）`
+              : "未运行"}
+          </p>
+          <p className="settings-hint-small">
+            请求需带 Authorization: Bearer 令牌；其他网页来源的请求一律拒绝
+          </p>
+        </div>
+
+        <div className="settings-section">
+          {libraryError && (
+            <p className="settings-hint-small">
+              提示词库无法读取: {libraryError}
+            </p>
+          )}
+          <label className="settings-label">
+            备份:
+            <select
+              value={selectedBackup}
+              onChange={(e) => setSelectedBackup(e.target.value)}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            >
+              {backups.length === 0 && <option value="">暂无备份</option>}
+              {backups.map((backup) => (
+                <option key={backup.name} value={backup.name}>
+                  {new Date(backup.createdAt * 1000).toLocaleString()}（
+                  {backup.promptCount} 个提示词）
+                </option>
+              ))}
+            </select>
+          </label>
+          <button
+            onClick={restoreBackup}
+            className="settings-close"
+            disabled={!selectedBackup}
+            data-tauri-drag-region="false"
+          >
+            恢复此备份
+          </button>
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            导入/导出格式:
+            <select
+              value={bundleFormat}
+              onChange={(e) => {
+                setBundleFormat(e.target.value as typeof bundleFormat);
+                setImportPreview(null);
+              }}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            >
+              <option value="json">JSON 提示词包</option>
+              <option value="markdown">Markdown 文件目录</option>
+              <option value="csv">CSV 表格</option>
+              <option value="espanso">Espanso 匹配文件 (.yml，仅导入)</option>
+              <option value="alfred">Alfred 片段集 (.alfredsnippets，仅导入)</option>
+              <option value="raycast">Raycast 片段 (.json，仅导入)</option>
+              <option value="vscode">VS Code 片段 (.code-snippets，仅导入)</option>
+            </select>
+          </label>
+          <label className="settings-label">
+            {bundleFormat === "markdown" ? "目录:" : "文件路径:"}
+            <input
+              value={bundlePath}
+              onChange={(e) => {
+                setBundlePath(e.target.value);
+                setImportPreview(null);
+              }}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          <label className="settings-label">
+            同 ID 或同名时:
+            <select
+              value={mergeStrategy}
+              onChange={(e) => {
+                setMergeStrategy(e.target.value as typeof mergeStrategy);
+                setImportPreview(null);
+              }}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            >
+              <option value="skip">跳过导入的提示词</option>
+              <option value="overwrite">覆盖现有提示词</option>
+              <option value="keepBoth">保留两者</option>
+            </select>
+          </label>
+          <button
+            onClick={exportLibrary}
+            className="settings-close"
+            disabled={!bundlePath || IMPORT_ONLY_FORMATS.includes(bundleFormat)}
+            data-tauri-drag-region="false"
+          >
+            导出
+          </button>
+          <button
+            onClick={previewImport}
+            className="settings-close"
+            disabled={!bundlePath}
+            data-tauri-drag-region="false"
+          >
+            预览导入
+          </button>
+          {importPreview && (
+            <>
+              <p className="settings-hint-small">
+                新增 {importPreview.added}，覆盖 {importPreview.overwritten}，跳过{" "}
+                {importPreview.skipped}，保留两者 {importPreview.keptBoth}
+              </p>
+              {importPreview.changes.map((change, i) => (
+                <p key={i} className="settings-hint-small">
+                  {importActionLabels[change.action] ?? change.action}: {change.title}
+                  {change.existingTitle && change.existingTitle !== change.title
+                    ? `（现有: ${change.existingTitle}）`
+                    : ""}
+                </p>
+              ))}
+              {importPreview.issues.length > 0 && (
+                <>
+                  <p className="settings-hint-small">
+                    {importPreview.issues.length} 处无法完整转换:
+                  </p>
+                  {importPreview.issues.map((issue, i) => (
+                    <p key={i} className="settings-hint-small">
+                      ⚠️ {issue.item}: {issue.message}
+                    </p>
+                  ))}
+                </>
+              )}
+              <button
+                onClick={importLibrary}
+                className="settings-save"
+                data-tauri-drag-region="false"
+              >
+                确认导入
+              </button>
+            </>
+          )}
+          <p className="settings-hint-small">
+            Markdown 导出需要一个新的或空的目录；导入可通过 Ctrl/Cmd+Z 撤销
+          </p>
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            发布到项目:
+            <select
+              value={ruleTarget}
+              onChange={(e) => setRuleTarget(e.target.value as typeof ruleTarget)}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            >
+              <option value="cursor">Cursor 规则 (.cursor/rules/*.mdc)</option>
+              <option value="agents">AGENTS.md</option>
+              <option value="copilot">Copilot 指令 (.github/copilot-instructions.md)</option>
+            </select>
+          </label>
+          <label className="settings-label">
+            项目目录:
+            <input
+              value={ruleProject}
+              onChange={(e) => setRuleProject(e.target.value)}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          {ruleTarget === "cursor" && (
+            <>
+              <label className="settings-label">
+                Globs:
+                <input
+                  value={ruleGlobs}
+                  onChange={(e) => setRuleGlobs(e.target.value)}
+                  placeholder="src/**/*.ts, *.tsx"
+                  className="settings-select"
+                  data-tauri-drag-region="false"
+                />
+              </label>
+              <label className="settings-label">
+                <input
+                  type="checkbox"
+                  checked={ruleAlwaysApply}
+                  onChange={(e) => setRuleAlwaysApply(e.target.checked)}
+                  data-tauri-drag-region="false"
+                />
+                始终应用 (alwaysApply)
+              </label>
+            </>
+          )}
+          {rulePrompts.map((prompt) => (
+            <label key={prompt.id} className="settings-label">
+              <input
+                type="checkbox"
+                checked={ruleSelection.includes(prompt.id)}
+                onChange={(e) =>
+                  setRuleSelection(
+                    e.target.checked
+                      ? [...ruleSelection, prompt.id]
+                      : ruleSelection.filter((id) => id !== prompt.id)
+                  )
+                }
+                data-tauri-drag-region="false"
+              />
+              {prompt.title}
+            </label>
+          ))}
+          <button
+            onClick={exportAgentRules}
+            className="settings-close"
+            disabled={!ruleProject || ruleSelection.length === 0}
+            data-tauri-drag-region="false"
+          >
+            发布
+          </button>
+          <p className="settings-hint-small">
+            重复发布会替换之前生成的内容，不会重复追加；标记之外的内容保持不变
+          </p>
+        </div>
+
+        <div className="settings-section">
+          <label className="settings-label">
+            回收站保留天数:
+            <input
+              type="number"
+              min={1}
+              value={trashRetentionDays}
+              onChange={(e) => setTrashRetentionDays(Math.max(1, Number(e.target.value)))}
+              className="settings-select"
+              data-tauri-drag-region="false"
+            />
+          </label>
+          {trash.length === 0 ? (
+            <p className="settings-hint-small">回收站为空</p>
+          ) : (
+            <>
+              {trash.map((item) => (
+                <div key={item.prompt.id} className="settings-hint-small">
+                  {item.prompt.title}（
+                  {new Date(item.deletedAt * 1000).toLocaleString()} 删除）
+                  <button
+                    onClick={() => restoreFromTrash(item.prompt.id)}
+                    className="settings-close"
+                    data-tauri-drag-region="false"
+                  >
+                    恢复
+                  </button>
+                  <button
+                    onClick={() => purgeTrash(item.prompt.id)}
+                    className="settings-close"
+                    data-tauri-drag-region="false"
+                  >
+                    永久删除
+                  </button>
+                </div>
+              ))}
+              <button
+                onClick={() => purgeTrash()}
+                className="settings-close"
+                data-tauri-drag-region="false"
+              >
+                清空回收站
+              </button>
+            </>
+          )}
+          <p className="settings-hint-small">
+            在提示栏或编辑窗口中按 Ctrl/Cmd+Z 撤销、Ctrl/Cmd+Shift+Z 重做
           </p>
         </div>
 
@@ -109,7 +1100,7 @@ function SettingsPage() {
           <button
             className="settings-save"
             onClick={saveSettings}
-            disabled={saving}
+            disabled={saving || shortcutCheck?.valid === false}
             data-tauri-drag-region="false"
           >
             {saving ? "保存中..." : "保存设置"}
@@ -3,6 +3,7 @@ import ReactDOM from "react-dom/client";
 import App from "./App";
 import SettingsPage from "./SettingsPage";
 import PromptEditor from "./PromptEditor";
+import LinkConfirm from "./LinkConfirm";
 
 const params = new URLSearchParams(window.location.search);
 let Component: React.ComponentType = App;
@@ -10,6 +11,8 @@ if (params.has("settings")) {
   Component = SettingsPage;
 } else if (params.has("edit")) {
   Component = PromptEditor;
+} else if (params.has("deep-link")) {
+  Component = LinkConfirm;
 }
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(